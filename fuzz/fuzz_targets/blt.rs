@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rankit::blt;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    if let Ok(election) = blt::read(text) {
+        // writing it back out and reading that shouldn't panic either
+        let written = blt::write(&election.ballot, election.seats, &election.title);
+        let _ = blt::read(&written);
+        let _ = election.ballot.runoff().count();
+    }
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rankit::{cvr, Ballot};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok((labels, rankings)) = cvr::read_ess(data, 0, None, Default::default()) {
+        let ballot = Ballot::from_rankings(labels, &rankings).expect("read rankings make valid ballots");
+        let _ = ballot.runoff().count();
+    }
+});
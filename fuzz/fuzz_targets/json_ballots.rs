@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rankit::json_ballots;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(ballot) = json_ballots::read(data) {
+        let _ = ballot.runoff().count();
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rankit::preflib;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(ballot) = preflib::read(data) {
+        let _ = ballot.runoff().count();
+    }
+});
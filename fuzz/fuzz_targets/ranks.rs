@@ -0,0 +1,30 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rankit::{inspect, ranks, Ballot};
+
+// goes through the same steps as reading a CSV file of ranks from stdin
+fuzz_target!(|data: &[u8]| {
+    let mut csv_reader = csv::Reader::from_reader(data);
+    let Ok(headers) = csv_reader.headers().cloned() else {
+        return;
+    };
+    let Ok(records) = csv_reader.records().collect::<Result<Vec<_>, _>>() else {
+        return;
+    };
+    let labels: Vec<_> = headers.iter().map(String::from).collect();
+
+    let columns = inspect::summarize(&headers, &records);
+    let _ = inspect::guess_ranks(&columns);
+
+    if inspect::check_ranks(&columns, 0, labels.len(), 1).is_err() {
+        return;
+    }
+
+    let _ = inspect::suspicious_columns(&headers, &records, 0, labels.len());
+
+    if let Ok(ranks) = ranks::parse(&records, 0, None, 1, labels.len()) {
+        let ballot = Ballot::new(labels, ranks).expect("checked ranks make valid ballots");
+        let _ = ballot.runoff().count();
+    }
+});
@@ -0,0 +1,91 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{Ballot, UNRANKED};
+
+/// Which of the candidates a ballot ranked it approves of.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Cutoff {
+    /// Every candidate it ranked, so approvals read as ballots ranking every
+    /// approved candidate equally first are counted as they are
+    #[default]
+    Ranked,
+    /// Every candidate it ranked this high or higher, from 1 for first
+    /// preferences only
+    Top(usize),
+    /// Every candidate it ranked at or above the average rank of the
+    /// candidates it ranked, so a ballot ranking 4 approves of its top 2
+    AboveAverage,
+}
+
+/// Counts how many ballots approve of each candidate, most first.
+/// Candidates with the same approvals stay in the order they were read.
+pub fn approvals<T: Clone>(ballot: &Ballot<T>, cutoff: Cutoff) -> Vec<(T, f64)> {
+    let mut approvals = vec![0.0; ballot.count()];
+
+    for (row, weight) in ballot.ballots().zip(ballot.weights()) {
+        let ranked: Vec<_> = row.iter().copied().filter(|rank| *rank != UNRANKED).collect();
+        let lowest_approved = match cutoff {
+            Cutoff::Ranked => UNRANKED - 1,
+            Cutoff::Top(top) => top.saturating_sub(1),
+            // ranks are whole numbers, so at or above the average is at or
+            // above the average rounded down
+            Cutoff::AboveAverage => ranked.iter().sum::<usize>().checked_div(ranked.len()).unwrap_or(0),
+        };
+
+        for (candidate, rank) in row.iter().enumerate() {
+            if *rank != UNRANKED && *rank <= lowest_approved {
+                approvals[candidate] += weight;
+            }
+        }
+    }
+
+    let mut approvals: Vec<_> = ballot.labels().iter().cloned().zip(approvals).collect();
+    approvals.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    approvals
+}
+
+/// Whether an option approved on `approved` of `total` ballots passes a
+/// threshold election, by being approved on more than `threshold` percent of
+/// them.
+pub fn passes(approved: f64, total: f64, threshold: f64) -> bool {
+    // comparing shares would make 3 of 5 come out a hair above 60%
+    approved * 100.0 > threshold * total
+}
+
+#[cfg(test)]
+mod test {
+    use super::Cutoff;
+    use crate::Ballot;
+
+    #[test]
+    fn approves_up_to_a_rank() {
+        let rankings = [vec![0, 1, 2], vec![1, 2], vec![2, 1, 0]];
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c'], &rankings).expect("valid rankings");
+
+        assert_eq!(super::approvals(&ballot, Cutoff::Top(2)), vec![('b', 3.0), ('c', 2.0), ('a', 1.0)]);
+        assert_eq!(super::approvals(&ballot, Cutoff::Ranked), vec![('b', 3.0), ('c', 3.0), ('a', 2.0)]);
+        // ranking 3 approves of the top 2, but ranking 2 only of the first
+        assert_eq!(super::approvals(&ballot, Cutoff::AboveAverage), vec![('b', 3.0), ('a', 1.0), ('c', 1.0)]);
+    }
+
+    #[test]
+    fn threshold_election() {
+        let rankings = [vec![0, 1], vec![0, 1], vec![0], vec![2], vec![]];
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c'], &rankings).expect("valid rankings");
+        let total = ballot.ballot_count() as f64;
+        let passed: Vec<_> = super::approvals(&ballot, Cutoff::Ranked)
+            .into_iter()
+            .filter(|(_, approved)| super::passes(*approved, total, 50.0))
+            .collect();
+
+        // 3 of 5 is over half, and the blank ballot still counts towards the total
+        assert_eq!(passed, vec![('a', 3.0)]);
+
+        // exactly the threshold isn't more than it
+        assert!(!super::passes(3.0, 5.0, 60.0));
+        assert!(super::passes(3.0, 5.0, 59.9));
+        assert!(!super::passes(0.0, 5.0, 0.0));
+    }
+}
@@ -0,0 +1,98 @@
+use alloc::format;
+use alloc::{vec, vec::Vec};
+use core::fmt::Display;
+
+use crate::rng::Rng;
+use crate::Ballot;
+
+/// A group of ballots that an audit sample was drawn from.
+pub struct Stratum<'a, T> {
+    /// The first preference shared by every ballot in the group, or `None`
+    /// if the sample was drawn from all of the ballots at once
+    pub first_preference: Option<&'a T>,
+    /// How many ballots were in the group
+    pub total: usize,
+    /// The records (indexed at 0, like errors are) that were drawn
+    pub records: Vec<usize>,
+}
+
+/// Draws `size` ballots for a manual audit, either from all of the ballots or
+/// `size` from each group of ballots sharing a first preference. The draws
+/// for each group are logged under its first preference.
+pub fn sample<'a, T: Clone + Display>(
+    ballot: &'a Ballot<T>,
+    size: usize,
+    stratify: bool,
+    rng: &mut Rng,
+) -> Vec<Stratum<'a, T>> {
+    if !stratify {
+        let pool: Vec<_> = (0..ballot.ballot_count()).collect();
+        rng.set_context("all ballots");
+
+        return vec![Stratum {
+            first_preference: None,
+            total: pool.len(),
+            records: rng.sample(&pool, size),
+        }];
+    }
+
+    let mut pools = vec![vec![]; ballot.count()];
+
+    for (i, row) in ballot.ballots().enumerate() {
+        if let Some(first) = Ballot::<T>::first_preference(&row) {
+            pools[first].push(i);
+        }
+    }
+
+    // candidates are always drawn for in label order, so that the draw can be
+    // reproduced from the seed alone
+    pools
+        .into_iter()
+        .enumerate()
+        .filter(|(_, pool)| !pool.is_empty())
+        .map(|(candidate, pool)| {
+            let first_preference = &ballot.labels()[candidate];
+            rng.set_context(format!("first preference {first_preference}"));
+
+            Stratum {
+                first_preference: Some(first_preference),
+                total: pool.len(),
+                records: rng.sample(&pool, size),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rng::Rng;
+    use crate::Ballot;
+
+    #[test]
+    fn stratified_sample() {
+        let labels = vec!['a', 'b'];
+        let values: Vec<_> = vec![[0, 1], [1, 0], [0, 1], [0, 1], [1, 0]]
+            .into_iter()
+            .flatten()
+            .collect();
+        let ballot = Ballot::new(labels, values).expect("label/values mismatch");
+
+        let mut rng = Rng::new(7);
+        let strata = super::sample(&ballot, 2, true, &mut rng);
+        let again = super::sample(&ballot, 2, true, &mut Rng::new(7));
+
+        assert_eq!(strata.len(), 2);
+        assert_eq!((strata[0].first_preference, strata[0].total), (Some(&'a'), 3));
+        assert_eq!((strata[1].first_preference, strata[1].total), (Some(&'b'), 2));
+        assert!(strata[0].records.iter().all(|r| [0, 2, 3].contains(r)));
+        assert!(strata[1].records.iter().all(|r| [1, 4].contains(r)));
+
+        // drawing 2 of 3, then 2 of 2
+        let bounds: Vec<_> = rng.draws().iter().map(|draw| (draw.context.as_str(), draw.bound)).collect();
+        assert_eq!(bounds, [("first preference a", 3), ("first preference a", 2), ("first preference b", 2), ("first preference b", 1)]);
+
+        for (a, b) in strata.iter().zip(&again) {
+            assert_eq!(a.records, b.records);
+        }
+    }
+}
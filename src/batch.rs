@@ -0,0 +1,62 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::Ballot;
+
+/// The subtotal of one file of ballots that was combined into the count.
+pub struct Batch {
+    pub name: String,
+    pub ballots: usize,
+    pub first_preferences: Vec<(String, f64)>,
+}
+
+impl Batch {
+    pub fn new(name: String, votes: &Ballot<String>) -> Self {
+        let first_preferences = votes.labels().iter().cloned().zip(votes.tier()).collect();
+
+        Self {
+            name,
+            ballots: votes.ballot_count(),
+            first_preferences,
+        }
+    }
+}
+
+/// Reads a manifest listing one ballot file per line. Paths are relative to
+/// the manifest, and blank lines and lines starting with # are skipped.
+pub fn read_manifest(path: &Path) -> Result<Vec<PathBuf>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("couldn't read manifest {}", path.display()))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| dir.join(line))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reads_manifest() {
+        let temp = tempfile::tempdir().expect("temp dir is writable");
+        let dir = temp.path();
+        let path = dir.join("manifest.txt");
+        std::fs::write(&path, "# precincts\nnorth.csv\n\n  south/ward 2.csv  \n").expect("temp dir is writable");
+
+        let paths = read_manifest(&path).expect("manifest is readable");
+        assert_eq!(paths, vec![dir.join("north.csv"), dir.join("south/ward 2.csv")]);
+
+        // the subtotals are each batch's own first preferences
+        let votes = Ballot::from_rankings(vec!["a".to_string(), "b".to_string()], &[vec![0, 1], vec![1, 0], vec![0, 1]]);
+        let batch = Batch::new("north.csv".to_string(), &votes.expect("valid rankings"));
+        assert_eq!(batch.ballots, 3);
+        assert_eq!(batch.first_preferences, vec![("a".to_string(), 2.0), ("b".to_string(), 1.0)]);
+
+        assert!(read_manifest(&dir.join("missing.txt")).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::{format, vec, vec::Vec};
+
+use crate::{Ballot, UNRANKED};
+use core::fmt::Write;
+
+/// Writes ballots in the BLT format used by OpaVote and OpenSTV, so a count
+/// can be cross-checked by uploading the same ballots there. Identical
+/// rankings are combined into one line weighted by how many voters cast them.
+pub fn write<T: Clone + core::fmt::Display>(ballot: &Ballot<T>, seats: usize, title: &str) -> String {
+    let count = ballot.count();
+    let mut patterns: Vec<(Vec<Vec<usize>>, f64)> = vec![];
+
+    for (row, weight) in ballot.ballots().zip(ballot.weights()) {
+        // candidates grouped by rank, so equal ranks can be written with "="
+        let mut grouped: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+
+        for (candidate, rank) in row.iter().enumerate().filter(|(_, r)| **r != UNRANKED) {
+            grouped.entry(*rank).or_default().push(candidate);
+        }
+
+        let ranking: Vec<_> = grouped.into_values().collect();
+
+        match patterns.iter_mut().find(|(pattern, _)| *pattern == ranking) {
+            Some((_, total)) => *total += weight,
+            None => patterns.push((ranking, *weight)),
+        }
+    }
+
+    let mut blt = String::new();
+
+    // writing to a string can't fail
+    let _ = writeln!(blt, "{count} {seats}");
+
+    for (ranking, weight) in patterns {
+        let preferences: Vec<_> = ranking
+            .iter()
+            .map(|tied| {
+                let tied: Vec<_> = tied.iter().map(|c| (c + 1).to_string()).collect();
+                tied.join("=")
+            })
+            .collect();
+
+        let _ = writeln!(blt, "{} {} 0", crate::format_votes(weight), preferences.join(" "));
+    }
+
+    let _ = writeln!(blt, "0");
+
+    for label in ballot.labels() {
+        let _ = writeln!(blt, "{}", quote(&label.to_string()));
+    }
+
+    let _ = writeln!(blt, "{}", quote(title));
+
+    blt
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "'"))
+}
+
+/// What a BLT file holds.
+pub struct Election {
+    /// The ballots, without the withdrawn candidates
+    pub ballot: Ballot<String>,
+    pub seats: usize,
+    /// The candidates marked as withdrawn, who were taken out before the
+    /// count so that their ballots go to their next preferences
+    pub withdrawn: Vec<String>,
+    pub title: String,
+}
+
+/// Reads a BLT file: the number of candidates and seats, then any withdrawn
+/// candidates as negative numbers, then a line per ballot (its weight, the
+/// candidates it ranked from 1 with "=" between equal ones, and a 0), then a
+/// 0, the candidates' names in quotes, and the election's title. Ballots can
+/// start with an ID in brackets, and a "-" for a skipped rank is ignored.
+pub fn read(text: &str) -> Result<Election, String> {
+    let tokens = tokenize(text);
+    let mut tokens = tokens.iter().peekable();
+    let mut number = |what: &str| -> Result<usize, String> {
+        match tokens.next() {
+            Some(Token::Word(word)) => word.parse().map_err(|_| format!("expected the {what}, found {word:?}")),
+            _ => Err(format!("expected the {what}")),
+        }
+    };
+
+    let count = number("number of candidates")?;
+    let seats = number("number of seats")?;
+    let mut withdrawn = vec![];
+    let mut rankings = vec![];
+    let mut weights = vec![];
+
+    let candidate = |word: &str| -> Result<usize, String> {
+        match word.parse::<usize>() {
+            Ok(candidate) if (1..=count).contains(&candidate) => Ok(candidate - 1),
+            _ => Err(format!("{word:?} isn't one of the {count} candidates")),
+        }
+    };
+
+    while let Some(Token::Word(word)) = tokens.peek() {
+        let Some(negative) = word.strip_prefix('-') else {
+            break;
+        };
+
+        withdrawn.push(candidate(negative)?);
+        tokens.next();
+    }
+
+    loop {
+        let weight = match tokens.next() {
+            Some(Token::Word(word)) if word.starts_with('(') => continue,
+            Some(Token::Word(word)) if word == "0" => break,
+            Some(Token::Word(word)) => word
+                .parse::<f64>()
+                .ok()
+                .filter(|weight| weight.is_finite() && *weight >= 0.0)
+                .ok_or_else(|| format!("invalid ballot weight {word:?}"))?,
+            _ => return Err("the ballots don't end with a 0".to_owned()),
+        };
+        let mut ranking = vec![];
+
+        loop {
+            match tokens.next() {
+                Some(Token::Word(word)) if word == "0" => break,
+                Some(Token::Word(word)) if word == "-" => continue,
+                Some(Token::Word(word)) => ranking.push(word.split('=').map(candidate).collect::<Result<Vec<_>, _>>()?),
+                _ => return Err("a ballot doesn't end with a 0".to_owned()),
+            }
+        }
+
+        rankings.push(ranking);
+        weights.push(weight);
+    }
+
+    let mut labels = vec![];
+
+    for i in 0..count {
+        match tokens.next() {
+            Some(Token::Quoted(name)) => labels.push(name.clone()),
+            _ => return Err(format!("expected a name for candidate {}", i + 1)),
+        }
+    }
+
+    let title = match tokens.next() {
+        Some(Token::Quoted(title)) => title.clone(),
+        _ => String::new(),
+    };
+
+    let mut ballot = Ballot::from_tied_rankings(labels, &rankings).map_err(|_| "a ballot ranks a candidate twice".to_owned())?;
+    ballot = ballot.with_weights(weights).expect("a weight for every ballot");
+
+    // taken out from the last, so the indices of the rest don't move
+    withdrawn.sort_unstable();
+    withdrawn.dedup();
+    let mut withdrawn: Vec<_> = withdrawn.into_iter().rev().map(|candidate| ballot.take(candidate)).collect();
+    withdrawn.reverse();
+
+    Ok(Election {
+        ballot,
+        seats,
+        withdrawn,
+        title,
+    })
+}
+
+enum Token {
+    Word(String),
+    Quoted(String),
+}
+
+/// Splits a BLT file into words and quoted names, wherever the line breaks
+/// are.
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '"' => tokens.push(Token::Quoted(chars.by_ref().take_while(|c| *c != '"').collect())),
+            c => {
+                let mut word = String::from(c);
+
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                    word.push(c);
+                }
+
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Ballot;
+
+    #[test]
+    fn reads_what_it_writes() {
+        let rankings = [vec![vec![0], vec![1, 2]], vec![vec![2]], vec![vec![0], vec![1, 2]]];
+        let ballot = Ballot::from_tied_rankings(vec!["A", "B", "C"], &rankings).expect("valid rankings");
+        let election = super::read(&super::write(&ballot, 2, "Club")).expect("valid blt");
+
+        assert_eq!((election.seats, election.title.as_str()), (2, "Club"));
+        assert_eq!(election.ballot.labels(), ["A", "B", "C"]);
+        assert_eq!(election.ballot.weights(), [2.0, 1.0]);
+        assert_eq!(election.ballot.tier(), vec![2.0, 0.0, 1.0]);
+
+        // A withdrew, so their ballots go to B and C
+        let election = super::read("3 1\n-1\n(x) 2 1 2=3 0\n1 3 0\n0\n\"A\" \"B\" \"C\"").expect("valid blt");
+        assert_eq!(election.withdrawn, ["A"]);
+        assert_eq!(election.ballot.tier(), vec![2.0, 3.0]);
+    }
+}
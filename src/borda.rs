@@ -0,0 +1,153 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{Ballot, UNRANKED};
+
+/// How many points each rank is worth.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Scoring {
+    /// With n candidates, first gets n - 1 points, second n - 2 and so on
+    #[default]
+    Standard,
+    /// The Dowdall (Nauru) variant: first gets 1 point, second 1/2, third
+    /// 1/3 and so on, so it leans more on first preferences
+    Dowdall,
+}
+
+/// Scores every candidate by where ballots ranked them, most points first.
+/// Unranked candidates get nothing from a ballot, and candidates ranked
+/// equally each get the points for that rank. Candidates with the same score
+/// stay in the order they were read.
+pub fn scores<T: Clone>(ballot: &Ballot<T>, scoring: Scoring) -> Vec<(T, f64)> {
+    let mut scores: Vec<_> = ballot.labels().iter().cloned().zip(points(ballot, scoring)).collect();
+    scores.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    scores
+}
+
+/// Every candidate's points, in the same order as the labels.
+fn points<T: Clone>(ballot: &Ballot<T>, scoring: Scoring) -> Vec<f64> {
+    let count = ballot.count();
+    let mut points = vec![0.0; count];
+
+    for (row, weight) in ballot.ballots().zip(ballot.weights()) {
+        for (candidate, rank) in row.iter().copied().enumerate().filter(|(_, rank)| *rank != UNRANKED) {
+            let score = match scoring {
+                Scoring::Standard => count.saturating_sub(rank + 1) as f64,
+                Scoring::Dowdall => 1.0 / (rank + 1) as f64,
+            };
+
+            points[candidate] += score * weight;
+        }
+    }
+
+    points
+}
+
+/// Which candidates are eliminated each round of a Borda elimination count.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Elimination {
+    /// The one with the lowest score, as in Baldwin's method
+    Lowest,
+    /// Every one scoring below the average, as in Nanson's method
+    BelowAverage,
+}
+
+/// One round of a Borda elimination count.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Round<T> {
+    /// Every candidate still in the count with their Borda score among just
+    /// them, most first
+    pub scores: Vec<(T, f64)>,
+    /// Who was eliminated at the end of the round, unless the count ended
+    pub eliminated: Vec<T>,
+    /// Whether more than one candidate had the lowest score under
+    /// `Elimination::Lowest`, in which case the first of them in the input
+    /// was eliminated
+    pub tied: bool,
+}
+
+/// Counts like instant-runoff, but eliminating by Borda score instead of
+/// first preferences, with the scores counted again among the candidates
+/// left each round, until one is left. If every candidate left has the same
+/// score, the first of them in the input wins. Returns every round, and the
+/// winner unless there were no candidates.
+pub fn eliminate<T: Clone>(ballot: &Ballot<T>, elimination: Elimination) -> (Vec<Round<T>>, Option<T>) {
+    let mut current = ballot.clone();
+    let mut rounds = vec![];
+
+    loop {
+        let points = points(&current, Scoring::Standard);
+        let mut scores: Vec<_> = current.labels().iter().cloned().zip(points.iter().copied()).collect();
+        scores.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        let lowest = (0..points.len()).min_by(|a, b| points[*a].total_cmp(&points[*b]));
+        let everyone_equal = points.iter().all(|score| Some(*score) == lowest.map(|lowest| points[lowest]));
+
+        let Some(lowest) = lowest else {
+            return (rounds, None);
+        };
+
+        if points.len() == 1 || everyone_equal {
+            rounds.push(Round {
+                scores,
+                eliminated: vec![],
+                tied: false,
+            });
+
+            return (rounds, current.labels().first().cloned());
+        }
+
+        let mut out = match elimination {
+            Elimination::Lowest => vec![lowest],
+            Elimination::BelowAverage => {
+                let average = points.iter().sum::<f64>() / points.len() as f64;
+
+                (0..points.len()).filter(|i| points[*i] < average).collect()
+            }
+        };
+        let tied = elimination == Elimination::Lowest && points.iter().filter(|score| **score == points[lowest]).count() > 1;
+
+        // taking the later candidates first keeps the earlier indices right
+        out.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut eliminated: Vec<_> = out.into_iter().map(|i| current.take(i)).collect();
+        eliminated.reverse();
+
+        rounds.push(Round { scores, eliminated, tied });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Elimination, Scoring};
+    use crate::Ballot;
+
+    #[test]
+    fn standard_and_dowdall() {
+        let rankings = [vec![0, 1, 2], vec![0, 1, 2], vec![1, 2, 0], vec![2]];
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c'], &rankings).expect("valid rankings");
+
+        assert_eq!(super::scores(&ballot, Scoring::Standard), vec![('a', 4.0), ('b', 4.0), ('c', 3.0)]);
+        assert_eq!(super::scores(&ballot, Scoring::Dowdall)[0], ('a', 2.0 + 1.0 / 3.0));
+    }
+
+    #[test]
+    fn baldwin_and_nanson() {
+        let mut rankings = vec![vec![0, 1, 2, 3]; 3];
+        rankings.extend(vec![vec![1, 2, 0, 3]; 2]);
+        rankings.extend(vec![vec![2, 1, 3, 0]; 2]);
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c', 'd'], &rankings).expect("valid rankings");
+
+        // scores start a 11, b 16, c 13, d 2
+        let (rounds, winner) = super::eliminate(&ballot, Elimination::Lowest);
+        assert_eq!(rounds[0].eliminated, vec!['d']);
+        assert_eq!(winner, Some('b'));
+
+        // the average is 10.5, so only d goes, then a and c both score 6
+        // against b's 9
+        let (rounds, winner) = super::eliminate(&ballot, Elimination::BelowAverage);
+        assert_eq!(rounds[1].eliminated, vec!['a', 'c']);
+        assert_eq!(winner, Some('b'));
+    }
+}
@@ -0,0 +1,80 @@
+use alloc::{vec, vec::Vec};
+
+use crate::rounding::Rounding;
+use crate::{Ballot, UNRANKED};
+
+/// One round of a Bucklin count, which looks one rank further down every
+/// ballot than the round before.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Round<T> {
+    /// How far down the ballots this round looks, from 1 for first
+    /// preferences only
+    pub depth: usize,
+    /// Every candidate with the votes of every ballot ranking them at this
+    /// depth or above, most first
+    pub tally: Vec<(T, f64)>,
+}
+
+/// Counts by the Bucklin rule: first preferences are counted, and if nobody
+/// has a majority of the ballots, second preferences are added to them, then
+/// third and so on until someone does. If more than one candidate has a
+/// majority at the same depth, the one with the most votes wins, and if
+/// nobody ever has one, whoever has the most once every rank is counted
+/// wins. Candidates with the same votes stay in the order they were read.
+/// Returns every round, and the winner unless there were no candidates.
+pub fn count<T: Clone>(ballot: &Ballot<T>, rounding: Rounding) -> (Vec<Round<T>>, Option<T>) {
+    let total: f64 = ballot.weights().iter().sum();
+    let deepest = ballot
+        .ballots()
+        .flat_map(|row| row.iter().copied().filter(|rank| *rank != UNRANKED).max())
+        .max()
+        .unwrap_or(0);
+
+    let mut votes = vec![0.0; ballot.count()];
+    let mut rounds = vec![];
+
+    for depth in 0..=deepest {
+        for (row, weight) in ballot.ballots().zip(ballot.weights()) {
+            for (candidate, _) in row.iter().enumerate().filter(|(_, rank)| **rank == depth) {
+                votes[candidate] += weight;
+            }
+        }
+
+        let mut tally: Vec<_> = ballot
+            .labels()
+            .iter()
+            .cloned()
+            .zip(votes.iter().map(|votes| rounding.apply(*votes)))
+            .collect();
+        tally.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        let majority = tally.first().is_some_and(|(_, votes)| *votes * 2.0 > total);
+
+        rounds.push(Round { depth: depth + 1, tally });
+
+        if majority {
+            break;
+        }
+    }
+
+    let winner = rounds.last().and_then(|round| round.tally.first()).map(|(label, _)| label.clone());
+
+    (rounds, winner)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Ballot;
+
+    #[test]
+    fn adds_lower_preferences() {
+        // a leads on first preferences, but b is nearly everyone's second
+        let rankings = [vec![0, 1], vec![0, 1], vec![2, 1], vec![2, 1], vec![1, 0]];
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c'], &rankings).expect("valid rankings");
+        let (rounds, winner) = super::count(&ballot, Default::default());
+
+        assert_eq!(rounds[0].tally, vec![('a', 2.0), ('c', 2.0), ('b', 1.0)]);
+        assert_eq!((rounds[1].depth, &rounds[1].tally[0]), (2, &('b', 5.0)));
+        assert_eq!(winner, Some('b'));
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::{anyhow, Result};
+
+/// The CRC-32 a zip file checks each entry against (the IEEE polynomial,
+/// reflected), worked out a bit at a time since bundles are small.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for byte in data {
+        crc ^= u32::from(*byte);
+
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+
+    !crc
+}
+
+/// Packs files (each a name and its contents) into a zip archive, stored
+/// without compression. Every entry is dated 1980-01-01, the earliest date a
+/// zip can hold, so bundling the same count twice gives the same archive.
+pub fn zip(files: &[(String, Vec<u8>)]) -> Result<Vec<u8>> {
+    // version 2.0, a UTF-8 name, stored, then the time and date
+    const HEADER: [u16; 5] = [20, 0x0800, 0, 0, 0x21];
+
+    let mut archive = vec![];
+    let mut directory = vec![];
+
+    let too_big = || anyhow!("the bundle is too big for a zip file without the zip64 extensions");
+    let u16s = |out: &mut Vec<u8>, values: &[u16]| values.iter().for_each(|value| out.extend(value.to_le_bytes()));
+    let u32s = |out: &mut Vec<u8>, values: &[u32]| values.iter().for_each(|value| out.extend(value.to_le_bytes()));
+
+    for (name, contents) in files {
+        let offset = u32::try_from(archive.len()).map_err(|_| too_big())?;
+        let size = u32::try_from(contents.len()).map_err(|_| too_big())?;
+        let name_len = u16::try_from(name.len()).map_err(|_| anyhow!("{name} is too long a name for a zip entry"))?;
+        let crc = crc32(contents);
+
+        u32s(&mut archive, &[0x0403_4b50]);
+        u16s(&mut archive, &HEADER);
+        u32s(&mut archive, &[crc, size, size]);
+        u16s(&mut archive, &[name_len, 0]);
+        archive.extend(name.as_bytes());
+        archive.extend(contents);
+
+        u32s(&mut directory, &[0x0201_4b50]);
+        u16s(&mut directory, &[20]);
+        u16s(&mut directory, &HEADER);
+        u32s(&mut directory, &[crc, size, size]);
+        // no extra field, comment, disk number or attributes
+        u16s(&mut directory, &[name_len, 0, 0, 0, 0]);
+        u32s(&mut directory, &[0, offset]);
+        directory.extend(name.as_bytes());
+    }
+
+    let entries = u16::try_from(files.len()).map_err(|_| too_big())?;
+    let start = u32::try_from(archive.len()).map_err(|_| too_big())?;
+    let length = u32::try_from(directory.len()).map_err(|_| too_big())?;
+
+    archive.extend(directory);
+    u32s(&mut archive, &[0x0605_4b50]);
+    u16s(&mut archive, &[0, 0, entries, entries]);
+    u32s(&mut archive, &[length, start]);
+    u16s(&mut archive, &[0]);
+
+    Ok(archive)
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn stored_entries() {
+        assert_eq!(super::crc32(b"123456789"), 0xCBF4_3926);
+
+        let archive = super::zip(&[(String::from("report.md"), b"# Results\n".to_vec())]).expect("small enough");
+
+        // a 30 byte local header, the name and contents, then a 46 byte
+        // directory entry with the name again, then the 22 byte end record
+        assert_eq!(archive.len(), 30 + 9 + 10 + 46 + 9 + 22);
+        assert_eq!(&archive[..4], b"PK\x03\x04");
+        assert_eq!(&archive[30..39], b"report.md");
+    }
+}
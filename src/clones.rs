@@ -0,0 +1,148 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{Ballot, UNRANKED};
+
+/// For every pair of candidates, the share of the ballots ranking either of
+/// them that rank both with nobody in between (or equally). Clones, like two
+/// candidates from the same party, are ranked next to each other on nearly
+/// every ballot that ranks them at all.
+pub fn adjacency<T: Clone>(ballot: &Ballot<T>) -> Vec<Vec<f64>> {
+    let count = ballot.count();
+    let mut adjacent = vec![vec![0.0; count]; count];
+    let mut either = vec![vec![0.0; count]; count];
+
+    for (row, weight) in ballot.ballots().zip(ballot.weights()) {
+        // how far down the ballot each candidate is, counting equal ranks
+        // and skipped ranks as one step
+        let mut ranks: Vec<_> = row.iter().copied().filter(|rank| *rank != UNRANKED).collect();
+        ranks.sort_unstable();
+        ranks.dedup();
+
+        let level = |candidate: usize| match row[candidate] {
+            UNRANKED => None,
+            rank => ranks.binary_search(&rank).ok(),
+        };
+
+        for a in 0..count {
+            for b in a + 1..count {
+                match (level(a), level(b)) {
+                    (None, None) => continue,
+                    (Some(a_level), Some(b_level)) if a_level.abs_diff(b_level) <= 1 => adjacent[a][b] += weight,
+                    _ => {}
+                }
+
+                either[a][b] += weight;
+            }
+        }
+    }
+
+    let mut shares = vec![vec![0.0; count]; count];
+
+    for a in 0..count {
+        for b in a + 1..count {
+            let share = match either[a][b] {
+                0.0 => 0.0,
+                either => adjacent[a][b] / either,
+            };
+
+            shares[a][b] = share;
+            shares[b][a] = share;
+        }
+    }
+
+    shares
+}
+
+/// Groups of candidates (by index) joined by pairs ranked next to each other
+/// on at least `threshold` (from 0 to 1) of the ballots ranking either, in
+/// the order their first members were read.
+pub fn clusters(shares: &[Vec<f64>], threshold: f64) -> Vec<Vec<usize>> {
+    let count = shares.len();
+    let mut cluster: Vec<_> = (0..count).collect();
+
+    // joining groups by relabelling is fine for the amount of candidates
+    // on a ballot
+    for a in 0..count {
+        for b in a + 1..count {
+            if shares[a][b] >= threshold && cluster[a] != cluster[b] {
+                let (from, to) = (cluster[b], cluster[a]);
+
+                for group in cluster.iter_mut().filter(|group| **group == from) {
+                    *group = to;
+                }
+            }
+        }
+    }
+
+    (0..count)
+        .filter(|first| cluster[*first] == *first)
+        .map(|first| (0..count).filter(|i| cluster[*i] == first).collect::<Vec<_>>())
+        .filter(|members| members.len() > 1)
+        .collect()
+}
+
+/// The same ballots with `members` (by index) merged into a single candidate
+/// called `label`, ranked wherever each ballot ranked the highest of them.
+/// The merged candidate takes the first member's place.
+pub fn merge<T: Clone>(ballot: &Ballot<T>, members: &[usize], label: T) -> Ballot<T> {
+    let first = members.iter().copied().min().unwrap_or(0);
+    let kept: Vec<_> = (0..ballot.count()).filter(|i| *i == first || !members.contains(i)).collect();
+    let labels = kept
+        .iter()
+        .map(|i| match *i == first {
+            true => label.clone(),
+            false => ballot.labels()[*i].clone(),
+        })
+        .collect();
+
+    let rankings: Vec<Vec<Vec<usize>>> = ballot
+        .ballots()
+        .map(|row| {
+            let ranks: Vec<_> = kept
+                .iter()
+                .map(|i| match *i == first {
+                    true => members.iter().map(|member| row[*member]).min().unwrap_or(UNRANKED),
+                    false => row[*i],
+                })
+                .collect();
+
+            let mut levels: Vec<_> = ranks.iter().copied().filter(|rank| *rank != UNRANKED).collect();
+            levels.sort_unstable();
+            levels.dedup();
+
+            levels
+                .iter()
+                .map(|level| (0..ranks.len()).filter(|i| ranks[*i] == *level).collect())
+                .collect()
+        })
+        .collect();
+
+    Ballot::from_tied_rankings(labels, &rankings)
+        .ok()
+        .and_then(|merged| merged.with_weights(ballot.weights().to_vec()).ok())
+        .expect("merged rankings only contain kept candidates, one weight per ballot")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Ballot;
+
+    #[test]
+    fn splits_and_merges() {
+        // a1 and a2 split their side's vote, so b wins on first preferences
+        let mut rankings = vec![vec![0, 1]; 3];
+        rankings.extend(vec![vec![1, 0]; 3]);
+        rankings.extend(vec![vec![2]; 4]);
+        let ballot = Ballot::from_rankings(vec!["a1", "a2", "b"], &rankings).expect("valid rankings");
+
+        let shares = super::adjacency(&ballot);
+        assert_eq!((shares[0][1], shares[0][2]), (1.0, 0.0));
+
+        let clusters = super::clusters(&shares, 0.9);
+        assert_eq!(clusters, vec![vec![0, 1]]);
+
+        let merged = super::merge(&ballot, &clusters[0], "a");
+        assert_eq!(merged.labels(), ["a", "b"]);
+        assert_eq!(merged.tier(), vec![6.0, 4.0]);
+    }
+}
@@ -0,0 +1,504 @@
+use alloc::collections::VecDeque;
+use alloc::{vec, vec::Vec};
+use core::ops::Range;
+
+use crate::{Ballot, Ranks};
+
+/// How many voters preferred each candidate over each other candidate, where
+/// `prefer[a][b]` is the amount (or weight) of ballots ranking `a` above `b`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Pairwise {
+    pub prefer: Vec<Vec<f64>>,
+}
+
+/// Ballots are counted in blocks of this many, and the blocks' counts added
+/// together in order, so that weighted counts (where the order floats are
+/// added in matters) come out exactly the same with or without rayon.
+const BLOCK: usize = 1024;
+
+impl Pairwise {
+    pub fn new<T: Clone>(ballot: &Ballot<T>) -> Self {
+        let count = ballot.count();
+        let ballots = ballot.ballot_count();
+        let (votes, weights) = (&ballot.votes, &ballot.weights);
+        #[cfg(feature = "rayon")]
+        let blocks: Vec<_> = {
+            use rayon::prelude::*;
+
+            // collecting keeps the blocks in order, however they were scheduled
+            (0..ballots)
+                .into_par_iter()
+                .step_by(BLOCK)
+                .map(|start| Self::block(count, votes, weights, start..ballots.min(start + BLOCK)))
+                .collect()
+        };
+        #[cfg(not(feature = "rayon"))]
+        let blocks = (0..ballots)
+            .step_by(BLOCK)
+            .map(|start| Self::block(count, votes, weights, start..ballots.min(start + BLOCK)));
+
+        let mut prefer = vec![vec![0.0; count]; count];
+
+        for block in blocks {
+            for (total, part) in prefer.iter_mut().flatten().zip(block.into_iter().flatten()) {
+                *total += part;
+            }
+        }
+
+        Self { prefer }
+    }
+
+    /// Counts one block of ballots on its own
+    fn block(count: usize, votes: &Ranks, weights: &[f64], ballots: Range<usize>) -> Vec<Vec<f64>> {
+        let mut prefer = vec![vec![0.0; count]; count];
+
+        for i in ballots {
+            Self::add(&mut prefer, &votes.row(count, i), weights[i]);
+        }
+
+        prefer
+    }
+
+    /// Adds one ballot's head-to-head preferences to the counts so far
+    fn add(prefer: &mut [Vec<f64>], row: &[usize], weight: f64) {
+        for a in 0..row.len() {
+            for b in 0..row.len() {
+                if row[a] < row[b] {
+                    prefer[a][b] += weight;
+                }
+            }
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.prefer.len()
+    }
+
+    /// Whether more voters prefer `a` to `b` than `b` to `a`
+    pub fn beats(&self, a: usize, b: usize) -> bool {
+        self.prefer[a][b] > self.prefer[b][a]
+    }
+
+    /// The candidate who beats every other candidate head-to-head, if any
+    pub fn winner(&self) -> Option<usize> {
+        (0..self.count()).find(|a| (0..self.count()).all(|b| *a == b || self.beats(*a, b)))
+    }
+
+    /// The candidate who loses to every other candidate head-to-head, if any.
+    /// A lone candidate doesn't lose to anyone.
+    pub fn loser(&self) -> Option<usize> {
+        if self.count() < 2 {
+            return None;
+        }
+
+        (0..self.count()).find(|b| (0..self.count()).all(|a| *b == a || self.beats(a, *b)))
+    }
+
+    /// The smallest set of candidates who each beat every candidate outside
+    /// of the set. If there's a Condorcet winner, it's the only member.
+    pub fn smith_set(&self) -> Vec<usize> {
+        let count = self.count();
+
+        // a candidate can reach another if it beats or ties it, possibly
+        // through a chain of other candidates
+        let mut reach: Vec<Vec<_>> = (0..count)
+            .map(|a| (0..count).map(|b| a == b || !self.beats(b, a)).collect())
+            .collect();
+
+        for k in 0..count {
+            for a in 0..count {
+                for b in 0..count {
+                    reach[a][b] = reach[a][b] || (reach[a][k] && reach[k][b]);
+                }
+            }
+        }
+
+        // the smith set is every candidate that can reach everyone who can
+        // reach them
+        (0..count)
+            .filter(|a| (0..count).all(|b| !reach[b][*a] || reach[*a][b]))
+            .collect()
+    }
+
+    /// Cycles of head-to-head wins (e.g. A beats B beats C beats A) within the
+    /// Smith set, which are why no Condorcet winner exists. Each cycle is
+    /// listed once, starting from its lowest candidate, with the first
+    /// candidate not repeated at the end.
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        let smith = self.smith_set();
+        let mut cycles: Vec<Vec<usize>> = vec![];
+
+        // the shortest cycle through each member is plenty to explain the
+        // result, where listing every cycle could be exponential
+        for start in smith.iter().copied() {
+            let Some(cycle) = self.shortest_cycle(start, &smith) else {
+                continue;
+            };
+
+            let lowest = (0..cycle.len()).min_by_key(|i| cycle[*i]).unwrap_or(0);
+            let mut cycle = cycle;
+            cycle.rotate_left(lowest);
+
+            if !cycles.contains(&cycle) {
+                cycles.push(cycle);
+            }
+        }
+
+        cycles
+    }
+
+    /// How many candidates `a` beats, loses to and ties with head-to-head
+    pub fn record(&self, a: usize) -> (usize, usize, usize) {
+        let others = (0..self.count()).filter(|b| *b != a);
+        let wins = others.clone().filter(|b| self.beats(a, *b)).count();
+        let losses = others.clone().filter(|b| self.beats(*b, a)).count();
+
+        (wins, losses, others.count() - wins - losses)
+    }
+
+    /// Copeland scores: a point for every head-to-head win and `tie_points`
+    /// for every tie, most first. With half a point for ties, the order is
+    /// the same as wins minus losses. Candidates with the same score stay in
+    /// their original order.
+    pub fn copeland(&self, tie_points: f64) -> Vec<(usize, f64)> {
+        let mut scores: Vec<_> = (0..self.count())
+            .map(|a| {
+                let (wins, _, ties) = self.record(a);
+
+                (a, wins as f64 + ties as f64 * tie_points)
+            })
+            .collect();
+
+        scores.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        scores
+    }
+
+    /// The strength of the strongest path from each candidate to each other
+    /// candidate, for the Schulze method. A path is a chain of head-to-head
+    /// wins, and it's as strong as its weakest win (counted by the votes of
+    /// whoever won it).
+    pub fn strongest_paths(&self) -> Vec<Vec<f64>> {
+        let count = self.count();
+        let mut paths: Vec<Vec<_>> = (0..count)
+            .map(|a| (0..count).map(|b| if self.beats(a, b) { self.prefer[a][b] } else { 0.0 }).collect())
+            .collect();
+
+        for k in 0..count {
+            for a in (0..count).filter(|a| *a != k) {
+                for b in (0..count).filter(|b| *b != k && *b != a) {
+                    paths[a][b] = paths[a][b].max(paths[a][k].min(paths[k][b]));
+                }
+            }
+        }
+
+        paths
+    }
+
+    /// The Schulze ordering of every candidate: a is ahead of b if a's
+    /// strongest path to b is stronger than b's to a. Candidates that can't
+    /// be separated that way stay in their original order.
+    pub fn schulze(&self) -> Vec<usize> {
+        let paths = self.strongest_paths();
+        // the relation is transitive, so how many candidates each one is
+        // ahead of is enough to order them
+        let ahead = |a: usize| (0..self.count()).filter(|b| paths[a][*b] > paths[*b][a]).count();
+        let mut order: Vec<_> = (0..self.count()).collect();
+
+        order.sort_by_key(|a| core::cmp::Reverse(ahead(*a)));
+        order
+    }
+
+    /// The Kemeny-Young ordering: the one that agrees with the most
+    /// head-to-head preferences on the ballots, along with how many it
+    /// agrees with. Finding it takes exponential time in the worst case, so
+    /// orderings are built up one place at a time, and any partial ordering
+    /// that can't beat the best one so far is dropped. The Schulze ordering is
+    /// the starting point, and is kept if nothing beats it.
+    pub fn kemeny(&self) -> (Vec<usize>, f64) {
+        let count = self.count();
+        let best_order = self.schulze();
+        let best_score = self.agreement(&best_order);
+        // the most any two candidates can add, whichever way round they go
+        let most = |a: usize, b: usize| self.prefer[a][b].max(self.prefer[b][a]);
+        let bound: f64 = (0..count).flat_map(|a| (a + 1..count).map(move |b| (a, b))).map(|(a, b)| most(a, b)).sum();
+
+        let mut search = Search {
+            pairwise: self,
+            best: (best_order, best_score),
+            order: vec![],
+        };
+
+        search.extend(&(0..count).collect::<Vec<_>>(), 0.0, bound);
+        search.best
+    }
+
+    /// A Kemeny-Young ordering found quickly, for when there are too many
+    /// candidates to search every ordering: starting from the Schulze
+    /// ordering, candidates are moved to whichever place agrees with more
+    /// head-to-head preferences, until no single move helps. That's usually
+    /// the best ordering, but it isn't guaranteed to be.
+    pub fn kemeny_heuristic(&self) -> (Vec<usize>, f64) {
+        let mut order = self.schulze();
+        let mut score = self.agreement(&order);
+
+        loop {
+            let mut best = (score, None);
+
+            for from in 0..order.len() {
+                for to in (0..order.len()).filter(|to| *to != from) {
+                    let mut moved = order.clone();
+                    let candidate = moved.remove(from);
+                    moved.insert(to, candidate);
+
+                    let agreement = self.agreement(&moved);
+
+                    if agreement > best.0 {
+                        best = (agreement, Some(moved));
+                    }
+                }
+            }
+
+            match best {
+                (agreement, Some(moved)) => (score, order) = (agreement, moved),
+                (_, None) => return (order, score),
+            }
+        }
+    }
+
+    /// How many head-to-head preferences an ordering agrees with
+    pub fn agreement(&self, order: &[usize]) -> f64 {
+        order
+            .iter()
+            .enumerate()
+            .flat_map(|(i, a)| order[i + 1..].iter().map(move |b| self.prefer[*a][*b]))
+            .sum()
+    }
+
+    fn shortest_cycle(&self, start: usize, within: &[usize]) -> Option<Vec<usize>> {
+        let mut previous = vec![None; self.count()];
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(current) = queue.pop_front() {
+            for next in within.iter().copied().filter(|n| self.beats(current, *n)) {
+                if next == start {
+                    let mut cycle = vec![current];
+
+                    while let Some(before) = previous[*cycle.last()?] {
+                        cycle.push(before);
+                    }
+
+                    cycle.reverse();
+                    return Some(cycle);
+                }
+
+                if previous[next].is_none() {
+                    previous[next] = Some(current);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// A Kemeny-Young branch-and-bound search in progress.
+struct Search<'a> {
+    pairwise: &'a Pairwise,
+    best: (Vec<usize>, f64),
+    /// The start of the ordering being built
+    order: Vec<usize>,
+}
+
+impl Search<'_> {
+    /// Tries every way of ordering the `remaining` candidates after the
+    /// current start, whose agreement so far is `score`. `bound` is the most
+    /// the remaining candidates could add between themselves.
+    fn extend(&mut self, remaining: &[usize], score: f64, bound: f64) {
+        if remaining.is_empty() {
+            if score > self.best.1 {
+                self.best = (self.order.clone(), score);
+            }
+
+            return;
+        }
+
+        // nothing after this start can do better than the best so far
+        if score + bound <= self.best.1 {
+            return;
+        }
+
+        let prefer = &self.pairwise.prefer;
+
+        for next in remaining.iter().copied() {
+            let rest: Vec<_> = remaining.iter().copied().filter(|c| *c != next).collect();
+            // putting them next fixes their pairs with everyone after them
+            let added: f64 = rest.iter().map(|c| prefer[next][*c]).sum();
+            let freed: f64 = rest.iter().map(|c| prefer[next][*c].max(prefer[*c][next])).sum();
+
+            self.order.push(next);
+            self.extend(&rest, score + added, bound - freed);
+            self.order.pop();
+        }
+    }
+}
+
+/// The candidate that more than half of the voters (by weight) rank below
+/// every other candidate, if any.
+pub fn majority_loser<T: Clone>(ballot: &Ballot<T>) -> Option<usize> {
+    let count = ballot.count();
+
+    // ranking a lone candidate last is also ranking them first
+    if count < 2 {
+        return None;
+    }
+
+    let total: f64 = ballot.weights().iter().sum();
+    let mut last = vec![0.0; count];
+
+    for (row, weight) in ballot.ballots().zip(ballot.weights()) {
+        let bottom = (0..count).find(|x| (0..count).all(|b| b == *x || row[b] < row[*x]));
+
+        if let Some(bottom) = bottom {
+            last[bottom] += weight;
+        }
+    }
+
+    (0..count).find(|x| last[*x] > total / 2.0)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Ballot;
+
+    #[test]
+    fn rock_paper_scissors() {
+        let labels = vec!["rock", "paper", "scissors", "lizard"];
+        let values: Vec<_> = vec![
+            [0, 1, 2, 3],
+            [0, 1, 2, 3],
+            [2, 0, 1, 3],
+            [2, 0, 1, 3],
+            [1, 2, 0, 3],
+            [1, 2, 0, 3],
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let ballot = Ballot::new(labels, values).expect("label/values mismatch");
+        let pairwise = super::Pairwise::new(&ballot);
+
+        assert_eq!(pairwise.winner(), None);
+        assert_eq!(pairwise.smith_set(), vec![0, 1, 2]);
+        assert_eq!(pairwise.cycles(), vec![vec![0, 1, 2]]);
+        assert_eq!(pairwise.record(0), (2, 1, 0));
+        assert_eq!(pairwise.copeland(0.5), vec![(0, 2.0), (1, 2.0), (2, 2.0), (3, 0.0)]);
+    }
+
+    #[test]
+    fn schulze_example() {
+        // the example from Schulze's paper, with 45 voters and a cycle
+        let profile = [
+            (5, [0, 2, 1, 4, 3]),
+            (5, [0, 3, 4, 2, 1]),
+            (8, [1, 4, 3, 0, 2]),
+            (3, [2, 0, 1, 4, 3]),
+            (7, [2, 0, 4, 1, 3]),
+            (2, [2, 1, 0, 3, 4]),
+            (7, [3, 2, 4, 1, 0]),
+            (8, [4, 1, 0, 3, 2]),
+        ];
+        let rankings: Vec<_> = profile.iter().flat_map(|(voters, ranking)| vec![ranking.to_vec(); *voters]).collect();
+
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c', 'd', 'e'], &rankings).expect("valid rankings");
+        let pairwise = super::Pairwise::new(&ballot);
+
+        assert_eq!(pairwise.schulze(), vec![4, 0, 2, 1, 3]);
+        assert_eq!(pairwise.strongest_paths()[4][0], 25.0);
+
+        // checked against every one of the 120 orderings
+        assert_eq!(pairwise.kemeny(), (vec![4, 1, 0, 3, 2], 260.0));
+
+        // moving one candidate at a time gets stuck one short of that here
+        let (order, agreement) = pairwise.kemeny_heuristic();
+        assert_eq!(agreement, 259.0);
+        assert_eq!(pairwise.agreement(&order), agreement);
+        assert!(agreement > pairwise.agreement(&pairwise.schulze()));
+    }
+
+    #[test]
+    fn losers() {
+        // a has the most first preferences, but loses to both b and c, and
+        // is ranked last by four of the seven voters
+        let mut rankings = vec![vec![0, 1, 2]; 3];
+        rankings.extend(vec![vec![1, 2, 0]; 2]);
+        rankings.extend(vec![vec![2, 1, 0]; 2]);
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c'], &rankings).expect("valid rankings");
+
+        assert_eq!(super::Pairwise::new(&ballot).loser(), Some(0));
+        assert_eq!(super::majority_loser(&ballot), Some(0));
+
+        // with one more b first, a is only last on half of the ballots
+        rankings.push(vec![1, 0, 2]);
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c'], &rankings).expect("valid rankings");
+        assert_eq!(super::majority_loser(&ballot), None);
+
+        let lone = Ballot::from_rankings(vec!['a'], &[vec![0]]).expect("valid rankings");
+        assert_eq!(super::Pairwise::new(&lone).loser(), None);
+        assert_eq!(super::majority_loser(&lone), None);
+    }
+
+    #[test]
+    fn parallel_counts_match() {
+        let labels = vec!['a', 'b', 'c'];
+        let rows = [[0, 1, 2], [2, 0, 1], [1, 2, 0], [0, 2, 1]];
+        let values: Vec<_> = (0..5000).flat_map(|i| rows[i % 4]).collect();
+        // quarters add up exactly in any order, so a plain count has to match
+        let weights: Vec<_> = (0..5000).map(|i| (i % 7 + 1) as f64 * 0.25).collect();
+
+        let ballot = Ballot::new(labels, values).expect("label/values mismatch");
+        let ballot = ballot.with_weights(weights).expect("a weight for every ballot");
+
+        // every ballot one after another, without blocks
+        let mut expected = vec![vec![0.0; 3]; 3];
+
+        for (row, weight) in ballot.ballots().zip(ballot.weights()) {
+            for a in 0..3 {
+                for b in 0..3 {
+                    if row[a] < row[b] {
+                        expected[a][b] += weight;
+                    }
+                }
+            }
+        }
+
+        assert_eq!(super::Pairwise::new(&ballot).prefer, expected);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_blocks_add_up_in_order() {
+        let labels = vec!['a', 'b', 'c'];
+        let rows = [[0, 1, 2], [2, 0, 1], [1, 2, 0], [0, 2, 1]];
+        let values: Vec<_> = (0..5000).flat_map(|i| rows[i % 4]).collect();
+        let weights: Vec<_> = (0..5000).map(|i| 0.1 + (i % 7) as f64 * 1e-3).collect();
+
+        let ballot = Ballot::new(labels, values).expect("label/values mismatch");
+        let ballot = ballot.with_weights(weights).expect("a weight for every ballot");
+
+        // these weights don't add up exactly, so the blocks have to be added
+        // in the same order as they would be without rayon
+        let mut expected = vec![vec![0.0; 3]; 3];
+
+        for start in (0..5000).step_by(super::BLOCK) {
+            let block = super::Pairwise::block(3, &ballot.votes, &ballot.weights, start..5000.min(start + super::BLOCK));
+
+            for (total, part) in expected.iter_mut().flatten().zip(block.into_iter().flatten()) {
+                *total += part;
+            }
+        }
+
+        assert_eq!(super::Pairwise::new(&ballot).prefer, expected);
+    }
+}
@@ -0,0 +1,295 @@
+use anyhow::{anyhow, Context, Result};
+use csv::StringRecord;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::Path;
+
+use crate::notation;
+use crate::ranks::Dialect;
+
+/// Candidate labels, and each ballot's ranking of candidates (by index) from
+/// most to least preferred.
+pub type Rankings = (Vec<String>, Vec<Vec<usize>>);
+
+/// Like `Rankings`, but each ranking lists groups of equally ranked candidates.
+pub type TiedRankings = (Vec<String>, Vec<Vec<Vec<usize>>>);
+
+/// Reads a Dominion cast vote record (CVR) export: a directory containing
+/// `CvrExport.json`, `CandidateManifest.json` and `ContestManifest.json`.
+/// `contest` (a contest description or id) picks the contest to tally if the
+/// export has several.
+pub fn read_dominion(dir: &Path, contest: Option<&str>) -> Result<Rankings> {
+    let (labels, rankings) = Dominion::read(dir)?.contest(contest)?;
+
+    Ok((labels, rankings.into_iter().map(|(_, ranking)| ranking).collect()))
+}
+
+/// A contest's candidate labels, and the ranking on each session (by index in
+/// the export) that had the contest on it.
+pub type SessionRankings = (Vec<String>, Vec<(usize, Vec<usize>)>);
+
+/// A Dominion CVR export, read once so that several contests can be taken
+/// from it.
+pub struct Dominion {
+    contests: Value,
+    candidates: Value,
+    export: Value,
+}
+
+impl Dominion {
+    pub fn read(dir: &Path) -> Result<Self> {
+        let read = |name: &str| -> Result<Value> {
+            let path = dir.join(name);
+            let file = std::fs::File::open(&path)
+                .with_context(|| format!("couldn't open {}", path.display()))?;
+
+            serde_json::from_reader(std::io::BufReader::new(file))
+                .with_context(|| format!("invalid json in {}", path.display()))
+        };
+
+        Ok(Self {
+            contests: read("ContestManifest.json")?,
+            candidates: read("CandidateManifest.json")?,
+            export: read("CvrExport.json")?,
+        })
+    }
+
+    /// Takes one contest's rankings out of the export. `contest` (a
+    /// description or id) can only be left out if there's just one contest.
+    pub fn contest(&self, contest: Option<&str>) -> Result<SessionRankings> {
+        let contests = list(&self.contests, "ContestManifest.json")?;
+        let contest_id = match contest {
+            Some(wanted) => contests
+                .iter()
+                .find(|c| {
+                    c["Description"].as_str() == Some(wanted)
+                        || wanted.parse::<u64>().is_ok_and(|id| c["Id"] == id)
+                })
+                .map(|c| c["Id"].clone())
+                .ok_or_else(|| anyhow!("no contest called {wanted:?} in ContestManifest.json"))?,
+            None if contests.len() == 1 => contests[0]["Id"].clone(),
+            None => {
+                let names: Vec<_> = contests.iter().filter_map(|c| c["Description"].as_str()).collect();
+                let names = names.join(", ");
+
+                return Err(anyhow!("the export has several contests, so pick one with --contest ({names})"));
+            }
+        };
+
+        let candidates: Vec<_> = list(&self.candidates, "CandidateManifest.json")?
+            .iter()
+            .filter(|c| c["ContestId"] == contest_id)
+            .collect();
+        let labels = candidates
+            .iter()
+            .map(|c| c["Description"].as_str().map(String::from))
+            .collect::<Option<Vec<_>>>()
+            .context("candidate without a description in CandidateManifest.json")?;
+        let index_of = |id: &Value| candidates.iter().position(|c| c["Id"] == *id);
+
+        let sessions = self.export["Sessions"]
+            .as_array()
+            .context("no sessions in CvrExport.json")?;
+        let mut rankings = vec![];
+
+        for (i, session) in sessions.iter().enumerate() {
+            // adjudicated ballots keep the original scan, marked as no longer current
+            let current = match (&session["Original"], &session["Modified"]) {
+                (original, Value::Null) => original,
+                (original, _) if original["IsCurrent"] != Value::Bool(false) => original,
+                (_, modified) => modified,
+            };
+
+            // older exports put contests directly on the session rather than on cards
+            let cards = match current["Cards"].as_array() {
+                Some(cards) => cards.iter().collect(),
+                None => vec![current],
+            };
+
+            for contest in cards
+                .into_iter()
+                .filter_map(|card| card["Contests"].as_array())
+                .flatten()
+                .filter(|contest| contest["Id"] == contest_id)
+            {
+                let mut marks: BTreeMap<u64, Vec<usize>> = BTreeMap::new();
+
+                for mark in contest["Marks"].as_array().into_iter().flatten() {
+                    if mark["IsAmbiguous"] == Value::Bool(true) || mark["IsVote"] == Value::Bool(false) {
+                        continue;
+                    }
+
+                    let rank = mark["Rank"]
+                        .as_u64()
+                        .with_context(|| format!("mark without a rank, session {i}"))?;
+                    let candidate = index_of(&mark["CandidateId"])
+                        .with_context(|| format!("mark for an unknown candidate, session {i}"))?;
+
+                    marks.entry(rank).or_default().push(candidate);
+                }
+
+                rankings.push((i, rank_order(marks.into_values())));
+            }
+        }
+
+        Ok((labels, rankings))
+    }
+}
+
+/// How many ballots had each pair of first preferences in two contests,
+/// matched up by session. `counts[a][b]` is the ballots whose first
+/// preference was candidate `a` in the first contest and `b` in the second.
+/// The last row and column are for ballots without a first preference in that
+/// contest, either since they didn't rank anyone or didn't have it at all.
+/// Sessions with neither contest aren't counted.
+pub fn crosstab(first: &SessionRankings, second: &SessionRankings) -> Vec<Vec<usize>> {
+    let (first_labels, first_rankings) = first;
+    let (second_labels, second_rankings) = second;
+    let mut counts = vec![vec![0; second_labels.len() + 1]; first_labels.len() + 1];
+
+    let by_session = |rankings: &[(usize, Vec<usize>)], none: usize| -> BTreeMap<usize, usize> {
+        rankings
+            .iter()
+            .map(|(session, ranking)| (*session, ranking.first().copied().unwrap_or(none)))
+            .collect()
+    };
+    let first = by_session(first_rankings, first_labels.len());
+    let second = by_session(second_rankings, second_labels.len());
+
+    for session in first.keys().chain(second.keys().filter(|s| !first.contains_key(s))) {
+        let a = first.get(session).copied().unwrap_or(first_labels.len());
+        let b = second.get(session).copied().unwrap_or(second_labels.len());
+
+        counts[a][b] += 1;
+    }
+
+    counts
+}
+
+/// Reads an ES&S CVR export saved as CSV (with headers), where `len` columns
+/// starting at `start` hold the candidate chosen at each rank, or
+/// "overvote"/"undervote". `dialect` is how the file is laid out, which is
+/// usually `Dialect::default()`.
+pub fn read_ess(reader: impl Read, start: usize, len: Option<usize>, dialect: Dialect) -> Result<Rankings> {
+    let records: Vec<_> = dialect
+        .reader(reader)
+        .records()
+        .enumerate()
+        .map(|(i, r)| r.with_context(|| format!("bad record {i}")))
+        .collect::<Result<_>>()?;
+
+    Ok(by_rank(&records, start, len))
+}
+
+/// Reads ballots from records where `len` columns starting at `start` hold
+/// the name of the candidate given each rank, like ES&S exports and ranked
+/// questions from survey tools, finding the candidates in the order they first
+/// appear. Blank ranks (or "undervote") are skipped, as are candidates named
+/// twice, and an "overvote" ends the ballot.
+pub fn by_rank(records: &[StringRecord], start: usize, len: Option<usize>) -> Rankings {
+    let mut labels: Vec<String> = vec![];
+    let mut rankings = vec![];
+
+    for row in records {
+        let cells = row.iter().skip(start).take(len.unwrap_or(usize::MAX));
+        let mut choices = vec![];
+
+        for cell in cells.map(str::trim) {
+            if cell.is_empty() || cell.eq_ignore_ascii_case("undervote") {
+                choices.push(vec![]);
+                continue;
+            }
+
+            if cell.eq_ignore_ascii_case("overvote") {
+                break;
+            }
+
+            let candidate = match labels.iter().position(|l| l == cell) {
+                Some(candidate) => candidate,
+                None => {
+                    labels.push(cell.to_owned());
+                    labels.len() - 1
+                }
+            };
+
+            choices.push(vec![candidate]);
+        }
+
+        rankings.push(rank_order(choices));
+    }
+
+    (labels, rankings)
+}
+
+/// Reads a CSV export (with headers) whose `column` holds each ballot's
+/// ranking in `A>B=C>D` notation, as some survey tools export rankings, giving
+/// the candidates and each ballot's groups of equally ranked candidates.
+pub fn read_notation(reader: impl Read, column: usize, dialect: Dialect) -> Result<TiedRankings> {
+    let mut csv_reader = dialect.reader(reader);
+    let mut labels: Vec<String> = vec![];
+    let mut rankings = vec![];
+
+    for (i, r) in csv_reader.records().enumerate() {
+        let row = r.with_context(|| format!("bad record {i}"))?;
+        let cell = row.get(column).with_context(|| format!("no ranking column, record {i}"))?;
+        let ranking = notation::parse_into(cell, &mut labels)
+            .map_err(|name| anyhow!("{name} is ranked twice, record {i}"))?;
+
+        rankings.push(ranking);
+    }
+
+    Ok((labels, rankings))
+}
+
+/// Turns the candidates marked at each rank into a ranking, skipping ranks
+/// that were left blank or repeat a candidate, and stopping at an overvote
+/// (the usual rules in US RCV statutes).
+fn rank_order(choices: impl IntoIterator<Item = Vec<usize>>) -> Vec<usize> {
+    let mut ranking = vec![];
+
+    for mut marked in choices {
+        marked.sort_unstable();
+        marked.dedup();
+
+        match marked[..] {
+            [] => continue,
+            [candidate] if !ranking.contains(&candidate) => ranking.push(candidate),
+            [_] => continue,
+            _ => break,
+        }
+    }
+
+    ranking
+}
+
+fn list<'a>(manifest: &'a Value, name: &str) -> Result<&'a Vec<Value>> {
+    manifest["List"]
+        .as_array()
+        .with_context(|| format!("no list in {name}"))
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn ess_overvotes_and_skips() {
+        let data = "\
+Cast Vote Record,Precinct,Mayor 1,Mayor 2,Mayor 3
+1,P1,Ann,undervote,Bob
+2,P1,Bob,overvote,Ann
+3,P2,Cat,Cat,Ann
+";
+        let (labels, rankings) = super::read_ess(data.as_bytes(), 2, None, Default::default()).expect("valid csv");
+
+        assert_eq!(labels, vec!["Ann", "Bob", "Cat"]);
+        assert_eq!(rankings, vec![vec![0, 1], vec![1], vec![2, 0]]);
+    }
+
+    #[test]
+    fn crosstab_by_session() {
+        let mayor = (vec![String::from("Ann"), String::from("Bob")], vec![(0, vec![0]), (1, vec![1, 0]), (2, vec![])]);
+        let council = (vec![String::from("Cat")], vec![(0, vec![0]), (1, vec![0]), (3, vec![0])]);
+
+        assert_eq!(super::crosstab(&mayor, &council), vec![vec![1, 0], vec![1, 0], vec![1, 1]]);
+    }
+}
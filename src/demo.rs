@@ -0,0 +1,104 @@
+use alloc::string::String;
+use alloc::vec;
+
+use crate::Ballot;
+
+/// A built-in election for trying out rankit without any data of your own.
+pub struct Example {
+    pub name: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub candidates: &'static [&'static str],
+    /// How many voters cast each ranking, where a ranking lists candidates
+    /// (by index) from most to least preferred
+    pub profile: &'static [(usize, &'static [usize])],
+}
+
+impl Example {
+    pub fn ballot(&self) -> Ballot<String> {
+        let labels = self.candidates.iter().copied().map(String::from).collect();
+        let mut votes = vec![];
+
+        for (voters, ranking) in self.profile {
+            let mut row = vec![0; self.candidates.len()];
+
+            for (rank, candidate) in ranking.iter().enumerate() {
+                row[*candidate] = rank;
+            }
+
+            for _ in 0..*voters {
+                votes.extend(&row);
+            }
+        }
+
+        Ballot::new(labels, votes).expect("example profiles are well formed")
+    }
+}
+
+pub const EXAMPLES: &[Example] = &[
+    Example {
+        name: "tennessee",
+        title: "Choosing the capital of Tennessee",
+        description: "\
+Every voter wants the capital as close to home as possible. Memphis has the
+most first preferences by far, but it's at one end of the state, so everyone
+else ranks it last.",
+        candidates: &["Memphis", "Nashville", "Chattanooga", "Knoxville"],
+        profile: &[
+            (42, &[0, 1, 2, 3]),
+            (26, &[1, 2, 3, 0]),
+            (15, &[2, 3, 1, 0]),
+            (17, &[3, 2, 1, 0]),
+        ],
+    },
+    Example {
+        name: "center-squeeze",
+        title: "A squeezed centrist",
+        description: "\
+The centrist is every voter's first or second choice and beats both others
+head-to-head, but has the fewest first preferences.",
+        candidates: &["Left", "Center", "Right"],
+        profile: &[
+            (35, &[0, 1, 2]),
+            (33, &[2, 1, 0]),
+            (16, &[1, 0, 2]),
+            (16, &[1, 2, 0]),
+        ],
+    },
+    Example {
+        name: "cycle",
+        title: "Rock, paper, scissors",
+        description: "\
+Each candidate beats one of the others head-to-head and loses to the other,
+so there's no Condorcet winner, and any result depends on the method used.",
+        candidates: &["Rock", "Paper", "Scissors"],
+        profile: &[
+            (10, &[0, 2, 1]),
+            (9, &[1, 0, 2]),
+            (8, &[2, 1, 0]),
+        ],
+    },
+];
+
+#[cfg(test)]
+mod test {
+    use crate::condorcet::Pairwise;
+
+    #[test]
+    fn examples_show_what_they_say() {
+        let ballot = |name| super::EXAMPLES.iter().find(|example| example.name == name).expect("a known example").ballot();
+
+        // Memphis leads on first preferences, but Nashville beats everyone head-to-head
+        let tennessee = ballot("tennessee");
+        assert_eq!(tennessee.ballot_count(), 100);
+        assert_eq!(tennessee.tier(), vec![42.0, 26.0, 15.0, 17.0]);
+        assert_eq!(Pairwise::new(&tennessee).winner(), Some(1));
+
+        // the centrist has the fewest first preferences and still wins head-to-head
+        let squeeze = ballot("center-squeeze");
+        assert_eq!(squeeze.tier(), vec![35.0, 32.0, 33.0]);
+        assert_eq!(Pairwise::new(&squeeze).winner(), Some(1));
+
+        assert_eq!(Pairwise::new(&ballot("cycle")).winner(), None);
+    }
+}
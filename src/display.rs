@@ -0,0 +1,159 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Short names to show in reports in place of the candidate labels from the
+/// input, e.g. for survey exports where the headers are entire sentences.
+#[derive(Debug, Default)]
+pub struct DisplayNames {
+    names: HashMap<String, String>,
+    ids: HashMap<String, usize>,
+    details: HashMap<String, Details>,
+    affiliations: HashMap<String, String>,
+}
+
+/// What the HTML report shows about a candidate besides their name.
+#[derive(Debug, Default)]
+pub struct Details {
+    /// The URL of their photo
+    pub photo: Option<String>,
+    pub description: Option<String>,
+}
+
+impl DisplayNames {
+    /// Reads a CSV file (with headers) whose first column contains candidate
+    /// labels and whose second column contains the names to display for them.
+    /// A third and fourth column can hold a photo URL and a description for
+    /// the HTML report, and a fifth the party or group they're affiliated
+    /// with, any of which can be left blank.
+    pub fn read(path: &Path, labels: &[String]) -> Result<Self> {
+        let mut csv_reader = csv::Reader::from_path(path)
+            .with_context(|| format!("couldn't open display names file {}", path.display()))?;
+        let mut names = HashMap::new();
+        let mut details = HashMap::new();
+        let mut affiliations = HashMap::new();
+
+        for (i, r) in csv_reader.records().enumerate() {
+            let row = r.with_context(|| format!("bad display names record {i}"))?;
+
+            let (Some(label), Some(display)) = (row.get(0), row.get(1)) else {
+                return Err(anyhow!("display names record {i} needs a label and a name"));
+            };
+
+            // a typo here would otherwise silently show the long label anyway
+            if !labels.iter().any(|l| l == label) {
+                return Err(anyhow!(
+                    "display names record {i} is for {label:?}, which isn't a candidate"
+                ));
+            }
+
+            let cell = |column| row.get(column).map(str::trim).filter(|cell| !cell.is_empty()).map(str::to_owned);
+            let (photo, description) = (cell(2), cell(3));
+
+            if photo.is_some() || description.is_some() {
+                details.insert(label.to_owned(), Details { photo, description });
+            }
+
+            if let Some(affiliation) = cell(4) {
+                affiliations.insert(label.to_owned(), affiliation);
+            }
+
+            names.insert(label.to_owned(), display.to_owned());
+        }
+
+        Ok(Self {
+            names,
+            ids: HashMap::new(),
+            details,
+            affiliations,
+        })
+    }
+
+    /// Numbers the candidates from 1 in the order they were read (the same
+    /// numbers the BLT output uses), and shows that number before every name.
+    pub fn with_ids(mut self, labels: &[String]) -> Self {
+        for (i, label) in labels.iter().enumerate() {
+            let id = i + 1;
+            let name = self.get(label).to_owned();
+
+            self.names.insert(label.clone(), format!("[{id}] {name}"));
+            self.ids.insert(label.clone(), id);
+        }
+
+        self
+    }
+
+    /// The candidate's number, if candidates were numbered
+    pub fn id(&self, label: &str) -> Option<usize> {
+        self.ids.get(label).copied()
+    }
+
+    /// The label as --raw prints it: after the candidate's number and a tab,
+    /// if candidates were numbered
+    pub fn label(&self, label: &str) -> String {
+        match self.id(label) {
+            Some(id) => format!("{id}\t{label}"),
+            None => label.to_owned(),
+        }
+    }
+
+    /// The candidate's photo and description, if they have either
+    pub fn details(&self, label: &str) -> Option<&Details> {
+        self.details.get(label)
+    }
+
+    /// The party or group the candidate is affiliated with, if any
+    pub fn affiliation(&self, label: &str) -> Option<&str> {
+        self.affiliations.get(label).map(String::as_str)
+    }
+
+    /// Whether any candidate has an affiliation
+    pub fn has_affiliations(&self) -> bool {
+        !self.affiliations.is_empty()
+    }
+
+    pub fn get<'a>(&'a self, label: &'a str) -> &'a str {
+        self.names.get(label).map_or(label, String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DisplayNames;
+
+    #[test]
+    fn short_names() {
+        let labels = vec![String::from("Which flavour do you like: Vanilla"), String::from("Chocolate")];
+        let dir = tempfile::tempdir().expect("temp dir is writable");
+        let path = dir.path().join("display-names.csv");
+
+        std::fs::write(&path, "label,name,photo\nWhich flavour do you like: Vanilla,Vanilla,vanilla.jpg\n").expect("temp dir is writable");
+        let names = DisplayNames::read(&path, &labels).expect("valid display names");
+
+        // candidates without a name keep their label
+        assert_eq!(names.get(&labels[0]), "Vanilla");
+        assert_eq!(names.get(&labels[1]), "Chocolate");
+        assert_eq!(names.details(&labels[0]).and_then(|details| details.photo.as_deref()), Some("vanilla.jpg"));
+
+        std::fs::write(&path, "label,name,photo,description,party\nChocolate,Chocolate,,,Cocoa\n").expect("temp dir is writable");
+        let names = DisplayNames::read(&path, &labels).expect("valid display names");
+        assert_eq!(names.affiliation("Chocolate"), Some("Cocoa"));
+        assert_eq!(names.affiliation(&labels[0]), None);
+
+        std::fs::write(&path, "label,name\nStrawberry,Strawberry\n").expect("temp dir is writable");
+        assert!(DisplayNames::read(&path, &labels).is_err());
+    }
+
+    #[test]
+    fn numbered() {
+        let labels = vec![String::from("Vanilla"), String::from("Chocolate")];
+        let names = DisplayNames::default().with_ids(&labels);
+
+        // numbered from 1 in input order, like the BLT output
+        assert_eq!(names.get("Chocolate"), "[2] Chocolate");
+        assert_eq!(names.id("Vanilla"), Some(1));
+        assert_eq!(names.id("Strawberry"), None);
+        assert_eq!(names.label("Chocolate"), "2\tChocolate");
+        assert_eq!(DisplayNames::default().label("Chocolate"), "Chocolate");
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt::Write;
+
+use crate::display::DisplayNames;
+use rankit::{format_votes, irv};
+
+/// Quotes text as a DOT string, where line breaks are written as \n.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+/// Draws an instant-runoff or Coombs count as a Graphviz DOT graph: a node for
+/// every candidate saying when they were eliminated (or that they won), and
+/// an edge for every transfer of votes from one to another, or to the
+/// exhausted ballots.
+pub fn graph(rounds: &[irv::Round<String>], winner: Option<&str>, names: &DisplayNames) -> String {
+    let mut dot = String::new();
+
+    // writing to a String can't fail
+    let _ = writeln!(dot, "digraph count {{\n    rankdir=LR;\n    node [shape=box];");
+
+    // everyone is in the first round's tally
+    for (label, _) in rounds.first().map_or(&[][..], |round| &round.tally[..]) {
+        let left = rounds.iter().enumerate().find_map(|(i, round)| {
+            match (round.eliminated.as_ref() == Some(label), round.withdrawn.contains(label)) {
+                (true, _) => Some(format!("eliminated in round {}", i + 1)),
+                (_, true) => Some(format!("withdrew after round {}", i + 1)),
+                _ => None,
+            }
+        });
+        let note = match (winner == Some(label.as_str()), left) {
+            (true, _) => "winner".to_owned(),
+            (false, Some(left)) => left,
+            (false, None) => "still in at the end".to_owned(),
+        };
+        let style = match winner == Some(label.as_str()) {
+            true => ", style=bold",
+            false => "",
+        };
+
+        let _ = writeln!(dot, "    {} [label={}{style}];", quote(label), quote(&format!("{}\n{note}", names.get(label))));
+    }
+
+    let exhausted = rounds.iter().any(|round| round.transfers.iter().any(|transfer| transfer.to.is_none()));
+
+    if exhausted {
+        let _ = writeln!(dot, "    exhausted [label=\"Exhausted\", shape=ellipse];");
+    }
+
+    for (i, round) in rounds.iter().enumerate() {
+        for transfer in &round.transfers {
+            let to = transfer.to.as_deref().map_or("exhausted".to_owned(), quote);
+            let label = format!("round {}: {}", i + 1, format_votes(transfer.votes));
+
+            let _ = writeln!(dot, "    {} -> {to} [label={}];", quote(&transfer.from), quote(&label));
+        }
+    }
+
+    let _ = writeln!(dot, "}}");
+
+    dot
+}
+
+#[cfg(test)]
+mod test {
+    use crate::display::DisplayNames;
+    use rankit::{irv, Ballot};
+
+    #[test]
+    fn edges_follow_transfers() {
+        let rankings = [vec![0, 1], vec![0], vec![1], vec![1], vec![2, 0]];
+        let ballot = Ballot::from_rankings(vec!["a \"A\"".to_owned(), "b".to_owned(), "c".to_owned()], &rankings).expect("valid rankings");
+        let (rounds, winner) = irv::count(&ballot, Default::default());
+        let dot = super::graph(&rounds, winner.as_deref(), &DisplayNames::default());
+
+        // c's ballot goes to a, who then has a majority
+        assert!(dot.contains("\"c\" -> \"a \\\"A\\\"\" [label=\"round 1: 1\"];"));
+        assert!(dot.contains("\"a \\\"A\\\"\" [label=\"a \\\"A\\\"\\nwinner\", style=bold];"));
+    }
+}
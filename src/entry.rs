@@ -0,0 +1,64 @@
+use anyhow::{anyhow, Result};
+
+/// Reads one ballot typed in as candidates from most to least preferred,
+/// either by name or by their number in the candidate list (from 1), separated
+/// by spaces, commas or ">". Every candidate has to be ranked, since the CSV
+/// format can't leave anyone out.
+pub fn parse_ranking(line: &str, candidates: &[String]) -> Result<Vec<usize>> {
+    let mut order = vec![];
+
+    for choice in line.split([' ', ',', '>']).map(str::trim).filter(|c| !c.is_empty()) {
+        let candidate = match choice.parse::<usize>() {
+            Ok(number) if (1..=candidates.len()).contains(&number) => number - 1,
+            Ok(number) => {
+                return Err(anyhow!("there's no candidate {number} (they're numbered 1 to {})", candidates.len()))
+            }
+            Err(_) => candidates
+                .iter()
+                .position(|c| c.eq_ignore_ascii_case(choice))
+                .ok_or_else(|| anyhow!("there's no candidate called {choice:?}"))?,
+        };
+
+        if order.contains(&candidate) {
+            return Err(anyhow!("{} is ranked twice", candidates[candidate]));
+        }
+
+        order.push(candidate);
+    }
+
+    let missing: Vec<_> = (0..candidates.len()).filter(|c| !order.contains(c)).collect();
+
+    // leaving out only the last choice is unambiguous
+    match missing[..] {
+        [] => {}
+        [last] if !order.is_empty() => order.push(last),
+        _ => {
+            let missing: Vec<_> = missing.iter().map(|c| candidates[*c].as_str()).collect();
+
+            return Err(anyhow!("every candidate has to be ranked, but {} aren't", missing.join(", ")));
+        }
+    }
+
+    // ranks by candidate, in the same layout as the CSV columns
+    let mut ranks = vec![0; candidates.len()];
+
+    for (rank, candidate) in order.into_iter().enumerate() {
+        ranks[candidate] = rank;
+    }
+
+    Ok(ranks)
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn names_and_numbers() {
+        let candidates = vec![String::from("Ann"), String::from("Bob"), String::from("Cat")];
+
+        assert_eq!(super::parse_ranking("bob > 3", &candidates).ok(), Some(vec![2, 0, 1]));
+        assert_eq!(super::parse_ranking("1, 2, 3", &candidates).ok(), Some(vec![0, 1, 2]));
+        assert!(super::parse_ranking("1 1 2", &candidates).is_err());
+        assert!(super::parse_ranking("Dan", &candidates).is_err());
+        assert!(super::parse_ranking("2", &candidates).is_err());
+    }
+}
@@ -0,0 +1,57 @@
+use alloc::{vec, vec::Vec};
+
+use crate::Ballot;
+
+/// The candidates left standing once all but a few have been eliminated.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Finalists<T> {
+    /// Each eliminated candidate in order, with their votes when eliminated
+    pub eliminated: Vec<(T, f64)>,
+    /// The candidates left, with their votes in the final round, most first
+    pub finalists: Vec<(T, f64)>,
+}
+
+/// Eliminates the candidate with the fewest first preferences, one round at a
+/// time, until only `amount` candidates are left.
+pub fn finalists<T: Clone>(ballot: &Ballot<T>, amount: usize) -> Finalists<T> {
+    let mut current = ballot.clone();
+    let mut eliminated = vec![];
+
+    while current.count() > amount.max(1) {
+        let tier = current.tier();
+        let Some(lowest) = (0..tier.len()).min_by(|a, b| tier[*a].total_cmp(&tier[*b])) else {
+            break;
+        };
+
+        eliminated.push((current.take(lowest), tier[lowest]));
+    }
+
+    let mut finalists: Vec<_> = current.labels().iter().cloned().zip(current.tier()).collect();
+    finalists.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    Finalists {
+        eliminated,
+        finalists,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Ballot;
+
+    #[test]
+    fn last_two() {
+        let mut rankings = vec![vec![0, 2, 1]; 4];
+        rankings.extend(vec![vec![1, 0, 2]; 3]);
+        rankings.extend(vec![vec![2, 1, 0]; 2]);
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c'], &rankings).expect("valid rankings");
+        let result = super::finalists(&ballot, 2);
+
+        // c goes first, and their ballots put b ahead of a
+        assert_eq!(result.eliminated, vec![('c', 2.0)]);
+        assert_eq!(result.finalists, vec![('b', 5.0), ('a', 4.0)]);
+
+        // asking for more finalists than candidates eliminates nobody
+        assert!(super::finalists(&ballot, 5).eliminated.is_empty());
+    }
+}
@@ -0,0 +1,63 @@
+use anyhow::{anyhow, Context, Result};
+use std::io::Read;
+
+use crate::{notation, Ballot};
+
+/// Reads ballots that were tallied by hand, one ranking per line in
+/// `A>B=C>D` notation with how many ballots had it after a comma, e.g.
+/// `A>B>C, 14`. A line without a count is a single ballot. Candidates are
+/// listed in the order they first appear, and each line becomes a single
+/// ballot weighted by its count, so counts don't have to be expanded into
+/// one row per ballot. Blank lines and lines starting with # are skipped.
+pub fn read(mut reader: impl Read) -> Result<Ballot<String>> {
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents).context("couldn't read the tallies")?;
+
+    let mut labels: Vec<String> = vec![];
+    let mut rankings = vec![];
+    let mut counts = vec![];
+
+    for (i, line) in contents.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (pattern, count) = match line.rsplit_once(',') {
+            Some((pattern, count)) => {
+                let count: f64 = count
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|count: &f64| count.is_finite() && *count >= 0.0)
+                    .with_context(|| format!("invalid count {:?}, line {i}", count.trim()))?;
+
+                (pattern, count)
+            }
+            None => (line, 1.0),
+        };
+
+        let ranking = notation::parse_into(pattern, &mut labels)
+            .map_err(|name| anyhow!("{name} is ranked twice, line {i}"))?;
+
+        rankings.push(ranking);
+        counts.push(count);
+    }
+
+    let ballot = Ballot::from_tied_rankings(labels, &rankings).expect("rankings only contain known candidates");
+
+    Ok(ballot.with_weights(counts).expect("a count for every pattern"))
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn patterns_with_counts() {
+        let data = "# precinct 4\nA>B>C, 14\nC > A=B, 3\n\n,2\nB\n";
+        let ballot = super::read(data.as_bytes()).expect("valid tallies");
+
+        assert_eq!(ballot.labels(), ["A", "B", "C"]);
+        assert_eq!(ballot.weights(), [14.0, 3.0, 2.0, 1.0]);
+        assert_eq!(ballot.tier(), vec![14.0, 1.0, 3.0]);
+        assert_eq!(ballot.ballots().nth(1).as_deref(), Some(&[1, 1, 0][..]));
+    }
+}
@@ -0,0 +1,103 @@
+use serde_json::Value;
+use std::fmt::Write;
+
+use crate::display::DisplayNames;
+use crate::json;
+use rankit::format_votes;
+
+/// Escapes text for use in HTML, including inside attributes.
+fn escape(text: &str) -> String {
+    text.chars().fold(String::new(), |mut escaped, c| {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+
+        escaped
+    })
+}
+
+/// Renders a count made by the `json` module as a standalone results page:
+/// the candidates, with their photos and descriptions if the display names
+/// file has them, then a table for every round.
+pub fn report(result: &Value, names: &DisplayNames) -> String {
+    let rows = json::rows(result);
+    let method = result["method"].as_str().unwrap_or_default();
+    let mut page = String::new();
+
+    // writing to a String can't fail
+    let _ = writeln!(page, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Results</title>\n</head>\n<body>");
+    let _ = writeln!(page, "<h1>Results</h1>\n<p>Counted by {}.</p>", escape(method));
+
+    // everyone is in the first round, in the order they placed in it
+    let candidates: Vec<_> = rows.iter().filter(|row| row.round == rows[0].round && !row.candidate.is_empty()).collect();
+
+    let _ = writeln!(page, "<h2>Candidates</h2>\n<ul class=\"candidates\">");
+
+    for row in candidates {
+        let _ = write!(page, "<li>");
+
+        if let Some(photo) = names.details(&row.candidate).and_then(|details| details.photo.as_deref()) {
+            let _ = write!(page, "<img src=\"{}\" alt=\"{}\"> ", escape(photo), escape(names.get(&row.candidate)));
+        }
+
+        let _ = write!(page, "<strong>{}</strong>", escape(names.get(&row.candidate)));
+
+        if let Some(description) = names.details(&row.candidate).and_then(|details| details.description.as_deref()) {
+            let _ = write!(page, "<p>{}</p>", escape(description));
+        }
+
+        let _ = writeln!(page, "</li>");
+    }
+
+    let _ = writeln!(page, "</ul>");
+
+    for (i, row) in rows.iter().enumerate() {
+        if i == 0 || rows[i - 1].round != row.round {
+            let _ = writeln!(page, "<h2>Round {}</h2>\n<table>\n<tr><th>Candidate</th><th>Votes</th><th></th></tr>", row.round);
+        }
+
+        let name = match row.candidate.as_str() {
+            "" => "Exhausted",
+            candidate => names.get(candidate),
+        };
+        let status = match row.status {
+            "continuing" | "exhausted" => "",
+            status => status,
+        };
+
+        let _ = writeln!(page, "<tr><td>{}</td><td>{}</td><td>{status}</td></tr>", escape(name), format_votes(row.votes));
+
+        if rows.get(i + 1).is_none_or(|next| next.round != row.round) {
+            let _ = writeln!(page, "</table>");
+        }
+    }
+
+    let _ = writeln!(page, "</body>\n</html>");
+
+    page
+}
+
+#[cfg(test)]
+mod test {
+    use crate::display::DisplayNames;
+    use serde_json::json;
+
+    #[test]
+    fn escapes_everything_shown() {
+        let result = json!({
+            "method": "irv",
+            "rounds": [{ "round": 1, "tally": [{ "candidate": "<b>", "votes": 2.0 }], "exhausted": 0.0, "eliminated": null, "withdrawn": [] }],
+            "winner": "<b>",
+        });
+        let page = super::report(&result, &DisplayNames::default());
+
+        assert!(page.contains("<strong>&lt;b&gt;</strong>"));
+        assert!(page.contains("<tr><td>&lt;b&gt;</td><td>2</td><td>elected</td></tr>"));
+        assert!(!page.contains("<img"));
+    }
+}
@@ -0,0 +1,317 @@
+use anyhow::{anyhow, Context, Result};
+use csv::StringRecord;
+use std::collections::BTreeSet;
+use std::io::Read;
+
+use crate::ranks::Dialect;
+
+/// What the values in a column look like.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    /// Every cell is blank
+    Empty,
+    /// Every non-blank cell is a whole number 0 or above
+    Integer { min: usize, max: usize },
+    /// Every non-blank cell is a number, but not all are whole
+    Number,
+    Text,
+}
+
+/// A summary of one column of an input file.
+pub struct Column {
+    pub name: String,
+    pub kind: Kind,
+    pub blank: usize,
+    pub distinct: BTreeSet<String>,
+}
+
+/// A run of contiguous columns that look like they contain ranks.
+pub struct RankGuess {
+    pub start: usize,
+    pub len: usize,
+    pub indexed_at: usize,
+}
+
+/// Summarizes every column of a CSV file laid out like `dialect`.
+pub fn columns(reader: impl Read, dialect: Dialect) -> Result<(usize, Vec<Column>)> {
+    let mut csv_reader = dialect.reader(reader);
+    let headers = dialect.headers(&mut csv_reader)?;
+    let records: Vec<_> = csv_reader
+        .records()
+        .enumerate()
+        .map(|(i, r)| r.with_context(|| format!("bad record {i}")))
+        .collect::<Result<_>>()?;
+
+    Ok((records.len(), summarize(&headers, &records)))
+}
+
+/// Summarizes every column of records that have already been read.
+pub fn summarize(headers: &StringRecord, records: &[StringRecord]) -> Vec<Column> {
+    let mut columns: Vec<_> = headers
+        .iter()
+        .map(|name| Column {
+            name: name.to_owned(),
+            kind: Kind::Empty,
+            blank: 0,
+            distinct: BTreeSet::new(),
+        })
+        .collect();
+
+    for row in records {
+        for (column, cell) in columns.iter_mut().zip(row.iter()) {
+            let cell = cell.trim();
+
+            if cell.is_empty() {
+                column.blank += 1;
+                continue;
+            }
+
+            column.kind = widen(column.kind, cell);
+            column.distinct.insert(cell.to_owned());
+        }
+    }
+
+    columns
+}
+
+/// Fails with a message naming the first of the selected columns whose values
+/// can't be ranks among `count` candidates, since a per-cell parse error
+/// doesn't make it obvious that the wrong columns were selected.
+pub fn check_ranks(columns: &[Column], start: usize, count: usize, indexed_at: usize) -> Result<()> {
+    let last_rank = indexed_at + count.saturating_sub(1);
+
+    for (i, column) in columns.iter().enumerate().skip(start).take(count) {
+        let name = &column.name;
+        let example = |fits: fn(&str) -> bool| {
+            column.distinct.iter().find(|v| !fits(v)).cloned().unwrap_or_default()
+        };
+
+        let problem = match column.kind {
+            Kind::Text => {
+                let example = example(|v| v.parse::<f64>().is_ok());
+                format!("it contains text such as {example:?}")
+            }
+            Kind::Number => {
+                let example = example(|v| v.parse::<usize>().is_ok());
+                format!("it contains numbers that aren't whole such as {example}")
+            }
+            Kind::Integer { max, .. } if max > last_rank => {
+                format!("it contains {max}, but with {count} candidates ranks only go up to {last_rank}")
+            }
+            _ => continue,
+        };
+
+        let suggestion = match guess_ranks(columns) {
+            Some(guess) => {
+                let (start, len, indexed_at) = (guess.start, guess.len, guess.indexed_at);
+                let end = start + len - 1;
+
+                format!("columns {start} to {end} look like ranks, so try --start {start} --indexed-at {indexed_at} {len}")
+            }
+            None => String::from("try adjusting --start and the amount of columns"),
+        };
+
+        return Err(anyhow!(
+            "column {i} ({name:?}) doesn't look like ranks, since {problem}; {suggestion}"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Warnings about selected columns that are constant across every record, or
+/// identical to another selected column, which usually means the wrong
+/// columns were selected.
+pub fn suspicious_columns(
+    headers: &StringRecord,
+    records: &[StringRecord],
+    start: usize,
+    count: usize,
+) -> Vec<String> {
+    let selected = start..start + count;
+    let cells = |column: usize| records.iter().map(move |row| row.get(column));
+    let name = |column: usize| headers.get(column).unwrap_or_default();
+    let mut warnings = vec![];
+
+    // with a single record, every column is trivially constant
+    if records.len() < 2 {
+        return warnings;
+    }
+
+    for column in selected.clone() {
+        let mut values = cells(column);
+        let first = values.next().flatten();
+
+        if values.all(|value| value == first) {
+            let value = first.unwrap_or_default();
+
+            warnings.push(format!(
+                "column {column} ({:?}) is {value:?} in every record",
+                name(column)
+            ));
+        }
+    }
+
+    for a in selected.clone() {
+        for b in (a + 1)..selected.end {
+            if cells(a).eq(cells(b)) {
+                warnings.push(format!(
+                    "columns {a} ({:?}) and {b} ({:?}) are identical in every record",
+                    name(a),
+                    name(b)
+                ));
+            }
+        }
+    }
+
+    warnings
+}
+
+/// Problems with individual ballots' ranks, which are counted as they are but
+/// may not be what the voter meant.
+#[derive(Debug, Default, PartialEq)]
+pub struct RankIssues {
+    /// Ballots ranking more than one candidate the same
+    pub equal: usize,
+    /// Ballots that skip a rank, e.g. ranking someone 1st and 3rd but nobody 2nd
+    pub skipped: usize,
+}
+
+/// Counts ballots with rank problems in `count` columns from `start`. Cells
+/// that aren't ranks are left to the rank parsing to report.
+pub fn rank_issues(records: &[StringRecord], start: usize, count: usize, indexed_at: usize) -> RankIssues {
+    let mut issues = RankIssues::default();
+
+    for row in records {
+        let mut ranks: Vec<usize> = row
+            .iter()
+            .skip(start)
+            .take(count)
+            .filter_map(|cell| cell.trim().parse().ok())
+            .collect();
+
+        ranks.sort_unstable();
+
+        let before = ranks.len();
+        ranks.dedup();
+
+        if ranks.len() < before {
+            issues.equal += 1;
+        }
+
+        if ranks.iter().enumerate().any(|(i, rank)| *rank != indexed_at + i) {
+            issues.skipped += 1;
+        }
+    }
+
+    issues
+}
+
+/// The kind of a column, given a kind that fit every cell so far and one more cell.
+fn widen(kind: Kind, cell: &str) -> Kind {
+    let cell_kind = if let Ok(value) = cell.parse::<usize>() {
+        Kind::Integer { min: value, max: value }
+    } else if cell.parse::<f64>().is_ok() {
+        Kind::Number
+    } else {
+        Kind::Text
+    };
+
+    match (kind, cell_kind) {
+        (Kind::Empty, cell_kind) => cell_kind,
+        (Kind::Text, _) | (_, Kind::Text) => Kind::Text,
+        (Kind::Integer { min, max }, Kind::Integer { min: value, .. }) => Kind::Integer {
+            min: min.min(value),
+            max: max.max(value),
+        },
+        _ => Kind::Number,
+    }
+}
+
+/// Finds the longest run of contiguous integer columns whose values look like
+/// ranks among that many candidates, i.e. running from 0 or 1 up to exactly
+/// the last place.
+pub fn guess_ranks(columns: &[Column]) -> Option<RankGuess> {
+    let mut best: Option<RankGuess> = None;
+
+    for start in 0..columns.len() {
+        let mut low = usize::MAX;
+        let mut high = 0;
+
+        for (offset, column) in columns[start..].iter().enumerate() {
+            let Kind::Integer { min, max } = column.kind else {
+                break;
+            };
+
+            low = low.min(min);
+            high = high.max(max);
+
+            let len = offset + 1;
+            let fits = low <= 1 && high + 1 == low + len;
+
+            if fits && len >= 2 && best.as_ref().is_none_or(|b| len > b.len) {
+                best = Some(RankGuess {
+                    start,
+                    len,
+                    indexed_at: low,
+                });
+            }
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn guesses_rank_columns() {
+        let data = "id,a,b,c,age\n101,1,2,3,40\n102,3,1,2,51\n103,2,3,1,27\n";
+
+        let (rows, columns) = super::columns(data.as_bytes(), Default::default()).expect("valid csv");
+        let guess = super::guess_ranks(&columns).expect("rank columns exist");
+
+        assert_eq!(rows, 3);
+        assert_eq!((guess.start, guess.len, guess.indexed_at), (1, 3, 1));
+
+        let records: Vec<_> = [["1", "1", "2"], ["1", "3", ""], ["2", "1", "2"]].iter().map(|row| csv::StringRecord::from(&row[..])).collect();
+        let issues = super::rank_issues(&records, 0, 3, 1);
+
+        assert_eq!((issues.equal, issues.skipped), (2, 1));
+    }
+
+    #[test]
+    fn names_columns_that_arent_ranks() {
+        let data = "id,name,a,b,c\n101,Ann,1,2,3\n102,Bob,3,1,2\n103,Cat,2,3,1\n";
+        let (_, columns) = super::columns(data.as_bytes(), Default::default()).expect("valid csv");
+
+        // starting a column early takes in the names, and two early the ids
+        let error = super::check_ranks(&columns, 1, 3, 1).expect_err("names aren't ranks").to_string();
+        assert_eq!(
+            error,
+            "column 1 (\"name\") doesn't look like ranks, since it contains text such as \"Ann\"; columns 2 to 4 look like ranks, so try --start 2 --indexed-at 1 3"
+        );
+
+        let error = super::check_ranks(&columns, 0, 3, 1).expect_err("ids aren't ranks").to_string();
+        assert!(error.contains("since it contains 103, but with 3 candidates ranks only go up to 3"));
+        assert!(super::check_ranks(&columns, 2, 3, 1).is_ok());
+    }
+
+    #[test]
+    fn flags_suspicious_columns() {
+        let headers = csv::StringRecord::from(vec!["a", "b", "c", "d"]);
+        let records: Vec<_> = [["1", "2", "3", "2"], ["2", "1", "3", "1"], ["3", "2", "3", "2"]]
+            .iter()
+            .map(|row| csv::StringRecord::from(&row[..]))
+            .collect();
+
+        assert_eq!(
+            super::suspicious_columns(&headers, &records, 0, 4),
+            ["column 2 (\"c\") is \"3\" in every record", "columns 1 (\"b\") and 3 (\"d\") are identical in every record"]
+        );
+
+        // one record is the same as itself everywhere, and c and d aren't selected here
+        assert!(super::suspicious_columns(&headers, &records[..1], 0, 4).is_empty());
+        assert!(super::suspicious_columns(&headers, &records, 0, 2).is_empty());
+    }
+}
@@ -0,0 +1,250 @@
+use alloc::{vec, vec::Vec};
+
+use crate::rounding::Rounding;
+use crate::Ballot;
+
+/// One round of a standard instant-runoff count.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Round<T> {
+    /// Every candidate still in the count with their votes, most first
+    pub tally: Vec<(T, f64)>,
+    /// The votes of ballots that don't rank anyone still in the count
+    pub exhausted: f64,
+    /// Who was eliminated at the end of the round, unless the count ended
+    pub eliminated: Option<T>,
+    /// What the eliminated candidate was eliminated with: their first
+    /// preferences, or their last places under the Coombs rule
+    pub eliminated_with: f64,
+    /// Whether more than one candidate had the fewest votes, in which case
+    /// the first of them in the input was eliminated
+    pub tied: bool,
+    /// Who withdrew at the end of the round, on top of whoever was
+    /// eliminated
+    pub withdrawn: Vec<T>,
+    /// Where the votes of whoever was eliminated or withdrew went for the
+    /// next round
+    pub transfers: Vec<Transfer<T>>,
+}
+
+/// Votes that moved from one candidate to another between rounds.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Transfer<T> {
+    pub from: T,
+    /// Who the votes went to, or nobody if the ballots were exhausted
+    pub to: Option<T>,
+    pub votes: f64,
+}
+
+/// Which candidate is eliminated each round.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Elimination {
+    /// The one with the fewest first preferences, as in instant-runoff
+    FewestFirst,
+    /// The one ranked last on the most ballots, as in the Coombs rule
+    MostLast,
+}
+
+/// Counts the way instant-runoff is usually defined: the candidate with the
+/// fewest first preferences is eliminated each round and their ballots go to
+/// their next preference, until someone has a majority of the votes still
+/// counting (or is the only one left). Returns every round, and the winner
+/// unless there were no candidates.
+pub fn count<T: Clone>(ballot: &Ballot<T>, rounding: Rounding) -> (Vec<Round<T>>, Option<T>) {
+    count_by(ballot, rounding, Elimination::FewestFirst)
+}
+
+/// Like `count`, but eliminating candidates by any rule. With
+/// `Elimination::MostLast`, this is the Coombs rule.
+pub fn count_by<T: Clone>(ballot: &Ballot<T>, rounding: Rounding, elimination: Elimination) -> (Vec<Round<T>>, Option<T>) {
+    count_withdrawing(ballot, rounding, elimination, &[])
+}
+
+/// Like `count_by`, but with candidates withdrawing from the count partway
+/// through, given as each one (by index) and the round (from 1) they
+/// withdraw after.
+/// Their ballots go to their next preferences in the next round, just like
+/// an eliminated candidate's. Withdrawals after the count ends, or by
+/// candidates who were already eliminated, don't change anything.
+pub fn count_withdrawing<T: Clone>(
+    ballot: &Ballot<T>,
+    rounding: Rounding,
+    elimination: Elimination,
+    withdrawals: &[(usize, usize)],
+) -> (Vec<Round<T>>, Option<T>) {
+    let mut current = ballot.clone();
+    // which of the original candidates each one still in the count is
+    let mut remaining: Vec<_> = (0..ballot.count()).collect();
+    let mut rounds = vec![];
+
+    loop {
+        let tier: Vec<_> = current.tier().into_iter().map(|votes| rounding.apply(votes)).collect();
+        let exhausted = current
+            .ballots()
+            .zip(current.weights())
+            .filter(|(row, _)| Ballot::<T>::first_preference(row).is_none())
+            .fold(0.0, |total, (_, weight)| total + weight);
+        let continuing = tier.iter().sum::<f64>();
+
+        let mut tally: Vec<_> = current.labels().iter().cloned().zip(tier.iter().copied()).collect();
+        tally.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        let leader = tally.first().map(|(label, votes)| (label.clone(), *votes));
+        // how bad each candidate's showing is, where the worst is eliminated
+        let against: Vec<_> = match elimination {
+            Elimination::FewestFirst => tier.clone(),
+            Elimination::MostLast => last_places(&current).into_iter().map(|votes| -rounding.apply(votes)).collect(),
+        };
+        let lowest = (0..against.len()).min_by(|a, b| against[*a].total_cmp(&against[*b]));
+
+        match (leader, lowest) {
+            (None, _) | (_, None) => return (rounds, None),
+            (Some((leader, votes)), _) if votes * 2.0 > continuing || tier.len() == 1 => {
+                rounds.push(Round {
+                    tally,
+                    exhausted: rounding.apply(exhausted),
+                    eliminated: None,
+                    eliminated_with: 0.0,
+                    tied: false,
+                    withdrawn: vec![],
+                    transfers: vec![],
+                });
+
+                return (rounds, Some(leader));
+            }
+            (_, Some(lowest)) => {
+                let tied = against.iter().filter(|votes| **votes == against[lowest]).count() > 1;
+                let before = first_choices(&current, &remaining);
+
+                let mut round = Round {
+                    tally,
+                    exhausted: rounding.apply(exhausted),
+                    eliminated: Some(current.take(lowest)),
+                    eliminated_with: against[lowest].abs(),
+                    tied,
+                    withdrawn: vec![],
+                    transfers: vec![],
+                };
+                remaining.remove(lowest);
+
+                for (candidate, _) in withdrawals.iter().filter(|(_, after)| *after == rounds.len() + 1) {
+                    if let Some(index) = remaining.iter().position(|original| original == candidate) {
+                        remaining.remove(index);
+                        round.withdrawn.push(current.take(index));
+                    }
+                }
+
+                round.transfers = transfers(&before, &first_choices(&current, &remaining), current.weights(), &remaining)
+                    .into_iter()
+                    .map(|(from, to, votes)| Transfer {
+                        from: ballot.labels()[from].clone(),
+                        to: to.map(|to| ballot.labels()[to].clone()),
+                        votes: rounding.apply(votes),
+                    })
+                    .collect();
+
+                rounds.push(round);
+            }
+        }
+    }
+}
+
+/// The candidates (by their index in the original ballot) each ballot
+/// counts for: the ones it ranks first out of those still in the count.
+fn first_choices<T: Clone>(ballot: &Ballot<T>, remaining: &[usize]) -> Vec<Vec<usize>> {
+    ballot
+        .ballots()
+        .map(|row| row.iter().enumerate().filter(|(_, rank)| **rank == 0).map(|(i, _)| remaining[i]).collect())
+        .collect()
+}
+
+/// Adds up the votes that moved from candidates who left the count to the
+/// ones their ballots count for now, or to nobody if they were exhausted.
+/// A ballot that still counts for someone it ranked equally with a
+/// candidate who left was already counting for them, so nothing moves.
+fn transfers(before: &[Vec<usize>], after: &[Vec<usize>], weights: &[f64], remaining: &[usize]) -> Vec<(usize, Option<usize>, f64)> {
+    let mut transfers: Vec<(usize, Option<usize>, f64)> = vec![];
+
+    for ((before, after), weight) in before.iter().zip(after).zip(weights) {
+        let gained: Vec<_> = after.iter().filter(|candidate| !before.contains(candidate)).map(|candidate| Some(*candidate)).collect();
+        let destinations = match after.is_empty() {
+            true => vec![None],
+            false => gained,
+        };
+
+        for from in before.iter().filter(|candidate| !remaining.contains(candidate)) {
+            for to in &destinations {
+                match transfers.iter_mut().find(|(f, t, _)| f == from && t == to) {
+                    Some((_, _, votes)) => *votes += weight,
+                    None => transfers.push((*from, *to, *weight)),
+                }
+            }
+        }
+    }
+
+    transfers
+}
+
+/// How many ballots rank each candidate last. Candidates a ballot didn't
+/// rank are all last on it, so it's split between them, as it is between
+/// candidates ranked equally last.
+fn last_places<T: Clone>(ballot: &Ballot<T>) -> Vec<f64> {
+    let mut last = vec![0.0; ballot.count()];
+
+    for (row, weight) in ballot.ballots().zip(ballot.weights()) {
+        // UNRANKED is the highest rank there is, so it's last if it's there
+        let Some(bottom) = row.iter().copied().max() else {
+            continue;
+        };
+        let tied = row.iter().filter(|rank| **rank == bottom).count();
+
+        for (candidate, _) in row.iter().enumerate().filter(|(_, rank)| **rank == bottom) {
+            last[candidate] += weight / tied as f64;
+        }
+    }
+
+    last
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Ballot;
+
+    #[test]
+    fn eliminates_the_fewest() {
+        // nobody has a majority until c is eliminated and their vote goes to b
+        let rankings = [vec![0, 1, 2], vec![0], vec![1, 0], vec![1, 2], vec![2, 1]];
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c'], &rankings).expect("valid rankings");
+        let (rounds, winner) = super::count(&ballot, Default::default());
+
+        assert_eq!(winner, Some('b'));
+        assert_eq!(rounds[0].eliminated, Some('c'));
+        assert_eq!(rounds[1].tally, vec![('b', 3.0), ('a', 2.0)]);
+        assert_eq!(rounds[1].exhausted, 0.0);
+
+        let transfer = &rounds[0].transfers[0];
+        assert_eq!((transfer.from, transfer.to, transfer.votes), ('c', Some('b'), 1.0));
+    }
+
+    #[test]
+    fn coombs_eliminates_the_most_last() {
+        let rankings = [vec![0, 1, 2], vec![0, 1, 2], vec![2, 1, 0], vec![2, 1, 0], vec![1, 0, 2]];
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c'], &rankings).expect("valid rankings");
+        let (rounds, winner) = super::count_by(&ballot, Default::default(), super::Elimination::MostLast);
+
+        // instant-runoff would eliminate b and elect a, but b is nobody's last
+        assert_eq!((rounds[0].eliminated, rounds[0].eliminated_with), (Some('c'), 3.0));
+        assert_eq!(winner, Some('b'));
+    }
+
+    #[test]
+    fn withdrawals_transfer_next_round() {
+        let rankings = [vec![0, 1, 2], vec![0], vec![1, 0], vec![1, 2], vec![2, 1], vec![2, 0], vec![3, 0]];
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c', 'd'], &rankings).expect("valid rankings");
+        let (rounds, winner) = super::count_withdrawing(&ballot, Default::default(), super::Elimination::FewestFirst, &[(1, 1)]);
+
+        // d goes out first, then b withdraws and their ballots split between a and c
+        assert_eq!((rounds[0].eliminated, &rounds[0].withdrawn), (Some('d'), &vec!['b']));
+        assert_eq!(rounds[1].tally, vec![('a', 4.0), ('c', 3.0)]);
+        assert_eq!(winner, Some('a'));
+    }
+}
@@ -0,0 +1,356 @@
+use serde_json::{json, Map, Value};
+use std::collections::BTreeSet;
+
+use rankit::{borda, bucklin, condorcet, irv, stv};
+
+use crate::display::DisplayNames;
+use crate::rcvis;
+
+/// A round's tally as a list, most votes first.
+fn tally(tally: &[(String, f64)]) -> Value {
+    tally
+        .iter()
+        .map(|(label, votes)| json!({ "candidate": label, "votes": votes }))
+        .collect()
+}
+
+/// The tiered count: a candidate is picked each round, and the rest of the
+/// ballots go on to the next.
+pub fn tiered(rounds: &[rcvis::Round], total: f64) -> Value {
+    let rounds: Vec<_> = rounds
+        .iter()
+        .enumerate()
+        .map(|(i, (winner, votes, others))| {
+            let mut all = vec![(winner.clone(), *votes)];
+            all.extend(others.iter().cloned());
+            let counting: f64 = all.iter().map(|(_, votes)| votes).sum();
+
+            json!({
+                "round": i + 1,
+                "tally": tally(&all),
+                "exhausted": total - counting,
+                "picked": winner,
+            })
+        })
+        .collect();
+    let winners: Vec<_> = rounds.iter().map(|round| round["picked"].clone()).collect();
+
+    json!({ "method": "tiered", "rounds": rounds, "winners": winners })
+}
+
+/// An instant-runoff or Coombs count.
+pub fn irv(method: &str, rounds: &[irv::Round<String>], winner: Option<&str>) -> Value {
+    let rounds: Vec<_> = rounds
+        .iter()
+        .enumerate()
+        .map(|(i, round)| {
+            json!({
+                "round": i + 1,
+                "tally": tally(&round.tally),
+                "exhausted": round.exhausted,
+                "eliminated": round.eliminated,
+                "eliminated_with": round.eliminated.as_ref().map(|_| round.eliminated_with),
+                "tied": round.tied,
+                "withdrawn": round.withdrawn,
+                "transfers": round
+                    .transfers
+                    .iter()
+                    .map(|transfer| json!({ "from": transfer.from, "to": transfer.to, "votes": transfer.votes }))
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+
+    json!({ "method": method, "rounds": rounds, "winner": winner })
+}
+
+/// A single transferable vote count.
+pub fn stv(count: &stv::Count<String>, seats: usize) -> Value {
+    let rounds: Vec<_> = count
+        .rounds
+        .iter()
+        .enumerate()
+        .map(|(i, round)| {
+            json!({
+                "round": i + 1,
+                "tally": tally(&round.tally),
+                "exhausted": round.exhausted,
+                "elected": round.elected,
+                "transfer_value": round.transfer_value,
+                "drawn": round.drawn,
+                "eliminated": round.eliminated,
+                "tied": round.tied,
+            })
+        })
+        .collect();
+
+    let seat_by_seat: Vec<_> = stv::seats(count)
+        .iter()
+        .map(|seat| {
+            let gained: Vec<_> = seat
+                .gained
+                .iter()
+                .map(|gain| json!({ "from": gain.from, "votes": gain.votes, "surplus": gain.surplus }))
+                .collect();
+
+            json!({
+                "candidate": seat.candidate,
+                "round": seat.round,
+                "votes": seat.votes,
+                "reached_quota": seat.reached_quota,
+                "quota_attainment": seat.votes / count.quota,
+                "surplus": seat.reached_quota.then_some(seat.votes - count.quota),
+                "gained": gained,
+            })
+        })
+        .collect();
+
+    json!({
+        "method": "stv",
+        "seats": seats,
+        "quota": count.quota,
+        "rounds": rounds,
+        "seat_by_seat": seat_by_seat,
+        "elected": count.elected,
+        "skipped": count
+            .skipped
+            .iter()
+            .map(|skipped| json!({ "candidate": skipped.candidate, "round": skipped.round, "constraint": skipped.constraint }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// A Bucklin count, with each round's totals down to its depth.
+pub fn bucklin(rounds: &[bucklin::Round<String>], winner: Option<&str>) -> Value {
+    let rounds: Vec<_> = rounds
+        .iter()
+        .map(|round| json!({ "round": round.depth, "depth": round.depth, "tally": tally(&round.tally) }))
+        .collect();
+
+    json!({ "method": "bucklin", "rounds": rounds, "winner": winner })
+}
+
+/// A Baldwin or Nanson count, with each round's Borda scores.
+pub fn borda_elimination(method: &str, rounds: &[borda::Round<String>], winner: Option<&str>) -> Value {
+    let rounds: Vec<_> = rounds
+        .iter()
+        .enumerate()
+        .map(|(i, round)| {
+            let scores: Vec<_> = round
+                .scores
+                .iter()
+                .map(|(label, score)| json!({ "candidate": label, "score": score }))
+                .collect();
+
+            json!({ "round": i + 1, "scores": scores, "eliminated": round.eliminated, "tied": round.tied })
+        })
+        .collect();
+
+    json!({ "method": method, "rounds": rounds, "winner": winner })
+}
+
+/// The head-to-head result for --condorcet: the Condorcet winner if there is
+/// one, and otherwise the Smith set and any cycles, each as the candidates in
+/// order with how every one of them beat the next.
+pub fn condorcet(pairwise: &condorcet::Pairwise, labels: &[String]) -> Value {
+    let cycles: Vec<_> = pairwise
+        .cycles()
+        .iter()
+        .map(|cycle| {
+            cycle
+                .iter()
+                .enumerate()
+                .map(|(i, &a)| {
+                    let b = cycle[(i + 1) % cycle.len()];
+
+                    json!({ "winner": labels[a], "loser": labels[b], "for": pairwise.prefer[a][b], "against": pairwise.prefer[b][a] })
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let smith_set: Vec<_> = pairwise.smith_set().into_iter().map(|i| &labels[i]).collect();
+
+    json!({ "winner": pairwise.winner().map(|i| &labels[i]), "smith_set": smith_set, "cycles": cycles })
+}
+
+/// Each candidate's number, for --candidate-ids, or nothing if they weren't
+/// numbered.
+pub fn ids(labels: &[String], names: &DisplayNames) -> Option<Value> {
+    let ids: Map<_, _> = labels.iter().filter_map(|label| Some((label.clone(), json!(names.id(label)?)))).collect();
+
+    (!ids.is_empty()).then_some(Value::Object(ids))
+}
+
+/// One row of the CSV table, for a candidate in a round.
+pub struct Row {
+    pub round: usize,
+    pub candidate: String,
+    /// Their votes, or their Borda score under Baldwin and Nanson
+    pub votes: f64,
+    /// What happened to them in the round: elected, picked, eliminated,
+    /// withdrawn or continuing. Exhausted votes are a row of their own.
+    pub status: &'static str,
+}
+
+/// Flattens a count made by any of the functions above into a row for every
+/// candidate in every round, so that the table and the JSON always agree.
+pub fn rows(result: &Value) -> Vec<Row> {
+    // a label, or a list of them, as a set
+    fn labels(value: &Value) -> BTreeSet<&str> {
+        match value {
+            Value::Array(values) => values.iter().filter_map(Value::as_str).collect(),
+            value => value.as_str().into_iter().collect(),
+        }
+    }
+
+    let empty = vec![];
+    let rounds = result["rounds"].as_array().unwrap_or(&empty);
+    let winner = result["winner"].as_str();
+    let mut rows = vec![];
+
+    for (i, round) in rounds.iter().enumerate() {
+        let number = round["round"].as_u64().map_or(i + 1, |round| round as usize);
+        let elected = labels(&round["elected"]);
+        let picked = labels(&round["picked"]);
+        let eliminated = labels(&round["eliminated"]);
+        let withdrawn = labels(&round["withdrawn"]);
+        let last = i + 1 == rounds.len();
+
+        let (entries, key) = match round.get("scores") {
+            Some(scores) => (scores, "score"),
+            None => (&round["tally"], "votes"),
+        };
+
+        for entry in entries.as_array().unwrap_or(&empty) {
+            let candidate = entry["candidate"].as_str().unwrap_or_default();
+            let status = match candidate {
+                _ if elected.contains(candidate) || (last && winner == Some(candidate)) => "elected",
+                _ if picked.contains(candidate) => "picked",
+                _ if eliminated.contains(candidate) => "eliminated",
+                _ if withdrawn.contains(candidate) => "withdrawn",
+                _ => "continuing",
+            };
+
+            rows.push(Row {
+                round: number,
+                candidate: candidate.to_owned(),
+                votes: entry[key].as_f64().unwrap_or_default(),
+                status,
+            });
+        }
+
+        if let Some(exhausted) = round["exhausted"].as_f64() {
+            rows.push(Row { round: number, candidate: String::new(), votes: exhausted, status: "exhausted" });
+        }
+    }
+
+    rows
+}
+
+/// When each candidate left the count or won, in the order it happened:
+/// the round, the candidate, and whether they were elected, picked,
+/// eliminated or withdrew. Candidates still in at the end without winning
+/// were defeated in the last round.
+pub fn order(result: &Value) -> Vec<(usize, String, &'static str)> {
+    let rows = rows(result);
+    let last = rows.last().map_or(0, |row| row.round);
+    let mut order: Vec<(usize, String, &'static str)> = vec![];
+
+    for row in rows.iter().filter(|row| !row.candidate.is_empty()) {
+        if order.iter().any(|(_, candidate, _)| *candidate == row.candidate) {
+            continue;
+        }
+
+        match row.status {
+            "continuing" if row.round == last => order.push((row.round, row.candidate.clone(), "defeated")),
+            "continuing" => {}
+            status => order.push((row.round, row.candidate.clone(), status)),
+        }
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod test {
+    use rankit::{condorcet, irv, Ballot};
+    use serde_json::json;
+
+    #[test]
+    fn rows_follow_the_rounds() {
+        let result = json!({
+            "method": "irv",
+            "rounds": [
+                { "round": 1, "tally": [{ "candidate": "a", "votes": 2.0 }, { "candidate": "b", "votes": 1.0 }], "exhausted": 1.0, "eliminated": "b", "withdrawn": [] },
+                { "round": 2, "tally": [{ "candidate": "a", "votes": 3.0 }], "exhausted": 1.0, "eliminated": null, "withdrawn": [] },
+            ],
+            "winner": "a",
+        });
+        let rows: Vec<_> = super::rows(&result).into_iter().map(|row| (row.round, row.candidate, row.status)).collect();
+
+        assert_eq!(rows[1], (1, "b".to_owned(), "eliminated"));
+        assert_eq!(rows[2], (1, String::new(), "exhausted"));
+        assert_eq!(rows[3], (2, "a".to_owned(), "elected"));
+
+        let order = super::order(&result);
+        assert_eq!(order, [(1, "b".to_owned(), "eliminated"), (2, "a".to_owned(), "elected")]);
+    }
+
+    #[test]
+    fn whole_count() {
+        let rankings = [vec![0], vec![0], vec![0], vec![1, 0], vec![2], vec![2]];
+        let ballot = Ballot::from_rankings(vec!["a".to_owned(), "b".to_owned(), "c".to_owned()], &rankings).expect("valid rankings");
+        let (rounds, winner) = irv::count(&ballot, Default::default());
+        let result = super::irv("irv", &rounds, winner.as_deref());
+
+        // b's one ballot goes on to a, giving them a majority
+        assert_eq!(result["rounds"][0]["eliminated"], "b");
+        assert_eq!(result["rounds"][0]["transfers"], json!([{ "from": "b", "to": "a", "votes": 1.0 }]));
+        assert_eq!(result["rounds"][1]["tally"][0], json!({ "candidate": "a", "votes": 4.0 }));
+        assert_eq!(result["winner"], "a");
+
+        let rounds = vec![("a".to_owned(), 3.0, vec![("b".to_owned(), 2.0)]), ("b".to_owned(), 4.0, vec![])];
+        let result = super::tiered(&rounds, 6.0);
+        assert_eq!(result["rounds"][0]["exhausted"], 1.0);
+        assert_eq!(result["rounds"][1]["exhausted"], 2.0);
+        assert_eq!(result["winners"], json!(["a", "b"]));
+    }
+
+    #[test]
+    fn elimination_order() {
+        let rankings = [vec![0], vec![0], vec![0], vec![1, 0], vec![2], vec![2]];
+        let ballot = Ballot::from_rankings(vec!["a".to_owned(), "b".to_owned(), "c".to_owned()], &rankings).expect("valid rankings");
+        let (rounds, winner) = irv::count(&ballot, Default::default());
+        let result = super::irv("irv", &rounds, winner.as_deref());
+
+        // c was never eliminated, but lost in the last round
+        let order = super::order(&result);
+        assert_eq!(order, [(1, "b".to_owned(), "eliminated"), (2, "a".to_owned(), "elected"), (2, "c".to_owned(), "defeated")]);
+
+        // the tiered count picks someone every round, down to the last
+        let rounds = vec![("a".to_owned(), 3.0, vec![("b".to_owned(), 2.0)]), ("b".to_owned(), 4.0, vec![])];
+        let order = super::order(&super::tiered(&rounds, 6.0));
+        assert_eq!(order, [(1, "a".to_owned(), "picked"), (2, "b".to_owned(), "picked")]);
+    }
+
+    #[test]
+    fn condorcet_cycle() {
+        let rankings = [vec![0, 1, 2], vec![1, 2, 0], vec![2, 0, 1]];
+        let labels = vec!["a".to_owned(), "b".to_owned(), "c".to_owned()];
+        let ballot = Ballot::from_rankings(labels.clone(), &rankings).expect("valid rankings");
+        let result = super::condorcet(&condorcet::Pairwise::new(&ballot), &labels);
+
+        assert_eq!(result["winner"], json!(null));
+        assert_eq!(result["smith_set"], json!(["a", "b", "c"]));
+        assert_eq!(result["cycles"][0].as_array().map(Vec::len), Some(3));
+        assert_eq!(result["cycles"][0][0]["for"], 2.0);
+    }
+
+    #[test]
+    fn candidate_ids() {
+        let labels = vec!["a".to_owned(), "b".to_owned()];
+
+        assert_eq!(super::ids(&labels, &Default::default()), None);
+        assert_eq!(super::ids(&labels, &crate::display::DisplayNames::default().with_ids(&labels)), Some(json!({ "a": 1, "b": 2 })));
+    }
+}
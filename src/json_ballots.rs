@@ -0,0 +1,112 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::io::Read;
+
+use crate::Ballot;
+
+/// Reads ballots stored as a JSON array, the way voting web apps tend to keep
+/// them. Each ballot is either an object mapping candidates to their ranks,
+/// like `{"Alice": 1, "Bob": 2}`, where equal ranks are ties and candidates
+/// that are missing or null are unranked, or an array of candidate names
+/// from most to least preferred, like `["Alice", "Bob"]`, where a nested
+/// array holds candidates ranked equally. Candidates are listed in the order
+/// they first appear.
+pub fn read(reader: impl Read) -> Result<Ballot<String>> {
+    let ballots: Value = serde_json::from_reader(std::io::BufReader::new(reader)).context("couldn't read the JSON ballots")?;
+    let ballots = ballots.as_array().context("expected an array of ballots")?;
+
+    let mut labels: Vec<String> = vec![];
+    let mut rankings = vec![];
+
+    for (i, ballot) in ballots.iter().enumerate().map(|(i, ballot)| (i + 1, ballot)) {
+        let groups = match ballot {
+            Value::Object(ranks) => by_rank(ranks).with_context(|| format!("ballot {i}"))?,
+            Value::Array(order) => in_order(order).with_context(|| format!("ballot {i}"))?,
+            _ => return Err(anyhow!("ballot {i} is neither an object of ranks nor an array of names")),
+        };
+
+        let mut index = |name: &str| match labels.iter().position(|label| label == name) {
+            Some(candidate) => candidate,
+            None => {
+                labels.push(name.to_owned());
+                labels.len() - 1
+            }
+        };
+
+        rankings.push(groups.into_iter().map(|group| group.into_iter().map(&mut index).collect()).collect::<Vec<Vec<usize>>>());
+    }
+
+    Ballot::from_tied_rankings(labels, &rankings).map_err(|_| anyhow!("a candidate is ranked twice"))
+}
+
+/// Groups the candidates of a `{"Alice": 1, "Bob": 2}` ballot by rank. Only
+/// the order of the ranks matters, so gaps between them are fine.
+fn by_rank(ranks: &serde_json::Map<String, Value>) -> Result<Vec<Vec<&str>>> {
+    let mut ranked = vec![];
+
+    for (name, rank) in ranks {
+        match rank {
+            Value::Null => {}
+            rank => {
+                let rank = rank.as_u64().filter(|rank| *rank > 0).with_context(|| format!("{name} has the rank {rank}, not a whole number from 1"))?;
+                ranked.push((rank, name.as_str()));
+            }
+        }
+    }
+
+    ranked.sort_by_key(|(rank, _)| *rank);
+
+    let mut groups: Vec<Vec<&str>> = vec![];
+    let mut last = None;
+
+    for (rank, name) in ranked {
+        match groups.last_mut() {
+            Some(group) if last == Some(rank) => group.push(name),
+            _ => groups.push(vec![name]),
+        }
+
+        last = Some(rank);
+    }
+
+    Ok(groups)
+}
+
+/// Reads a `["Alice", ["Bob", "Carol"]]` ballot as its groups of candidates.
+fn in_order(order: &[Value]) -> Result<Vec<Vec<&str>>> {
+    let mut groups: Vec<Vec<&str>> = vec![];
+
+    for entry in order {
+        let group = match entry {
+            Value::Array(tied) => tied.iter().map(name).collect::<Result<Vec<_>>>()?,
+            entry => vec![name(entry)?],
+        };
+
+        if let Some(twice) = group.iter().find(|candidate| groups.iter().flatten().chain(&group).filter(|c| c == candidate).count() > 1) {
+            return Err(anyhow!("{twice} is ranked twice"));
+        }
+
+        if !group.is_empty() {
+            groups.push(group);
+        }
+    }
+
+    Ok(groups)
+}
+
+fn name(value: &Value) -> Result<&str> {
+    value.as_str().with_context(|| format!("expected a candidate's name, found {value}"))
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn objects_and_arrays() {
+        let data = r#"[{"Alice": 1, "Bob": 2, "Carol": null}, ["Carol", ["Alice", "Bob"]], {"Bob": 3, "Carol": 3}, []]"#;
+        let ballot = super::read(data.as_bytes()).expect("valid ballots");
+
+        assert_eq!(ballot.labels(), ["Alice", "Bob", "Carol"]);
+        assert_eq!(ballot.ballots().nth(1).as_deref(), Some(&[1, 1, 0][..]));
+        assert_eq!(ballot.tier(), vec![1.0, 1.0, 2.0]);
+        assert!(super::read(r#"[["Alice", "Alice"]]"#.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,689 @@
+//! The counting engine behind rankit, without the command line. Only the
+//! `formats` feature (for reading CVR exports) pulls in anything beyond the
+//! standard library, so it can be embedded with `default-features = false`.
+//!
+//! Without the `std` feature the engine only needs `alloc`, so it also runs
+//! in WASM sandboxes and on embedded kiosks.
+//!
+//! Ballots are built with `Ballot::new` (or `ranks::read` for a CSV), and
+//! counted with `Ballot::runoff`, which gives the winner of each tier with
+//! their votes and everyone else's:
+//!
+//! ```
+//! use rankit::Ballot;
+//!
+//! // each ranking lists candidates by index, from most to least preferred
+//! let ballot = Ballot::from_rankings(vec!["a", "b", "c"], &[vec![0, 1, 2], vec![1, 0], vec![0, 2, 1]]).unwrap();
+//! let winners: Vec<_> = ballot.runoff().map(|(winner, _, _)| winner).collect();
+//!
+//! assert_eq!(winners, ["a", "b", "c"]);
+//! ```
+//!
+//! The `rayon` feature never changes results, down to the last bit of a
+//! weighted count: anything counted in parallel is split into fixed blocks
+//! whose counts are added together in the same order as without it.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::{borrow::Cow, collections::BTreeMap, format, string::String, vec, vec::Vec};
+
+use rounding::Rounding;
+
+pub mod approval;
+pub mod audit;
+pub mod blt;
+pub mod borda;
+pub mod bucklin;
+pub mod clones;
+pub mod condorcet;
+#[cfg(feature = "formats")]
+pub mod cvr;
+pub mod demo;
+pub mod finalists;
+#[cfg(feature = "formats")]
+pub mod handcount;
+#[cfg(feature = "formats")]
+pub mod inspect;
+pub mod irv;
+#[cfg(feature = "formats")]
+pub mod json_ballots;
+pub mod notation;
+#[cfg(feature = "formats")]
+pub mod preflib;
+#[cfg(feature = "formats")]
+pub mod ranks;
+pub mod retention;
+pub mod rng;
+pub mod rounding;
+pub mod score;
+pub mod sensitivity;
+pub mod stv;
+pub mod trace;
+
+/// Formats a (possibly weighted) amount of votes, without decimals if it's whole.
+pub fn format_votes(votes: f64) -> String {
+    // fract() needs std
+    if votes % 1.0 == 0.0 {
+        format!("{votes}")
+    } else {
+        format!("{votes:.2}")
+    }
+}
+
+/// Every ballot in a contest, as each one's rank for every candidate.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Ballot<T: Clone> {
+    /// The names of the candidates
+    labels: Vec<T>,
+    /// The raw rankings. Every rank r is 0 <= r < width, or UNRANKED
+    votes: Ranks,
+    /// How much each ballot counts for, which is 1 unless they're weighted
+    weights: Vec<f64>,
+    /// How a ballot's vote goes to candidates it ranked equally first
+    #[cfg_attr(feature = "serde", serde(skip))]
+    ties: Ties,
+}
+
+/// How a ballot's vote is counted when it ranks several candidates equally
+/// first. Head-to-head, candidates ranked equally are never preferred to each
+/// other either way.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Ties {
+    /// Each of them gets the whole vote
+    #[default]
+    Whole,
+    /// They split the vote evenly between them
+    Split,
+}
+
+/// How the ranks are stored. A poll with hundreds of candidates where voters
+/// only rank a few would be almost all UNRANKED as a matrix, so ballots like
+/// that only keep the candidates they ranked.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(untagged))]
+enum Ranks {
+    /// Every ballot's rank for every candidate, one ballot after another
+    Dense(Vec<usize>),
+    /// Each ballot's (candidate, rank) pairs, sorted by candidate, for only
+    /// the candidates it ranked
+    Sparse(Vec<Vec<(usize, usize)>>),
+}
+
+impl Ranks {
+    /// A single ballot's ranks, given how many candidates there are
+    fn row(&self, count: usize, ballot: usize) -> Cow<'_, [usize]> {
+        match self {
+            Self::Dense(votes) => Cow::Borrowed(&votes[ballot * count..(ballot + 1) * count]),
+            Self::Sparse(rows) => Cow::Owned(expand(&rows[ballot], count)),
+        }
+    }
+}
+
+/// Turns a sparse ballot's (candidate, rank) pairs into its rank for every
+/// candidate
+fn expand(ranked: &[(usize, usize)], count: usize) -> Vec<usize> {
+    let mut row = vec![UNRANKED; count];
+
+    for (candidate, rank) in ranked.iter().copied() {
+        row[candidate] = rank;
+    }
+
+    row
+}
+
+/// Ballots suit sparse storage when there are at least this many
+/// candidates...
+const SPARSE_CANDIDATES: usize = 32;
+/// ...and at most 1 in this many of the ranks are filled in
+const SPARSE_FILL: usize = 4;
+
+/// How a `Ballot` keeps its ranks. Results are the same either way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Storage {
+    /// Every ballot's rank for every candidate, even unranked ones
+    Dense,
+    /// Only the candidates each ballot ranked
+    Sparse,
+}
+
+impl Storage {
+    /// Which storage is smaller, given how many ranks out of how many
+    /// candidates on how many ballots are filled in
+    fn suited(count: usize, filled: usize, ballots: usize) -> Self {
+        match count >= SPARSE_CANDIDATES && filled * SPARSE_FILL <= count * ballots {
+            true => Self::Sparse,
+            false => Self::Dense,
+        }
+    }
+}
+
+/// The rank of a candidate that a voter didn't rank at all. Once every
+/// candidate a ballot ranked is gone, the ballot is exhausted.
+pub const UNRANKED: usize = usize::MAX;
+
+impl<T: Clone> Ballot<T> {
+    /// Builds ballots from every ballot's ranks one after another, each in
+    /// the same order as the labels. The labels and votes are given back if
+    /// the votes don't fit them.
+    pub fn new(labels: Vec<T>, votes: Vec<usize>) -> Result<Self, (Vec<T>, Vec<usize>)> {
+        // without candidates there can't be any ranks, but no ballots is fine
+        let ballots = match labels.len() {
+            0 if votes.is_empty() => Some(0),
+            count => votes.len().checked_div(count).filter(|_| votes.len().is_multiple_of(count)),
+        };
+
+        match ballots {
+            Some(ballots) if votes.iter().copied().all(|v| v < labels.len() || v == UNRANKED) => {
+                let weights = vec![1.0; ballots];
+                let votes = Ranks::Dense(votes);
+
+                Ok(Self { labels, votes, weights, ties: Ties::Whole })
+            }
+            _ => Err((labels, votes)),
+        }
+    }
+
+    /// Builds ballots from rankings listing candidates (by index) from most to
+    /// least preferred. Candidates missing from a ranking are unranked.
+    pub fn from_rankings(labels: Vec<T>, rankings: &[Vec<usize>]) -> Result<Self, Vec<T>> {
+        let entries = rankings
+            .iter()
+            .map(|ranking| ranking.iter().copied().enumerate().map(|(rank, candidate)| (candidate, rank)).collect())
+            .collect();
+
+        Self::from_entries(labels, entries)
+    }
+
+    /// Like `from_rankings`, but each ranking lists groups of candidates
+    /// that were ranked equally, from most to least preferred.
+    pub fn from_tied_rankings(labels: Vec<T>, rankings: &[Vec<Vec<usize>>]) -> Result<Self, Vec<T>> {
+        let entries = rankings
+            .iter()
+            .map(|ranking| {
+                ranking
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(rank, tied)| tied.iter().map(move |candidate| (*candidate, rank)))
+                    .collect()
+            })
+            .collect();
+
+        Self::from_entries(labels, entries)
+    }
+
+    /// Builds ballots from each one's (candidate, rank) pairs, stored sparsely
+    /// if that's much smaller than the full matrix
+    fn from_entries(labels: Vec<T>, mut entries: Vec<Vec<(usize, usize)>>) -> Result<Self, Vec<T>> {
+        let count = labels.len();
+
+        for row in entries.iter_mut() {
+            row.sort_unstable();
+
+            let duplicate = row.windows(2).any(|pair| pair[0].0 == pair[1].0);
+
+            if duplicate || row.last().is_some_and(|(candidate, _)| *candidate >= count) {
+                return Err(labels);
+            }
+        }
+
+        let filled: usize = entries.iter().map(Vec::len).sum();
+
+        if Storage::suited(count, filled, entries.len()) == Storage::Sparse {
+            let weights = vec![1.0; entries.len()];
+            let votes = Ranks::Sparse(entries);
+
+            return Ok(Self { labels, votes, weights, ties: Ties::Whole });
+        }
+
+        let mut votes = vec![UNRANKED; count * entries.len()];
+
+        for (row, ranked) in votes.chunks_mut(count.max(1)).zip(&entries) {
+            for (candidate, rank) in ranked.iter().copied() {
+                row[candidate] = rank;
+            }
+        }
+
+        Self::new(labels, votes).map_err(|(labels, _)| labels)
+    }
+
+    /// Adds another set of ballots to these ones. Any candidates they have
+    /// that these don't are added, unranked on the ballots already here.
+    pub fn append(&mut self, other: Ballot<T>)
+    where
+        T: PartialEq,
+    {
+        let old_count = self.count();
+        let positions: Vec<_> = other
+            .labels
+            .iter()
+            .map(|label| match self.labels.iter().position(|l| l == label) {
+                Some(position) => position,
+                None => {
+                    self.labels.push(label.clone());
+                    self.labels.len() - 1
+                }
+            })
+            .collect();
+        let count = self.count();
+
+        match &mut self.votes {
+            Ranks::Dense(votes) => {
+                if count != old_count {
+                    let added = count - old_count;
+
+                    *votes = votes
+                        .chunks(old_count.max(1))
+                        .flat_map(|row| row.iter().copied().chain(core::iter::repeat_n(UNRANKED, added)))
+                        .collect();
+                }
+
+                for row in other.ballots() {
+                    let mut widened = vec![UNRANKED; count];
+
+                    for (rank, position) in row.iter().zip(&positions) {
+                        widened[*position] = *rank;
+                    }
+
+                    votes.extend(widened);
+                }
+            }
+            // sparse ballots don't list unranked candidates, so new ones
+            // don't change the ballots already here
+            Ranks::Sparse(rows) => {
+                for row in other.ballots() {
+                    let mut ranked: Vec<_> = row
+                        .iter()
+                        .zip(&positions)
+                        .filter(|(rank, _)| **rank != UNRANKED)
+                        .map(|(rank, position)| (*position, *rank))
+                        .collect();
+
+                    ranked.sort_unstable();
+                    rows.push(ranked);
+                }
+            }
+        }
+
+        self.weights.extend(other.weights);
+    }
+
+    /// Makes each ballot count for its weight instead of 1.
+    pub fn with_weights(mut self, weights: Vec<f64>) -> Result<Self, Vec<f64>> {
+        if weights.len() == self.ballot_count() && weights.iter().all(|w| w.is_finite() && *w >= 0.0) {
+            self.weights = weights;
+
+            Ok(self)
+        } else {
+            Err(weights)
+        }
+    }
+
+    /// Counts votes for candidates ranked equally first by `ties`, instead
+    /// of giving each of them the whole vote.
+    pub fn with_ties(mut self, ties: Ties) -> Self {
+        self.ties = ties;
+        self
+    }
+
+    /// How much each ballot counts for
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    /// The amount of candidates
+    pub fn count(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// The names of the candidates
+    pub fn labels(&self) -> &[T] {
+        &self.labels
+    }
+
+    /// How the ranks are stored. Ballots built from rankings are stored
+    /// sparsely when there are many candidates and most ballots only rank a
+    /// few.
+    pub fn storage(&self) -> Storage {
+        match self.votes {
+            Ranks::Dense(_) => Storage::Dense,
+            Ranks::Sparse(_) => Storage::Sparse,
+        }
+    }
+
+    /// Which storage takes up less room for these ballots
+    pub fn suited_storage(&self) -> Storage {
+        let filled = match &self.votes {
+            Ranks::Dense(votes) => votes.iter().filter(|rank| **rank != UNRANKED).count(),
+            Ranks::Sparse(rows) => rows.iter().map(Vec::len).sum(),
+        };
+
+        Storage::suited(self.count(), filled, self.ballot_count())
+    }
+
+    /// Moves the ranks into another kind of storage, if they aren't in it
+    /// already
+    pub fn with_storage(mut self, storage: Storage) -> Self {
+        self.votes = match (storage, self.votes) {
+            (Storage::Sparse, Ranks::Dense(votes)) => Ranks::Sparse(
+                votes
+                    .chunks(self.labels.len().max(1))
+                    .map(|row| {
+                        row.iter()
+                            .copied()
+                            .enumerate()
+                            .filter(|(_, rank)| *rank != UNRANKED)
+                            .collect()
+                    })
+                    .collect(),
+            ),
+            (Storage::Dense, Ranks::Sparse(rows)) => {
+                Ranks::Dense(rows.iter().flat_map(|ranked| expand(ranked, self.labels.len())).collect())
+            }
+            (_, votes) => votes,
+        };
+
+        self
+    }
+
+    /// Roughly how many bytes the ranks and weights take up
+    pub fn size(&self) -> usize {
+        self.size_in(self.storage())
+    }
+
+    /// Roughly how many bytes the ranks and weights would take up in another
+    /// kind of storage
+    pub fn size_in(&self, storage: Storage) -> usize {
+        let ballots = self.ballot_count();
+        let weights = ballots * size_of::<f64>();
+
+        let ranks = match (storage, &self.votes) {
+            (Storage::Dense, _) => self.count() * ballots * size_of::<usize>(),
+            (Storage::Sparse, Ranks::Sparse(rows)) => {
+                ballots * size_of::<Vec<(usize, usize)>>() + rows.iter().map(Vec::len).sum::<usize>() * size_of::<(usize, usize)>()
+            }
+            (Storage::Sparse, Ranks::Dense(votes)) => {
+                let filled = votes.iter().filter(|rank| **rank != UNRANKED).count();
+
+                ballots * size_of::<Vec<(usize, usize)>>() + filled * size_of::<(usize, usize)>()
+            }
+        };
+
+        ranks + weights
+    }
+
+    /// Merges identical ballots into one, weighted by all of theirs, in the
+    /// order each was first seen. Results are the same, but ballots can't be
+    /// told apart by row afterwards.
+    pub fn deduplicated(self) -> Self {
+        let storage = self.storage();
+        let mut seen: BTreeMap<Vec<(usize, usize)>, usize> = BTreeMap::new();
+        let mut rows: Vec<Vec<(usize, usize)>> = vec![];
+        let mut weights = vec![];
+
+        for (row, weight) in self.ballots().zip(&self.weights) {
+            let ranked: Vec<_> = row.iter().copied().enumerate().filter(|(_, rank)| *rank != UNRANKED).collect();
+
+            match seen.get(&ranked) {
+                Some(i) => weights[*i] += weight,
+                None => {
+                    seen.insert(ranked.clone(), rows.len());
+                    rows.push(ranked);
+                    weights.push(*weight);
+                }
+            }
+        }
+
+        let deduplicated = Self {
+            labels: self.labels,
+            votes: Ranks::Sparse(rows),
+            weights,
+            ties: self.ties,
+        };
+
+        deduplicated.with_storage(storage)
+    }
+
+    /// The amount of individual ballots that were cast
+    pub fn ballot_count(&self) -> usize {
+        match &self.votes {
+            Ranks::Dense(votes) => votes.len().checked_div(self.count()).unwrap_or(0),
+            Ranks::Sparse(rows) => rows.len(),
+        }
+    }
+
+    /// A single ballot's ranks, in the same order as the labels
+    pub fn row(&self, ballot: usize) -> Cow<'_, [usize]> {
+        self.votes.row(self.count(), ballot)
+    }
+
+    /// Each individual ballot's ranks, in the same order as the labels
+    pub fn ballots(&self) -> impl Iterator<Item = Cow<'_, [usize]>> + '_ {
+        (0..self.ballot_count()).map(|ballot| self.row(ballot))
+    }
+
+    /// The candidate (by index) that a single ballot ranked highest, unless
+    /// it didn't rank anyone
+    pub fn first_preference(row: &[usize]) -> Option<usize> {
+        (0..row.len())
+            .filter(|i| row[*i] != UNRANKED)
+            .min_by_key(|i| row[*i])
+    }
+
+    /// The labels of a single ballot, from highest ranked to lowest
+    pub fn ranking(&self, ballot: usize) -> impl Iterator<Item = &T> + '_ {
+        let row = self.row(ballot);
+        let mut order: Vec<_> = (0..self.count()).filter(|i| row[*i] != UNRANKED).collect();
+
+        order.sort_by_key(|i| row[*i]);
+        order.into_iter().map(|i| &self.labels[i])
+    }
+
+    /// Calculates each tier of an instant-runoff vote
+    pub fn runoff(self) -> impl Iterator<Item = (T, f64, Vec<(T, f64)>)> {
+        self.runoff_rounded(Rounding::Exact)
+    }
+
+    /// Like `runoff`, but with every tier's counts rounded before they're
+    /// compared
+    pub fn runoff_rounded(mut self, rounding: Rounding) -> impl Iterator<Item = (T, f64, Vec<(T, f64)>)> {
+        // According to R I G O R O U S testing (my head), this could
+        // just be implemented by summing the ranks of votes that each
+        // candidate gets, and then sorting the candidates according
+        // to their vote counts.
+        //
+        // HOWEVER,
+        //
+        // that means each tier of votes cannot be counted i.e. only
+        // the final result is known. Knowing the results of each
+        // tier of vote makes it much easier to understand how the
+        // results came to be.
+
+        core::iter::from_fn(move || {
+            let mut tier: Vec<_> = self.tier().into_iter().map(|votes| rounding.apply(votes)).collect();
+            let winner_index = Self::pick(&tier)?;
+
+            let winner_label = self.take(winner_index);
+            let winner_count = tier.remove(winner_index);
+            let data: Vec<_> = self.labels.iter().cloned().zip(tier).collect();
+
+            Some((winner_label, winner_count, data))
+        })
+    }
+
+    /// How many first preferences each remaining candidate has
+    pub fn tier(&self) -> Vec<f64> {
+        let count = self.count();
+
+        if self.ties == Ties::Split {
+            let mut tier = vec![0.0; count];
+
+            for (row, weight) in self.ballots().zip(&self.weights) {
+                let firsts: Vec<_> = (0..row.len()).filter(|candidate| row[*candidate] == 0).collect();
+
+                for candidate in firsts.iter().copied() {
+                    tier[candidate] += weight / firsts.len() as f64;
+                }
+            }
+
+            return tier;
+        }
+
+        match &self.votes {
+            Ranks::Dense(votes) => (0..count)
+                .map(|i| {
+                    votes
+                        .iter()
+                        .skip(i)
+                        .step_by(count)
+                        .zip(&self.weights)
+                        .filter(|(vote_rank, _)| **vote_rank == 0)
+                        .fold(0.0, |total, (_, weight)| total + weight)
+                })
+                .collect(),
+            // adds up each candidate's votes in ballot order, like the
+            // columns above, so both come out exactly the same
+            Ranks::Sparse(rows) => {
+                let mut tier = vec![0.0; count];
+
+                for (row, weight) in rows.iter().zip(&self.weights) {
+                    for (candidate, _) in row.iter().filter(|(_, rank)| *rank == 0) {
+                        tier[*candidate] += weight;
+                    }
+                }
+
+                tier
+            }
+        }
+    }
+
+    /// The candidate (by index) that the runoff picks from a tier, if there
+    /// are any left
+    pub fn pick(tier: &[f64]) -> Option<usize> {
+        (0..tier.len()).max_by(|a, b| tier[*a].total_cmp(&tier[*b]))
+    }
+
+    /// Removes a candidate from every ballot, moving everyone ranked below
+    /// them up by one, unless someone else was ranked equally with them
+    pub fn take(&mut self, index: usize) -> T {
+        let count = self.count();
+
+        match &mut self.votes {
+            Ranks::Dense(votes) => {
+                for row in votes.chunks_mut(count.max(1)) {
+                    let winner_rank = row[index];
+
+                    if (0..row.len()).any(|candidate| candidate != index && row[candidate] == winner_rank) {
+                        continue;
+                    }
+
+                    for choice in row
+                        .iter_mut()
+                        .filter(|rank| **rank > winner_rank && **rank != UNRANKED)
+                    {
+                        *choice -= 1;
+                    }
+                }
+
+                for i in (0..votes.len()).rev().filter(|i| i % count == index) {
+                    votes.remove(i);
+                }
+            }
+            Ranks::Sparse(rows) => {
+                for row in rows.iter_mut() {
+                    if let Ok(position) = row.binary_search_by_key(&index, |(candidate, _)| *candidate) {
+                        let (_, winner_rank) = row.remove(position);
+                        let shared = row.iter().any(|(_, rank)| *rank == winner_rank);
+
+                        for (_, rank) in row.iter_mut().filter(|(_, rank)| !shared && *rank > winner_rank) {
+                            *rank -= 1;
+                        }
+                    }
+
+                    // everyone after them in the labels moves along by one
+                    for (candidate, _) in row.iter_mut().filter(|(candidate, _)| *candidate > index) {
+                        *candidate -= 1;
+                    }
+                }
+            }
+        }
+
+        self.labels.remove(index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn three_example() {
+        let labels = vec![0, 1, 2];
+        let values: Vec<_> = vec![
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 2, 0],
+            [1, 0, 2],
+            [2, 0, 1],
+            [2, 1, 0],
+            [0, 2, 1],
+            [0, 2, 1],
+            [2, 0, 1],
+        ]
+        .into_iter()
+        .flatten() // i put arrays and then flatten anyway so rustfmt doesn't put 50 billion numbers on one line
+        .collect();
+        let winners_known = vec![0, 2, 1]; // proven by the power of my hand and head
+
+        let ballot = super::Ballot::new(labels, values).expect("label/values mismatch");
+        let winners_exp: Vec<_> = ballot.runoff().map(|(winner, _, _)| winner).collect();
+
+        assert_eq!(winners_known, winners_exp);
+    }
+
+    #[test]
+    fn sparse_matches_dense() {
+        let labels: Vec<_> = (0..40).collect();
+        let rankings: Vec<_> = (0..30).map(|i| vec![i % 7, 10 + (i * 3) % 20, 39 - i % 5]).collect();
+
+        let sparse = super::Ballot::from_rankings(labels.clone(), &rankings).expect("valid rankings");
+        let dense: Vec<_> = sparse.ballots().flat_map(|row| row.into_owned()).collect();
+        let dense = super::Ballot::new(labels, dense).expect("label/values mismatch");
+
+        assert_eq!((sparse.storage(), dense.storage()), (super::Storage::Sparse, super::Storage::Dense));
+        assert_eq!(dense.suited_storage(), super::Storage::Sparse);
+        assert!(sparse.clone().runoff().eq(dense.runoff()));
+        assert!(sparse.clone().with_storage(super::Storage::Dense).runoff().eq(sparse.clone().runoff()));
+
+        let twice: Vec<_> = rankings.iter().chain(&rankings).cloned().collect();
+        let twice = super::Ballot::from_rankings((0..40).collect(), &twice).expect("valid rankings");
+        let deduplicated = twice.clone().deduplicated();
+
+        assert_eq!(deduplicated.ballot_count(), 30);
+        assert!(deduplicated.size() < twice.size());
+        assert!(deduplicated.runoff().eq(twice.runoff()));
+    }
+
+    #[test]
+    fn equal_ranks() {
+        // a=b>c and c
+        let rankings = [vec![vec![0, 1], vec![2]], vec![vec![2]]];
+        let mut ballot = super::Ballot::from_tied_rankings(vec!['a', 'b', 'c'], &rankings).expect("valid rankings");
+
+        assert_eq!(ballot.tier(), vec![1.0, 1.0, 1.0]);
+        assert_eq!(ballot.clone().with_ties(super::Ties::Split).tier(), vec![0.5, 0.5, 1.0]);
+
+        // b is still first on the tied ballot, not level with c
+        ballot.take(0);
+        assert_eq!(ballot.tier(), vec![1.0, 1.0]);
+    }
+
+    #[test]
+    fn no_candidates() {
+        let ballot = super::Ballot::<u8>::new(vec![], vec![]).expect("no ballots is fine");
+
+        assert_eq!(ballot.ballots().count(), 0);
+        assert_eq!(ballot.runoff().count(), 0);
+        assert!(super::Ballot::<u8>::new(vec![], vec![0]).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::{anyhow, Result};
+use clap::parser::ValueSource;
+
+/// Checks the options an election will be counted with, and adds every option
+/// left at its default with that default spelled out, so that a newer rankit
+/// with different defaults still counts it the same way.
+pub fn resolve(command: clap::Command, options: &[String]) -> Result<Vec<String>> {
+    let name = command.get_name().to_owned();
+    let matches = command
+        .clone()
+        .try_get_matches_from(std::iter::once(&name).chain(options))
+        .map_err(|e| anyhow!("those options can't be locked: {}", e.render().to_string().trim()))?;
+
+    if let Some((subcommand, _)) = matches.subcommand() {
+        return Err(anyhow!("only the options for counting can be locked, not {subcommand}"));
+    }
+
+    // the file can only hold one option per line
+    if let Some(option) = options.iter().find(|option| option.contains('\n')) {
+        return Err(anyhow!("{option:?} can't be locked since it has a line break in it"));
+    }
+
+    let mut resolved = options.to_vec();
+
+    for arg in command.get_arguments() {
+        let Some(long) = arg.get_long() else {
+            continue;
+        };
+
+        // flags that are off aren't worth spelling out
+        if matches.value_source(arg.get_id().as_str()) != Some(ValueSource::DefaultValue) || !arg.get_action().takes_values() {
+            continue;
+        }
+
+        let values: Vec<_> = arg.get_default_values().iter().map(|value| value.to_string_lossy()).collect();
+        let default = format!("--{long}={}", values.join(","));
+
+        // some defaults can't be given alongside other options, like --format
+        // with --raw, and those don't matter to the count anyway
+        let mut with_default = resolved.clone();
+        with_default.push(default);
+
+        if command.clone().try_get_matches_from(std::iter::once(&name).chain(&with_default)).is_ok() {
+            resolved = with_default;
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// The lock file holding the options, one per line, under a comment saying
+/// what it is.
+pub fn write(options: &[String]) -> String {
+    let mut contents = format!(
+        "# rankit {} election lock, counted with rankit --locked. Don't edit it by hand.\n",
+        env!("CARGO_PKG_VERSION")
+    );
+
+    for option in options {
+        contents.push_str(option);
+        contents.push('\n');
+    }
+
+    contents
+}
+
+/// The options in a lock file, skipping blank lines and comments.
+pub fn read(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_owned)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use clap::{Arg, ArgAction, Command};
+
+    #[test]
+    fn spells_out_defaults() {
+        let command = Command::new("rankit")
+            .arg(Arg::new("method").long("method").default_value("tiered"))
+            .arg(Arg::new("seats").long("seats"))
+            .arg(Arg::new("raw").long("raw").action(ArgAction::SetTrue))
+            .arg(Arg::new("format").long("format").default_value("text").conflicts_with("raw"));
+        let options = vec!["--seats".to_owned(), "2".to_owned(), "--raw".to_owned()];
+        let resolved = super::resolve(command, &options).expect("valid options");
+
+        // --format would conflict with --raw, so it's left out
+        assert_eq!(resolved, ["--seats", "2", "--raw", "--method=tiered"]);
+        assert_eq!(super::read(&super::write(&resolved)), resolved);
+    }
+}
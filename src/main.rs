@@ -1,13 +1,62 @@
 // Assumes columns contain rank indices and each row is a respondant
 
 use anyhow::{anyhow, Context, Result};
-use clap::Parser;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+mod batch;
+mod bundle;
+mod entry;
+#[cfg(feature = "plugins")]
+mod plugin;
+mod display;
+mod dot;
+mod html;
+mod json;
+mod lock;
+mod markdown;
+mod minimize;
+mod rcvis;
+mod sankey;
+mod tiebreak;
+mod validation;
+mod weighting;
+
+use rankit::{approval, audit, blt, borda, bucklin, clones, condorcet, cvr, demo, finalists, format_votes, handcount, inspect, irv, json_ballots, notation, preflib, ranks, retention, rng, score, sensitivity, stv, trace};
+use rankit::rounding::Rounding;
+use rankit::{Ballot, Storage, UNRANKED};
+
+use display::DisplayNames;
+use sensitivity::{Sensitivity, TieStability};
+
+/// Prints a report that comes before the results: to stdout with the text
+/// report, but to stderr when stdout has the results in another --format, so
+/// that they can still be read as JSON, CSV and so on.
+macro_rules! note {
+    ($cli:expr) => {
+        note!($cli, "")
+    };
+    ($cli:expr, $($arg:tt)*) => {
+        match $cli.format {
+            Format::Text => println!($($arg)*),
+            _ => eprintln!($($arg)*),
+        }
+    };
+}
+
+/// How many candidates there have to be before rounds are collapsed by default
+const COLLAPSE_FROM: usize = 50;
 
 /// Calculates the results of instant-runoff voting.
 /// 
-/// Pipe the contents of a CSV file (with headers) to use, where votes are contained in contiguous columns.
+/// Give a CSV file (with headers) to use, or pipe its contents in, where votes are contained in contiguous columns.
 #[derive(Debug, Parser)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// What column ranks start at, indexed at 0.
     #[arg(short, long, default_value_t = 0)]
     start: usize,
@@ -17,217 +66,3943 @@ struct Cli {
     /// Outputs the winners only, delimited by newlines.
     #[arg(short, long)]
     raw: bool,
-    /// The amount of columns which ranks occupy. If not specified, all remaining columns starting at the start index are used.
+    /// How the results are written out.
+    #[arg(long, value_enum, default_value_t = Format::Text, conflicts_with = "raw")]
+    format: Format,
+    /// LEN is the amount of columns which ranks occupy. If not specified, all remaining columns starting at the start index are used. FILE is the file to read the ballots from, or - for stdin, which is read without one. Several FILEs are counted together as batches of one election, like the files in a --manifest. A number is always taken as LEN, so a file called 3 has to be given as ./3.
+    #[arg(value_name = "LEN | FILE")]
+    positionals: Vec<String>,
+    /// LEN, from the positional arguments.
+    #[arg(skip)]
     len: Option<usize>,
+    /// Every FILE, from the positional arguments.
+    #[arg(skip)]
+    files: Vec<PathBuf>,
+    /// How the ballots are laid out.
+    #[arg(long, value_enum, default_value_t = InputFormat::Csv)]
+    input_format: InputFormat,
+    /// Whether csv input has a column per candidate or a column per rank.
+    #[arg(long, value_enum, default_value_t = Layout::ByCandidate)]
+    layout: Layout,
+    /// The directory holding a Dominion CVR export, for the dominion input format.
+    #[arg(long, required_if_eq("input_format", "dominion"))]
+    cvr: Option<PathBuf>,
+    /// The contest (by description or id) to tally from a Dominion CVR export with several.
+    #[arg(long)]
+    contest: Option<String>,
+    /// Also compares first preferences in this contest from the same Dominion CVR export with the --contest ones, ballot by ballot.
+    #[arg(long, requires = "cvr", conflicts_with = "raw")]
+    correlate: Option<String>,
+    /// A file listing ballot files (one per line, relative to the manifest) to read and tally together instead of stdin.
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+    /// Counts with exactly the options frozen in a lock file made by rankit lock, so they can't drift between a test run and the official count. No other options can be given.
+    #[arg(long, exclusive = true, value_name = "FILE")]
+    locked: Option<PathBuf>,
+    /// Shows how each candidate's votes and position changed since the previous round. Works with the tiered method.
+    #[arg(long)]
+    highlight_changes: bool,
+    /// Also reports the Condorcet winner, or the cycles of head-to-head wins that prevent one, and warns if the count elected someone else. Works with the tiered, irv and coombs methods, and --format text or json.
+    #[arg(long, conflicts_with = "raw")]
+    condorcet: bool,
+    /// Shows how much of each candidate's first-preference vote is still counting for them each round, and how much of their vote was transferred.
+    #[arg(long, conflicts_with = "raw")]
+    retention: bool,
+    /// How the winners are worked out.
+    #[arg(long, value_enum, default_value_t = Method::Tiered)]
+    method: Method,
+    /// Scores Borda counts the Dowdall (Nauru) way, with 1 point for a first preference, 1/2 for a second, 1/3 for a third and so on.
+    #[arg(long)]
+    dowdall: bool,
+    /// Counts a ballot as approving of every candidate it ranked this high or higher, for the approval method. Without it, every ranked candidate is approved.
+    #[arg(long)]
+    approve_up_to: Option<usize>,
+    /// Counts a ballot as approving of every candidate it ranked at or above the average rank of those it ranked, for the approval method.
+    #[arg(long, conflicts_with = "approve_up_to")]
+    approve_above_average: bool,
+    /// Runs a threshold election with the approval method instead of picking a winner: every option approved on more than this percent of ballots passes, and the rest fail.
+    #[arg(long, value_name = "PERCENT")]
+    pass_threshold: Option<f64>,
+    /// Withdraws a candidate after a round of --method irv or coombs, given as ROUND:CANDIDATE (e.g. 2:Smith), with their ballots going to the next preferences from the round after. Can be given more than once.
+    #[arg(long, value_name = "ROUND:CANDIDATE")]
+    withdraw: Vec<String>,
+    /// Counts as if only these candidates (separated by commas) had run, taking everyone else off every ballot and moving the candidates ranked below them up, like asking how the count would have gone between the final four.
+    #[arg(long, value_name = "CANDIDATES", value_delimiter = ',')]
+    only: Vec<String>,
+    /// How a ballot's vote is counted when it ranks several candidates equally first. Head-to-head, candidates ranked equally are never preferred to each other either way.
+    #[arg(long, value_enum, default_value_t = EqualRanks::Whole)]
+    equal_ranks: EqualRanks,
+    /// The lowest score a ballot can give with --method score or star, or --input-format scores.
+    #[arg(long, default_value_t = 0.0)]
+    min_score: f64,
+    /// The highest score a ballot can give with --method score or star, or --input-format scores.
+    #[arg(long, default_value_t = 5.0)]
+    max_score: f64,
+    /// Puts each candidate's scores on the same scale before they're counted, for scores gathered on different scales (like 1 to 5 for one question and 0 to 10 for another). The scale each candidate's scores had is reported with the results.
+    #[arg(long, value_enum)]
+    normalize: Option<Normalize>,
+    /// How many points a head-to-head tie is worth with --method copeland, from 0 to 1. A win is worth 1.
+    #[arg(long, default_value_t = 0.5)]
+    copeland_ties: f64,
+    /// The most candidates --method kemeny will search every ranking of, since the time it takes can grow exponentially with more.
+    #[arg(long, default_value_t = 12)]
+    max_candidates: usize,
+    /// How --method kemeny finds its ranking, which is picked by the number of candidates unless this is given. Which one was used is reported.
+    #[arg(long, value_enum, default_value_t = KemenySearch::Auto)]
+    kemeny_search: KemenySearch,
+    /// Fills this many seats by single transferable vote, with a Droop quota and surpluses passed on as --surplus says.
+    #[arg(long, conflicts_with_all = ["method", "finalists"])]
+    seats: Option<usize>,
+    /// How --seats passes on the surplus of a candidate who reached the quota.
+    #[arg(long, value_enum, default_value_t = SurplusRule::Fractional, requires = "seats")]
+    surplus: SurplusRule,
+    /// Lets no more than this many of these candidates be elected with --seats, given as N:CANDIDATES (e.g. 1:Smith,Jones). Once they have that many seats, the rest of them are skipped and their ballots go to the next preferences. Can be given more than once.
+    #[arg(long, value_name = "N:CANDIDATES", requires = "seats")]
+    at_most: Vec<String>,
+    /// Makes sure at least this many of these candidates are elected with --seats (or as many as are left), given as N:CANDIDATES (e.g. 2:Smith,Jones,Brown), for quotas like a seat for each region. Once the seats left are only enough for them, everyone else is skipped. Can be given more than once.
+    #[arg(long, value_name = "N:CANDIDATES", requires = "seats")]
+    at_least: Vec<String>,
+    /// How fractional counts are rounded before candidates are compared, as some election rules require.
+    #[arg(long, value_enum, default_value_t = RoundingRule::Exact)]
+    rounding: RoundingRule,
+    /// How many decimal places counts are rounded to, unless rounding is exact.
+    #[arg(long, default_value_t = 5)]
+    decimals: usize,
+    /// Sums up candidates with less than this percentage of a round's votes in one line, instead of listing them. This happens below 1% by default once there are 50 or more candidates, and 0 turns it off.
+    #[arg(long, conflicts_with = "raw")]
+    collapse_below: Option<f64>,
+    /// Also reports groups of candidates ranked next to each other on at least this percentage of the ballots ranking any of them (possible clones), and whether merging each group would change the plurality or instant-runoff winner.
+    #[arg(long, conflicts_with = "raw")]
+    clones: Option<f64>,
+    /// Also lists this many of the most common rankings, in A>B=C>D notation.
+    #[arg(long, conflicts_with = "raw")]
+    common_patterns: Option<usize>,
+    /// Also reports rounds where another candidate came within this many votes of being picked, and whether picking them would have changed the winners. Works with the tiered method.
+    #[arg(long, conflicts_with = "raw")]
+    sensitivity: Option<f64>,
+    /// Also reports rounds where candidates tied for the most votes, and how often each tie went another way when the count was rerun with every ballot's weight randomly scaled by up to this fraction either way (e.g. 0.01 for 1%).
+    #[arg(long, value_name = "NOISE", conflicts_with = "raw")]
+    tie_stability: Option<f64>,
+    /// How many times to rerun the count for --tie-stability.
+    #[arg(long, default_value_t = 1000, requires = "tie_stability")]
+    trials: usize,
+    /// Also writes the round-by-round results to this file as JSON that RCVIS can visualize.
+    #[arg(long)]
+    rcvis: Option<PathBuf>,
+    /// Also writes where votes moved each round to this file, as JSON nodes and links for a Sankey diagram in the shape plotly takes. Works with the irv and coombs methods.
+    #[arg(long)]
+    sankey: Option<PathBuf>,
+    /// Also writes a Graphviz DOT graph to this file, showing when each candidate was eliminated and where their votes went. Works with the irv and coombs methods.
+    #[arg(long)]
+    dot: Option<PathBuf>,
+    /// Also writes the round each candidate went out or won in to this file as CSV (round, candidate, outcome), for bracket or timeline visualizations. Works with the methods --format json does.
+    #[arg(long, value_name = "FILE")]
+    elimination_order: Option<PathBuf>,
+    /// Also packs the results into this zip file, for keeping a record of the election: the count as JSON and as a Markdown report, the --manifest, and every file written by --rcvis, --sankey, --dot, --elimination-order, --validation or --blt (each with its signature, if there's a --sign-cmd). Works with the methods --format json does.
+    #[arg(long, value_name = "FILE", conflicts_with = "audit_sample")]
+    bundle: Option<PathBuf>,
+    /// Signs each results file written by --rcvis, --sankey, --dot, --elimination-order or --bundle by piping it through this shell command (like "gpg --detach-sign --armor"), and writes what it prints next to the file, with .sig added to its name. Everything in the bundle is signed too, including the count and the --manifest, but the results printed to stdout aren't, so use --bundle for a signed copy of them.
+    #[arg(long, value_name = "CMD")]
+    sign_cmd: Option<String>,
+    /// Also writes what was found checking the input before tallying (column statistics and how many of each kind of problem) to this file as JSON. Needs csv input.
+    #[arg(long, conflicts_with = "manifest")]
+    validation: Option<PathBuf>,
+    /// Also writes the ballots to this file in the BLT format, which OpaVote and OpenSTV can count.
+    #[arg(long)]
+    blt: Option<PathBuf>,
+    /// A CSV file (with headers) mapping candidate labels in its first column to shorter names shown in reports in its second. A third and fourth column can hold a photo URL and a description for --format html, and a fifth the candidate's party or group, for comparing each group's seats with its first preferences under --seats.
+    #[arg(long)]
+    display_names: Option<PathBuf>,
+    /// Numbers the candidates in the order they were read, and includes those numbers in every report and output file.
+    #[arg(long)]
+    candidate_ids: bool,
+    /// The column (indexed at 0) holding each respondent's stratum, used to weight ballots to population targets.
+    #[arg(long, requires = "strata_targets")]
+    strata_column: Option<usize>,
+    /// A CSV file (with headers) giving each stratum in its first column and its size in the population in its second.
+    #[arg(long, requires = "strata_column")]
+    strata_targets: Option<PathBuf>,
+    /// Caps each ballot's weight at this, listing the ballots that were capped.
+    #[arg(long, requires = "strata_targets")]
+    max_weight: Option<f64>,
+    /// The column (indexed at 0) holding each respondent's group, such as their district, to also count each group on its own and flag those whose winner differs from the overall one. Only the tiered, irv and coombs methods can be grouped.
+    #[arg(long, conflicts_with = "raw")]
+    group_by: Option<usize>,
+    /// A CSV file (with headers) of rules for reading a survey export with more than ballots in it. Its first column names a column (by header, or index from 0) and its second says what it holds: rank, score, approval, weight, group, id or ignore. The ballot is every rank, score or approval column, which all have to be the same kind, and columns without a rule are ignored.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["group_by", "strata_column", "checksum_column", "validation"])]
+    columns: Option<PathBuf>,
+    /// Counts with the tally method in this WASM module instead of the built-in runoff.
+    #[cfg(feature = "plugins")]
+    #[arg(long)]
+    plugin: Option<PathBuf>,
+    /// A shell command that picks between candidates tied for the most votes. It's given the round, the tied candidates and the tally as JSON on stdin, and prints the label of the one it picks.
+    #[arg(long)]
+    tie_break: Option<String>,
+    /// Instead of tallying winners, eliminates the candidate with the fewest votes each round until this many finalists are left.
+    #[arg(long)]
+    finalists: Option<usize>,
+    /// Instead of tallying, selects this many ballots at random for a manual audit.
+    #[arg(long)]
+    audit_sample: Option<usize>,
+    /// Draws the audit sample separately from each group of ballots sharing a first preference.
+    #[arg(long, requires = "audit_sample")]
+    stratify: bool,
+    /// How ballots are stored while they're counted, which is picked automatically unless this is given. The strategy used is reported.
+    #[arg(long, value_enum)]
+    strategy: Option<Strategy>,
+    /// Keeps the ballots being counted under this many megabytes, by switching to whichever storage is smaller and then merging identical ballots if that isn't enough.
+    #[arg(long, conflicts_with = "audit_sample")]
+    max_memory: Option<f64>,
+    /// The column (indexed at 0, and not one of the rank columns) holding each record's checksum, which is checked against its ranks to catch ballots keyed in wrong. A checksum is the 64-bit FNV-1a hash, in hex, of the --checksum-salt, a colon, and the rank cells joined by commas.
+    #[arg(long)]
+    checksum_column: Option<usize>,
+    /// The salt mixed into every checksum, which can be kept from whoever keys in the ballots.
+    #[arg(long, requires = "checksum_column", default_value = "")]
+    checksum_salt: String,
+    /// What to do with csv records that have fewer or more cells than there are headers.
+    #[arg(long, value_enum, default_value_t = RaggedRows::Reject)]
+    ragged: RaggedRows,
+    /// The character that separates cells in csv input (and ES&S, notation and --columns exports), like ";" for exports from Excel in locales that write decimal commas. "tab" or "\t" is a tab.
+    #[arg(long, value_name = "CHAR", value_parser = parse_delimiter)]
+    delimiter: Option<u8>,
+    /// Reads tab-separated input, the same as --delimiter tab.
+    #[arg(long, conflicts_with = "delimiter")]
+    tsv: bool,
+    /// More words that mean a candidate was approved in approvals input (or --columns approval columns), separated by commas. 1, yes, oui, sí, ja, true, x and checked boxes already do, in any case.
+    #[arg(long, value_name = "WORDS", value_delimiter = ',')]
+    approved_words: Vec<String>,
+    /// More words that mean a candidate wasn't approved, separated by commas. 0, blank, no, non, nein, false and empty boxes already do, in any case.
+    #[arg(long, value_name = "WORDS", value_delimiter = ',')]
+    unapproved_words: Vec<String>,
+    /// Reads the first row of csv input as a ballot rather than headers naming the columns. Candidates are named Candidate 1, Candidate 2 and so on, unless there are --labels.
+    #[arg(long)]
+    no_headers: bool,
+    /// The candidates' names for csv input without headers, in the same order as their columns, separated by commas.
+    #[arg(long, value_delimiter = ',', requires = "no_headers")]
+    labels: Vec<String>,
+    /// Turns warnings about the input into errors.
+    #[arg(long)]
+    strict: bool,
+    /// The seed used for anything random, so that a draw, --tie-stability or --surplus random-draw can be reproduced.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Also writes every random draw (what it was for, the range it was drawn from, and the value) to this file as JSON, so an observer can check the draws against the seed. --audit-sample, --tie-stability and --surplus random-draw draw at random.
+    #[arg(long, value_name = "FILE")]
+    draw_log: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum InputFormat {
+    /// Columns of ranks, one column per candidate.
+    Csv,
+    /// A Dominion CVR export, read from the --cvr directory.
+    Dominion,
+    /// An ES&S CVR export saved as CSV, with a column per rank holding candidate names.
+    Ess,
+    /// Ballots tallied by hand, one ranking and its count per line, like "A>B>C, 14".
+    Handcount,
+    /// A CSV file (with headers) whose --start column holds each ballot's ranking, like "A>B=C>D".
+    Notation,
+    /// Columns of approvals, one column per candidate, holding 1 for approved and 0 or nothing for not. Approved candidates are ranked equally first.
+    Approvals,
+    /// A JSON array of ballots, each an object of ranks like {"Alice": 1, "Bob": 2} or an array of names like ["Alice", "Bob"].
+    Json,
+    /// A PrefLib .soc, .soi or .toc file of orders, each after how many voters cast it, like "12: 1,{2,3}".
+    Preflib,
+    /// A BLT file, as used by OpaVote and OpenSTV. Candidates it marks as withdrawn are taken out before the count.
+    Blt,
+    /// Columns of scores from --min-score to --max-score, one column per candidate, ranked highest score first. Equal scores are ranked equally, and blank ones are unranked.
+    Scores,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum EqualRanks {
+    /// Each of them gets the whole vote.
+    Whole,
+    /// They split the vote evenly, so a ballot ranking two candidates equally gives each of them half.
+    Split,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Layout {
+    /// Each candidate has a column, holding the rank they were given.
+    ByCandidate,
+    /// Each rank has a column, holding the name of the candidate given it, as Google Forms and many survey tools export ranked questions. The candidates are the names found. Blank ranks are skipped, as are candidates named again.
+    ByRank,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum RaggedRows {
+    /// They're an error.
+    Reject,
+    /// Short records are kept, and the candidates they're missing at the end are unranked.
+    Pad,
+    /// Long records have the cells past the last header dropped.
+    Truncate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    /// A report to read.
+    Text,
+    /// The whole count as JSON: every round's tally, exhausted votes, and who was eliminated or elected.
+    Json,
+    /// A table with a row for every candidate in every round: the round, candidate, votes and status. Exhausted votes get a row with no candidate.
+    Csv,
+    /// A results page to publish, showing each candidate's photo and description from --display-names if it has them.
+    Html,
+    /// A summary of who won and a table for every round, in Markdown for meeting minutes or forum posts.
+    Markdown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Method {
+    /// Picks the candidate with the most first preferences each round, ranking every candidate.
+    Tiered,
+    /// Only counts first preferences, electing whoever has the most. Input with a single column of choices (e.g. --input-format ess) works too.
+    Plurality,
+    /// Standard instant-runoff: eliminates the candidate with the fewest first preferences each round until someone has a majority.
+    Irv,
+    /// The Coombs rule: eliminates the candidate ranked last on the most ballots each round until someone has a majority of first preferences.
+    Coombs,
+    /// Scores candidates by where each ballot ranked them, and ranks them all by score.
+    Borda,
+    /// Counts how many ballots approve of each candidate, and elects whoever has the most approvals.
+    Approval,
+    /// Reads every cell as a score instead of a rank, and elects whoever has the highest total score.
+    Score,
+    /// Reads every cell as a score like score, then elects whichever of the two highest scoring candidates more ballots scored higher (score then automatic runoff).
+    Star,
+    /// Eliminates the candidate with the lowest Borda score each round, scoring again among those left, until one is left.
+    Baldwin,
+    /// Eliminates every candidate with a below average Borda score each round, scoring again among those left, until one is left.
+    Nanson,
+    /// Counts first preferences, then adds second preferences, third and so on until someone has a majority of the ballots.
+    Bucklin,
+    /// Ranks every candidate by the strengths of the strongest chains of head-to-head wins between them.
+    Schulze,
+    /// Ranks every candidate by how many other candidates they beat head-to-head.
+    Copeland,
+    /// Finds the ranking of every candidate that agrees with the most head-to-head preferences on the ballots.
+    Kemeny,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Strategy {
+    /// Picks the storage that takes up less room for these ballots.
+    Auto,
+    /// Keeps every ballot's rank for every candidate.
+    Dense,
+    /// Keeps only the candidates each ballot ranked, which suits many candidates with only a few ranked.
+    Sparse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum KemenySearch {
+    /// Searches exhaustively up to --max-candidates candidates, and uses the heuristic with more.
+    Auto,
+    /// Finds the best ranking there is, however long it takes, up to --max-candidates candidates.
+    Exact,
+    /// Improves the Schulze ranking one candidate at a time, which is quick but may not find the best ranking.
+    Heuristic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum RoundingRule {
+    /// Counts are used as they are.
+    Exact,
+    /// Counts are cut off after --decimals places.
+    Truncate,
+    /// Counts are rounded to --decimals places, with halves rounded up.
+    HalfUp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Normalize {
+    /// Scales each candidate's scores from 0 for the lowest they were given to 1 for the highest.
+    MinMax,
+    /// Replaces each score with how many standard deviations it is from the candidate's average.
+    ZScore,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum SurplusRule {
+    /// Every ballot counting for them is passed on at a fraction of its weight (the Gregory method).
+    Fractional,
+    /// As many whole ballots as the surplus are drawn at random with --seed and passed on, as some older constitutions specify (the Hare method).
+    RandomDraw,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Runs built-in example elections through the count, explaining what each one shows.
+    Demo {
+        /// The example to run. If not specified, every example is run.
+        name: Option<String>,
+    },
+    /// Reports what each column of a CSV file (with headers) contains, and guesses which columns hold ranks.
+    Inspect {
+        /// The file to inspect. If not specified, stdin is read instead.
+        file: Option<PathBuf>,
+    },
+    /// Prompts for ballots one at a time, writing them to a CSV file and showing a provisional tally as they're entered.
+    Enter {
+        /// The CSV file to write the ballots to, which rankit can count afterwards.
+        #[arg(short, long)]
+        output: PathBuf,
+        /// The candidates on the ballot, which can be entered by name or by their number in this list.
+        #[arg(required = true)]
+        candidates: Vec<String>,
+    },
+    /// Replays the count from the point of view of a single ballot, showing who it counted for in each round and when it was exhausted.
+    Trace {
+        /// The ballot to follow, numbered from 0 in the order the ballots were read.
+        #[arg(long)]
+        ballot_row: usize,
+    },
+    /// Shrinks a CSV file that makes rankit fail to the fewest records and columns that still fail the same way, to attach to a bug report instead of the full ballots. The options that fail go after --, e.g. rankit minimize ballots.csv -o small.csv -- --method irv.
+    Minimize {
+        /// The CSV file that fails.
+        file: PathBuf,
+        /// Where to write the shrunk CSV file.
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Text the error has to contain to count as the same failure. If not specified, it's the first line of the error the whole file gives.
+        #[arg(long)]
+        error: Option<String>,
+        /// The options rankit fails with.
+        #[arg(last = true)]
+        options: Vec<String>,
+    },
+    /// Checks the options an election will be counted with and freezes them, with every default spelled out, in a lock file for --locked. The options go after --, e.g. rankit lock -- --method irv --start 2.
+    Lock {
+        /// Where to write the lock file.
+        #[arg(short, long, default_value = "election.lock")]
+        output: PathBuf,
+        /// The options to lock.
+        #[arg(last = true)]
+        options: Vec<String>,
+    },
+    /// Prints how many voters prefer each candidate over each other one, with a row for every candidate's wins over the candidates in the columns.
+    Pairwise {
+        /// Prints the matrix as CSV instead of a table.
+        #[arg(long)]
+        csv: bool,
+    },
+    /// Fills a casual vacancy in a --seats count by countback, as in Tasmania and the ACT: the part of each ballot that made up the vacating member's quota is counted again by instant runoff among the candidates standing, until one has a majority. Give it the original ballots and the options they were counted with.
+    Countback {
+        /// The elected candidate whose seat is vacant.
+        #[arg(long)]
+        vacating: String,
+        /// The candidates contesting the vacancy, separated by commas. If not specified, it's everyone who wasn't elected.
+        #[arg(long, value_delimiter = ',')]
+        standing: Vec<String>,
+    },
+    /// Counts the same question asked in several polls, such as a monthly club poll, and reports how each candidate's first preferences and instant-runoff final round changed from one to the next.
+    Series {
+        /// The polls' ballot files, oldest first, all in the --input-format.
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let mut cli = match &cli.locked {
+        Some(path) => read_lock(path)?,
+        None => cli,
+    };
+    sort_positionals(&mut cli)?;
 
-    let votes = read_data(&cli)?;
-    let results = votes.runoff();
+    if cli.draw_log.is_some() && cli.audit_sample.is_none() && cli.tie_stability.is_none() && cli.surplus != SurplusRule::RandomDraw {
+        return Err(anyhow!("--draw-log needs something drawn at random, by --audit-sample, --tie-stability or --surplus random-draw"));
+    }
 
-    if cli.raw {
-        for winner in results.map(|(winner, _, _)| winner) {
-            println!("{winner}");
-        }
+    let scores = matches!(cli.method, Method::Score | Method::Star) || cli.input_format == InputFormat::Scores || cli.columns.is_some();
+
+    if cli.normalize.is_some() && !scores {
+        return Err(anyhow!("--normalize only works with scores, from --method score or star, --input-format scores or score --columns"));
     }
-    else {
-        for (i, (winner, counts, mut other)) in results.enumerate() {
-            let cardinal = i + 1;
 
-            println!("Winner #{cardinal}: {winner} with {counts} votes");
+    match &cli.command {
+        Some(Command::Demo { name }) => return run_demo(&cli, name.as_deref()),
+        Some(Command::Inspect { file }) => return run_inspect(file.as_deref(), dialect(&cli)),
+        Some(Command::Enter { output, candidates }) => return run_enter(output, candidates),
+        Some(Command::Series { files }) => return run_series(&cli, files),
+        Some(Command::Minimize { file, output, error, options }) => return run_minimize(file, output, error.as_deref(), options),
+        Some(Command::Lock { output, options }) => return run_lock(output, options),
+        Some(Command::Trace { .. } | Command::Pairwise { .. } | Command::Countback { .. }) | None => {}
+    }
+
+    // scores can't be turned into rankings without losing how far apart they are
+    if let Method::Score | Method::Star = cli.method {
+        return run_scores(&cli);
+    }
+
+    let batch_paths = match &cli.manifest {
+        Some(path) => Some(batch::read_manifest(path)?),
+        None if cli.files.len() > 1 => Some(cli.files.clone()),
+        None => None,
+    };
+
+    let (mut votes, columns) = match batch_paths {
+        Some(paths) => {
+            let (votes, columns, batches) = read_batches(&cli, paths)?;
 
-            other.sort_by(|(_, count_a), (_, count_b)| count_b.cmp(count_a));
-            for (label, count) in other {
-                println!("{label}: {count}");
+            if !cli.raw && cli.audit_sample.is_none() {
+                print_batches(&cli, &batches);
             }
 
-            println!();
-            println!();
-        }        
+            (votes, columns)
+        }
+        None => match input_file(&cli) {
+            Some(path) => {
+                let file = std::fs::File::open(path).with_context(|| format!("couldn't open {}", path.display()))?;
+
+                read_input(&cli, std::io::BufReader::new(file)).with_context(|| format!("in {}", path.display()))?
+            }
+            None => read_input(&cli, std::io::stdin())?,
+        },
+    };
+    let names = match &cli.display_names {
+        Some(path) => DisplayNames::read(path, votes.labels())?,
+        None => DisplayNames::default(),
+    };
+    let names = match cli.candidate_ids {
+        true => names.with_ids(votes.labels()),
+        false => names,
+    };
+
+    if let (Some(other), Some(dir)) = (&cli.correlate, &cli.cvr) {
+        print_correlation(&cli, dir, other)?;
     }
 
-    Ok(())
+    let storage = match cli.strategy.unwrap_or(Strategy::Auto) {
+        Strategy::Auto => votes.suited_storage(),
+        Strategy::Dense => Storage::Dense,
+        Strategy::Sparse => Storage::Sparse,
+    };
+    votes = votes.with_storage(storage);
+
+    if cli.strategy.is_some() && !cli.raw {
+        print_strategy(&cli, &votes);
+    }
+
+    if let Some(size) = cli.audit_sample {
+        return print_audit_sample(&cli, &votes, &names, size);
+    }
+
+    if let Some(path) = &cli.strata_targets {
+        let targets = weighting::read_targets(path)?;
+        let (groups, mut weights) = weighting::weights(&columns.strata, &targets)?;
+        let capped = match cli.max_weight {
+            Some(max) if !max.is_finite() || max <= 0.0 => return Err(anyhow!("the max weight has to be more than 0")),
+            Some(max) => weighting::cap(&mut weights, max),
+            None => vec![],
+        };
+
+        if !cli.raw {
+            print_weights(&cli, &groups, &weights, &capped);
+        }
+
+        votes = votes.with_weights(weights).expect("weights and votes mismatch");
+    }
+
+    if !cli.only.is_empty() {
+        votes = only(&cli, votes, &names)?;
+    }
+
+    if cli.equal_ranks == EqualRanks::Split {
+        votes = votes.with_ties(rankit::Ties::Split);
+    }
+
+    if !columns.groups.is_empty() {
+        print_groups(&cli, &votes, &columns.groups, &names)?;
+    }
+
+    if let Some(megabytes) = cli.max_memory {
+        votes = fit_memory(&cli, votes, megabytes)?;
+    }
+
+    if let Some(percent) = cli.clones {
+        print_clones(&cli, &votes, percent, &names)?;
+    }
+
+    // the ballots are the same whichever method counts them
+    if let Some(path) = &cli.blt {
+        std::fs::write(path, blt::write(&votes, cli.seats.unwrap_or(1), "rankit"))
+            .with_context(|| format!("couldn't write BLT ballots to {}", path.display()))?;
+    }
+
+    if let Some(Command::Trace { ballot_row }) = cli.command {
+        return run_trace(&cli, votes, ballot_row, &names);
+    }
+
+    if let Some(Command::Countback { vacating, standing }) = &cli.command {
+        return run_countback(&cli, &votes, vacating, standing, &names);
+    }
+
+    if let Some(Command::Pairwise { csv }) = cli.command {
+        return write_pairwise(std::io::stdout(), &condorcet::Pairwise::new(&votes), votes.labels(), csv, &names);
+    }
+
+    // worked out now, since counting uses up the ballots
+    let bundled = match cli.bundle {
+        Some(_) => Some(structured(&cli, &votes, "--bundle")?),
+        None => None,
+    };
+
+    tally(&cli, votes, &names)?;
+
+    match (&cli.bundle, bundled) {
+        (Some(path), Some(result)) => write_bundle(&cli, path, &result, &names),
+        _ => Ok(()),
+    }
+}
+
+/// Zips up the count and every file written about it for --bundle.
+fn write_bundle(cli: &Cli, path: &Path, result: &serde_json::Value, names: &DisplayNames) -> Result<()> {
+    let json = serde_json::to_string_pretty(result).expect("json values always serialize");
+    let mut files = vec![
+        (String::from("results.json"), json.into_bytes()),
+        (String::from("report.md"), markdown::report(result, names).into_bytes()),
+    ];
+
+    let read = |file: &Path| -> Result<(String, Vec<u8>)> {
+        let name = file.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let contents = std::fs::read(file).with_context(|| format!("couldn't read {} to bundle it", file.display()))?;
+
+        Ok((name, contents))
+    };
+    let signed = [&cli.rcvis, &cli.sankey, &cli.dot, &cli.elimination_order];
+    let unsigned = [&cli.manifest, &cli.validation, &cli.blt];
+
+    for written in unsigned.into_iter().flatten() {
+        files.push(read(written)?);
+    }
+
+    // these weren't signed when they were written, so they're signed here
+    let mut signatures = vec![];
+
+    for (name, contents) in &files {
+        if let Some(signature) = signature(cli, contents, name)? {
+            signatures.push((format!("{name}.sig"), signature));
+        }
+    }
+
+    files.extend(signatures);
+
+    for written in signed.into_iter().flatten() {
+        files.push(read(written)?);
+
+        if cli.sign_cmd.is_some() {
+            let mut signature = written.as_os_str().to_owned();
+            signature.push(".sig");
+
+            files.push(read(Path::new(&signature))?);
+        }
+    }
+
+    std::fs::write(path, bundle::zip(&files)?).with_context(|| format!("couldn't write the bundle to {}", path.display()))?;
+    sign(cli, path)
 }
 
-fn read_data(cli: &Cli) -> Result<Ballot<String>> {
-    let mut csv_reader = csv::Reader::from_reader(std::io::stdin());
+/// Counts each group's ballots on their own, flagging the groups whose winner
+/// isn't the overall winner along with how differently they voted for the
+/// two.
+fn print_groups(cli: &Cli, votes: &Ballot<String>, groups: &[String], names: &DisplayNames) -> Result<()> {
+    let winner = |votes: &Ballot<String>| match (cli.seats, cli.method) {
+        (None, Method::Tiered) => Ok(votes.clone().runoff_rounded(rounding(cli)).next().map(|(winner, _, _)| winner)),
+        (None, Method::Irv | Method::Coombs) => Ok(irv::count_by(votes, rounding(cli), elimination(cli)).1),
+        _ => Err(anyhow!("only the tiered, irv and coombs methods can be grouped")),
+    };
+    // each candidate's share of the first preferences, by label
+    let shares = |votes: &Ballot<String>| -> HashMap<String, f64> {
+        let tier = votes.tier();
+        let total: f64 = votes.weights().iter().sum();
 
-    let labels: Vec<_> = {
-        let headers_start = csv_reader
-            .headers()
-            .context("headers issue")?
+        votes
+            .labels()
             .iter()
-            .skip(cli.start)
-            .map(String::from);
+            .cloned()
+            .zip(tier.into_iter().map(|firsts| if total > 0.0 { firsts / total * 100.0 } else { 0.0 }))
+            .collect()
+    };
 
-        if let Some(len) = cli.len {
-            headers_start.take(len).collect()
-        } else {
-            headers_start.collect()
-        }
-    };
-    let mut all_ranks = csv_reader.records().enumerate().try_fold(
-        vec![],
-        |mut all_ranks, (i, r)| {
-            let row_ranks = {
-                let cells: Vec<_> = {
-                    let row = r.with_context(|| format!("bad record {i}"))?;
-                    let cols_start = row.into_iter().skip(cli.start).map(String::from);
-
-                    if let Some(len) = cli.len {
-                        cols_start.take(len).collect()
-                    } else {
-                        cols_start.collect()
-                    }
-                };
+    let shown = |winner: &Option<String>| String::from(winner.as_deref().map_or("nobody", |winner| names.get(winner)));
+    let overall = winner(votes)?;
+    let overall_shares = shares(votes);
 
-                let merhaps: Result<Vec<_>, _> = cells
-                    .into_iter()
-                    .enumerate()
-                    .map(|(j, v)| {
-                        v.parse::<usize>()
-                            .with_context(|| format!("invalid rank, record {i}, value {j}"))
-                    })
-                    .collect();
+    let mut order: Vec<&String> = vec![];
 
-                merhaps?
-            };
+    for group in groups {
+        if !order.contains(&group) {
+            order.push(group);
+        }
+    }
 
-            let row_ranks_len = row_ranks.len();
-            let labels_len = labels.len();
-            
-            if row_ranks_len != labels_len {
-                Err(anyhow!("invalid number of ranks, record {i} (expected {labels_len}, got {row_ranks_len})"))
-            } else {
-                all_ranks.extend(row_ranks);
+    note!(cli, "Groups:");
 
-                Ok(all_ranks)
-            }
-        },
-    )?;
+    let mut split = 0;
 
-    for value in all_ranks.iter_mut() {
-        if let Some(sub) = value.checked_sub(cli.indexed_at) {
-            *value = sub;
-        } else {
-            return Err(anyhow!(
-                "bad index-at argument (ranks occur lower than the index)"
-            ));
+    for group in order {
+        // leaving out everyone else's ballots is the same as weighting them at 0
+        let weights = votes
+            .weights()
+            .iter()
+            .zip(groups)
+            .map(|(weight, of)| if of == group { *weight } else { 0.0 })
+            .collect();
+        let in_group = votes.clone().with_weights(weights).expect("a weight for every ballot");
+        let ballots = groups.iter().filter(|of| *of == group).count();
+        let group_winner = winner(&in_group)?;
+
+        match (&group_winner, &overall) {
+            (Some(here), Some(there)) if here != there => {
+                let group_shares = shares(&in_group);
+                let share = |shares: &HashMap<String, f64>, label: &String| shares.get(label).copied().unwrap_or(0.0);
+
+                split += 1;
+                note!(cli, 
+                    "{group} ({ballots} ballots): won by {}, not {} (first preferences {:.1}% to {:.1}% here, against {:.1}% to {:.1}% overall)",
+                    shown(&group_winner),
+                    shown(&overall),
+                    share(&group_shares, here),
+                    share(&group_shares, there),
+                    share(&overall_shares, here),
+                    share(&overall_shares, there),
+                );
+            }
+            _ => note!(cli, "{group} ({ballots} ballots): won by {}", shown(&group_winner)),
         }
     }
 
-    let ballot = Ballot::new(labels, all_ranks).expect("labels and votes mismatch");
+    note!(cli);
+
+    match split {
+        0 => note!(cli, "Every group agrees with the overall winner, {}", shown(&overall)),
+        1 => note!(cli, "1 group disagrees with the overall winner, {}", shown(&overall)),
+        split => note!(cli, "{split} groups disagree with the overall winner, {}", shown(&overall)),
+    }
 
-    Ok(ballot)
+    note!(cli);
+    note!(cli);
+
+    Ok(())
 }
 
-pub struct Ballot<T: Clone> {
-    /// The names of the candidates
-    labels: Vec<T>,
-    /// The raw rankings. For all elements e in this vec, 0 <= e < width
-    votes: Vec<usize>,
+/// Shrinks the ballots until they take up at most `megabytes`, first by
+/// changing storage (unless one was asked for) and then by merging identical
+/// ballots.
+fn fit_memory(cli: &Cli, votes: Ballot<String>, megabytes: f64) -> Result<Ballot<String>> {
+    if !megabytes.is_finite() || megabytes <= 0.0 {
+        return Err(anyhow!("the memory budget has to be more than 0 megabytes"));
+    }
+
+    let budget = (megabytes * 1_000_000.0) as usize;
+    let in_units = |bytes: usize| match bytes {
+        0..1_000_000 => format!("{:.1} kB", bytes as f64 / 1_000.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_000_000.0),
+    };
+
+    if votes.size() <= budget {
+        return Ok(votes);
+    }
+
+    let votes = match cli.strategy {
+        Some(Strategy::Dense | Strategy::Sparse) => votes,
+        _ => {
+            let other = match votes.storage() {
+                Storage::Dense => Storage::Sparse,
+                Storage::Sparse => Storage::Dense,
+            };
+
+            match votes.size_in(other) < votes.size() {
+                true => votes.with_storage(other),
+                false => votes,
+            }
+        }
+    };
+
+    if votes.size() <= budget {
+        if !cli.raw {
+            note!(cli, "Memory: switched storage to fit {} in {}", in_units(votes.size()), in_units(budget));
+            note!(cli);
+            note!(cli);
+        }
+
+        return Ok(votes);
+    }
+
+    // tracing a ballot needs every ballot kept in its own row
+    if let Some(Command::Trace { .. }) = cli.command {
+        return Err(anyhow!(
+            "the ballots need about {}, over the budget of {}, and tracing a ballot can't merge identical ones (try a bigger --max-memory)",
+            in_units(votes.size()),
+            in_units(budget)
+        ));
+    }
+
+    let ballots = votes.ballot_count();
+    let votes = votes.deduplicated();
+
+    if votes.size() > budget {
+        return Err(anyhow!(
+            "the ballots need about {} even with identical ones merged, over the budget of {} (try a bigger --max-memory, or fewer rank columns)",
+            in_units(votes.size()),
+            in_units(budget)
+        ));
+    }
+
+    if !cli.raw {
+        let distinct = votes.ballot_count();
+
+        note!(cli, "Memory: merged {ballots} ballots into {distinct} distinct ones to fit {} in {}", in_units(votes.size()), in_units(budget));
+        note!(cli);
+        note!(cli);
+    }
+
+    Ok(votes)
 }
 
-impl<T: Clone> Ballot<T> {
-    pub fn new(labels: Vec<T>, votes: Vec<usize>) -> Result<Self, (Vec<T>, Vec<usize>)> {
-        if votes.len() % labels.len() == 0 && votes.iter().copied().all(|v| v < labels.len()) {
-            Ok(Self { labels, votes })
-        } else {
-            Err((labels, votes))
+/// Sorts the positional arguments into LEN and FILE: a number is LEN, and
+/// anything else is FILE.
+fn sort_positionals(cli: &mut Cli) -> Result<()> {
+    for positional in std::mem::take(&mut cli.positionals) {
+        match positional.parse::<usize>() {
+            Ok(len) if cli.len.is_none() => cli.len = Some(len),
+            Ok(_) => return Err(anyhow!("LEN was given twice")),
+            Err(_) => cli.files.push(PathBuf::from(positional)),
         }
     }
 
-    fn count(&self) -> usize {
-        self.labels.len()
+    if cli.files.len() > 1 && cli.files.iter().any(|path| path == Path::new("-")) {
+        return Err(anyhow!("stdin can't be read along with other files"));
+    }
+
+    if cli.len.is_some() && cli.columns.is_some() {
+        return Err(anyhow!("LEN can't be given with --columns, which says where the ranks are"));
+    }
+
+    if !cli.files.is_empty() && cli.manifest.is_some() {
+        return Err(anyhow!("FILE can't be given with --manifest, which lists the files to read"));
     }
 
-    fn rows(&mut self) -> impl Iterator<Item = &mut [usize]> + '_ {
-        let count = self.count();
+    Ok(())
+}
+
+/// The one FILE to read the ballots from, unless they're read from stdin (or
+/// there are several, which are read as batches).
+fn input_file(cli: &Cli) -> Option<&Path> {
+    match cli.files.as_slice() {
+        [path] if path != Path::new("-") => Some(path),
+        _ => None,
+    }
+}
 
-        self.votes.chunks_mut(count)
+fn run_lock(output: &Path, options: &[String]) -> Result<()> {
+    if options.iter().any(|option| option.starts_with("--locked")) {
+        return Err(anyhow!("a lock file can't point to another one"));
     }
 
-    fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = usize> + '_> + '_ {
-        let count = self.count();
+    let resolved = lock::resolve(Cli::command(), options)?;
+
+    std::fs::write(output, lock::write(&resolved)).with_context(|| format!("couldn't write {}", output.display()))?;
+    eprintln!("Locked the options in {}", output.display());
+
+    Ok(())
+}
+
+/// The options frozen in a lock file, which replace any given on the command
+/// line (clap makes sure there weren't any).
+fn read_lock(path: &Path) -> Result<Cli> {
+    let contents = std::fs::read_to_string(path).with_context(|| format!("couldn't read {}", path.display()))?;
+    let options = lock::read(&contents);
+
+    Cli::try_parse_from(std::iter::once("rankit".to_owned()).chain(options))
+        .map_err(|e| anyhow!("{} doesn't hold valid options: {}", path.display(), e.render().to_string().trim()))
+}
+
+fn run_minimize(file: &Path, output: &Path, error: Option<&str>, options: &[String]) -> Result<()> {
+    let input = std::fs::File::open(file).with_context(|| format!("couldn't open {}", file.display()))?;
+    let (headers, records) = ranks::read_table(input)?;
+    let exe = std::env::current_exe().context("couldn't find the rankit executable")?;
+
+    // what rankit printed to stderr if it failed on the table, or nothing
+    let run = |headers: &csv::StringRecord, records: &[csv::StringRecord]| -> Result<Option<String>> {
+        let mut child = std::process::Command::new(&exe)
+            .args(options)
+            .env("RUST_BACKTRACE", "0")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .context("couldn't run rankit")?;
+
+        // rankit can fail before reading everything, so a closed pipe is fine
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = std::io::Write::write_all(&mut stdin, minimize::to_csv(headers, records)?.as_bytes());
+        }
+
+        let result = child.wait_with_output().context("couldn't run rankit")?;
+        let stderr = String::from_utf8_lossy(&result.stderr).into_owned();
 
-        (0..count).map(move |i| self.votes.iter().skip(i).step_by(count).copied())
+        Ok((!result.status.success()).then_some(stderr))
+    };
+
+    let Some(stderr) = run(&headers, &records)? else {
+        return Err(anyhow!("{} doesn't make rankit fail with those options, so there's nothing to minimize", file.display()));
+    };
+    let expected = match error {
+        Some(error) => String::from(error),
+        None => stderr
+            .lines()
+            .find(|line| !line.trim().is_empty() && !line.starts_with("warning:"))
+            .unwrap_or_default()
+            .to_owned(),
+    };
+
+    if !stderr.contains(&expected) {
+        return Err(anyhow!("{} fails, but not with {expected:?}", file.display()));
     }
 
-    fn remove_column(&mut self, col: usize) -> T {
-        let count = self.count();
+    println!("Minimizing {} records failing with: {expected}", records.len());
 
-        for i in (0..self.votes.len()).rev().filter(|i| i % count == col) {
-            self.votes.remove(i);
+    let mut failed = None;
+    let (headers, records) = minimize::minimize(headers, records, |headers, records| {
+        match run(headers, records) {
+            Ok(stderr) => stderr.is_some_and(|stderr| stderr.contains(&expected)),
+            Err(e) => {
+                failed.get_or_insert(e);
+                false
+            }
         }
+    });
 
-        self.labels.remove(col)
+    if let Some(e) = failed {
+        return Err(e);
     }
 
-    /// Calculates each tier of an instant-runoff vote
-    pub fn runoff(mut self) -> impl Iterator<Item = (T, usize, Vec<(T, usize)>)> {
-        // According to R I G O R O U S testing (my head), this could
-        // just be implemented by summing the ranks of votes that each
-        // candidate gets, and then sorting the candidates according
-        // to their vote counts.
-        //
-        // HOWEVER,
-        //
-        // that means each tier of votes cannot be counted i.e. only
-        // the final result is known. Knowing the results of each
-        // tier of vote makes it much easier to understand how the
-        // results came to be.
+    std::fs::write(output, minimize::to_csv(&headers, &records)?)
+        .with_context(|| format!("couldn't write {}", output.display()))?;
 
-        (0..self.count()).map(move |_| {
-            let mut tier: Vec<_> = self
-                .columns()
-                .map(|col| col.filter(|vote_rank| *vote_rank == 0).count())
-                .collect();
+    let plural = |count: usize, what: &str| match count {
+        1 => format!("1 {what}"),
+        count => format!("{count} {what}s"),
+    };
 
-            let winner_index = (0..tier.len()).max_by_key(|i| tier[*i]).unwrap();
+    println!("Wrote {} and {} to {}", plural(records.len(), "record"), plural(headers.len(), "column"), output.display());
 
-            for row in self.rows() {
-                let winner_rank = row[winner_index];
+    Ok(())
+}
 
-                for choice in row.into_iter().filter(|rank| **rank > winner_rank) {
-                    *choice -= 1;
-                }
+/// Counts every poll in a series, and lists each candidate's share of the
+/// first preferences and of the final round in each.
+fn run_series(cli: &Cli, files: &[PathBuf]) -> Result<()> {
+    let mut polls = vec![];
+
+    for path in files {
+        let file = std::fs::File::open(path).with_context(|| format!("couldn't open poll {}", path.display()))?;
+        let (votes, _) = read_input(cli, std::io::BufReader::new(file)).with_context(|| format!("in poll {}", path.display()))?;
+        let (rounds, winner) = irv::count(&votes, rounding(cli));
+
+        // shares of every ballot at first, and of the votes still counting at the end
+        let shares = |round: Option<&irv::Round<String>>, exhausted: bool| -> HashMap<String, f64> {
+            let Some(round) = round else {
+                return HashMap::new();
+            };
+            let total = round.tally.iter().map(|(_, votes)| votes).sum::<f64>() + if exhausted { round.exhausted } else { 0.0 };
+
+            round
+                .tally
+                .iter()
+                .map(|(label, votes)| (label.clone(), if total > 0.0 { votes / total * 100.0 } else { 0.0 }))
+                .collect()
+        };
+        let first = shares(rounds.first(), true);
+        let last = shares(rounds.last(), false);
+        let name = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+
+        polls.push((name, votes.labels().to_vec(), first, last, winner));
+    }
+
+    if cli.raw {
+        for (_, _, _, _, winner) in &polls {
+            println!("{}", winner.as_deref().unwrap_or_default());
+        }
+
+        return Ok(());
+    }
+
+    // every candidate in any poll, in the order they first appeared
+    let mut candidates: Vec<String> = vec![];
+
+    for (_, labels, ..) in &polls {
+        for label in labels {
+            if !candidates.contains(label) {
+                candidates.push(label.clone());
             }
+        }
+    }
 
-            let winner_label = self.remove_column(winner_index);
-            let winner_count = tier.remove(winner_index);
-            let data: Vec<_> = self.labels.iter().cloned().zip(tier.into_iter()).collect();
+    let width = candidates.iter().map(|label| label.chars().count()).max().unwrap_or(0);
+    let header: Vec<_> = polls.iter().map(|(name, ..)| format!("{name:>7}")).collect();
+    let cell = |name: &str, share: Option<&f64>| {
+        let cell = name.chars().count().max(7);
 
-            (winner_label, winner_count, data)
-        })
+        match share {
+            Some(share) => format!("{:>cell$}", format!("{share:.1}%")),
+            None => format!("{:>cell$}", "-"),
+        }
+    };
+
+    for (title, final_round) in [("First preferences", false), ("Final round (- if eliminated before it)", true)] {
+        println!("{title}:");
+        println!("{:width$}  {}", "", header.join("  "));
+
+        for candidate in &candidates {
+            let cells: Vec<_> = polls
+                .iter()
+                .map(|(name, _, first, last, _)| cell(name, if final_round { last } else { first }.get(candidate)))
+                .collect();
+
+            println!("{candidate:width$}  {}", cells.join("  "));
+        }
+
+        println!();
+        println!();
+    }
+
+    for (name, _, _, _, winner) in &polls {
+        println!("{name}: won by {}", winner.as_deref().unwrap_or("nobody"));
     }
+
+    Ok(())
 }
 
-#[cfg(test)]
-mod test {
-    #[test]
-    fn three_example() {
-        let labels = vec![0, 1, 2];
-        let values: Vec<_> = vec![
-            [0, 1, 2],
-            [0, 2, 1],
-            [1, 2, 0],
-            [1, 0, 2],
-            [2, 0, 1],
-            [2, 1, 0],
-            [0, 2, 1],
-            [0, 2, 1],
-            [2, 0, 1],
-        ]
-        .into_iter()
-        .flatten() // i put arrays and then flatten anyway so rustfmt doesn't put 50 billion numbers on one line
-        .collect();
-        let winners_known = vec![0, 2, 1]; // proven by the power of my hand and head
+/// Fills a vacancy by counting back the ballots that elected `vacating`.
+fn run_countback(cli: &Cli, votes: &Ballot<String>, vacating: &str, standing: &[String], names: &DisplayNames) -> Result<()> {
+    let Some(seats) = cli.seats.filter(|seats| *seats > 0) else {
+        return Err(anyhow!("countback needs the --seats the election was counted with"));
+    };
+
+    for candidate in std::iter::once(vacating).chain(standing.iter().map(String::as_str)) {
+        if !votes.labels().iter().any(|label| label == candidate) {
+            return Err(anyhow!("no candidate called {candidate:?}"));
+        }
+    }
+
+    let elected = count_stv(cli, votes, seats)?.elected;
+
+    if !elected.iter().any(|label| label == vacating) {
+        return Err(anyhow!("{vacating} wasn't elected, so there's no vacancy to fill"));
+    }
+
+    let standing = match standing {
+        [] => votes.labels().iter().filter(|label| !elected.contains(label)).cloned().collect(),
+        standing => standing.to_vec(),
+    };
+
+    // the same seed draws the same surpluses as the original count did
+    let constraints = constraints(cli, votes.labels())?;
+    let mut rng = rng::Rng::new(cli.seed).with_logging(false);
+    let surplus = match cli.surplus {
+        SurplusRule::Fractional => stv::Surplus::Fractional,
+        SurplusRule::RandomDraw => stv::Surplus::RandomDraw(&mut rng),
+    };
+    let (rounds, winner) = stv::countback(votes, seats, rounding(cli), surplus, &constraints, &vacating.to_owned(), &standing)
+        .with_context(|| format!("{vacating} didn't reach the quota, so there are no ballots that elected them to count back"))?;
+
+    if !cli.raw {
+        let standing: Vec<_> = standing.iter().map(|label| names.get(label)).collect();
+
+        println!("Countback for {}'s seat, among {}", names.get(vacating), standing.join(", "));
+        println!();
+        println!();
+    }
+
+    print_irv(cli, &rounds, winner.as_deref(), names);
+
+    Ok(())
+}
+
+fn run_trace(cli: &Cli, votes: Ballot<String>, row: usize, names: &DisplayNames) -> Result<()> {
+    if row >= votes.ballot_count() {
+        return Err(anyhow!("there's no ballot row {row} (rows are numbered from 0, and {} were read)", votes.ballot_count()));
+    }
+
+    // the candidates in each round, and who left the count after it
+    let (standing, left): (Vec<Vec<String>>, Vec<_>) = match (cli.seats, cli.method) {
+        (None, Method::Tiered) => run_count(cli, votes.clone())?
+            .0
+            .into_iter()
+            .map(|(winner, _, others)| {
+                let standing = std::iter::once(winner.clone()).chain(others.into_iter().map(|(label, _)| label));
+
+                (standing.collect(), Some((winner, "was picked")))
+            })
+            .unzip(),
+        (None, Method::Irv | Method::Coombs) => irv::count_withdrawing(&votes, rounding(cli), elimination(cli), &withdrawals(cli, votes.labels())?)
+            .0
+            .into_iter()
+            .map(|round| {
+                let standing = round.tally.into_iter().map(|(label, _)| label).collect();
+
+                (standing, round.eliminated.map(|label| (label, "was eliminated")))
+            })
+            .unzip(),
+        _ => return Err(anyhow!("only the tiered, irv and coombs methods can be traced")),
+    };
+
+    let trace = trace::trace(&votes, row, &standing);
+
+    let ranking: Vec<_> = votes.ranking(row).map(|label| names.get(label)).collect();
+    let ranking = match ranking.is_empty() {
+        true => String::from("(nobody ranked)"),
+        false => ranking.join(" > "),
+    };
+    let weight = votes.weights()[row];
+
+    match weight == 1.0 {
+        true => println!("Ballot row {row}: {ranking}"),
+        false => println!("Ballot row {row}: {ranking}, with a weight of {}", format_votes(weight)),
+    }
+
+    println!();
+
+    for (i, (counted, left)) in trace.iter().zip(&left).enumerate() {
+        let round = i + 1;
+
+        if counted.is_empty() {
+            match ranking.as_str() {
+                "(nobody ranked)" => println!("Round {round}: exhausted, since it didn't rank anyone"),
+                _ => println!("Round {round}: exhausted, since everyone it ranked is out of the count"),
+            }
+
+            break;
+        }
+
+        let counted_names: Vec<_> = counted.iter().map(|label| names.get(label)).collect();
+        let counted_for = match counted_names.len() {
+            1 => format!("counted for {}", counted_names[0]),
+            _ => format!("counted for {}, who it ranked equally", counted_names.join(" and ")),
+        };
+
+        match left {
+            Some((label, how)) if counted.contains(label) => {
+                let label = names.get(label);
+                let moved = match round < trace.len() {
+                    true => ", so it moved to its next choice",
+                    false => "",
+                };
+
+                println!("Round {round}: {counted_for}, then {label} {how}{moved}");
+            }
+            _ => println!("Round {round}: {counted_for}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints a contingency table of first preferences in the tallied contest
+/// against another contest on the same ballots.
+fn print_correlation(cli: &Cli, dir: &Path, other: &str) -> Result<()> {
+    if cli.input_format != InputFormat::Dominion {
+        return Err(anyhow!("contests can only be correlated in a dominion export"));
+    }
+
+    let export = cvr::Dominion::read(dir)?;
+    let first = export.contest(cli.contest.as_deref())?;
+    let second = export.contest(Some(other))?;
+    let counts = cvr::crosstab(&first, &second);
+
+    let none = String::from("(none)");
+    let rows: Vec<_> = first.0.iter().chain([&none]).collect();
+    let columns: Vec<_> = second.0.iter().chain([&none]).collect();
+    let width = rows.iter().map(|label| label.chars().count()).max().unwrap_or(0);
+
+    let contest = cli.contest.as_deref().unwrap_or("this contest");
+    note!(cli, "First preferences in {contest} (rows) against {other} (columns):");
+
+    let header: Vec<_> = columns.iter().map(|label| format!("{label:>6}")).collect();
+    note!(cli, "{:width$}  {}", "", header.join("  "));
+
+    for (label, row) in rows.iter().zip(&counts) {
+        let cells: Vec<_> = row
+            .iter()
+            .zip(&columns)
+            .map(|(count, column)| format!("{count:>cell$}", cell = column.chars().count().max(6)))
+            .collect();
+
+        note!(cli, "{label:width$}  {}", cells.join("  "));
+    }
+
+    note!(cli);
+    note!(cli);
+
+    Ok(())
+}
+
+fn print_clones(cli: &Cli, votes: &Ballot<String>, percent: f64, names: &DisplayNames) -> Result<()> {
+    if !(0.0..=100.0).contains(&percent) {
+        return Err(anyhow!("--clones is a percentage, from 0 to 100"));
+    }
+
+    let shares = clones::adjacency(votes);
+    let clusters = clones::clusters(&shares, percent / 100.0);
+    let plurality = |votes: &Ballot<String>| Ballot::<String>::pick(&votes.tier()).map(|i| votes.labels()[i].clone());
+    let instant_runoff = |votes: &Ballot<String>| irv::count(votes, rounding(cli)).1;
+    let winners = [("Plurality", plurality(votes)), ("Instant-runoff", instant_runoff(votes))];
+
+    note!(cli, "Possible clones (ranked next to each other on at least {percent}% of the ballots ranking either):");
+
+    if clusters.is_empty() {
+        note!(cli, "None");
+    }
+
+    for members in &clusters {
+        let labels: Vec<_> = members.iter().map(|i| names.get(&votes.labels()[*i])).collect();
+        let merged_label = labels.join(" + ");
+        let lowest = members
+            .iter()
+            .flat_map(|a| members.iter().filter(move |b| a < *b).map(|b| shares[*a][*b]))
+            .fold(1.0, f64::min);
+
+        let merged = clones::merge(votes, members, merged_label.clone());
+        let merged_winners = [plurality(&merged), instant_runoff(&merged)];
+
+        note!(cli);
+        note!(cli, "{merged_label} (at least {:.0}% between any two of them)", lowest * 100.0);
+
+        for ((method, before), after) in winners.iter().zip(merged_winners) {
+            // the merged label isn't one of the candidates, so it's shown as is
+            let shown = |winner: &Option<String>| match winner {
+                Some(winner) if *winner == merged_label => winner.clone(),
+                Some(winner) => String::from(names.get(winner)),
+                None => String::from("nobody"),
+            };
+            // a clone winning separately and the group winning merged is the
+            // same result
+            let same = match before {
+                Some(winner) if members.iter().any(|i| &votes.labels()[*i] == winner) => after.as_ref() == Some(&merged_label),
+                before => *before == after,
+            };
+            let changed = match same {
+                true => "stays the same",
+                false => "changes",
+            };
+
+            note!(cli, "{method} winner: {} separately, {} merged, so it {changed}", shown(before), shown(&after));
+        }
+    }
+
+    note!(cli);
+    note!(cli);
+
+    Ok(())
+}
+
+fn print_strategy(cli: &Cli, votes: &Ballot<String>) {
+    let storage = match votes.storage() {
+        Storage::Dense => "dense",
+        Storage::Sparse => "sparse",
+    };
+    let chosen = match cli.strategy {
+        Some(Strategy::Auto) => format!(" (picked for {} candidates on {} ballots)", votes.count(), votes.ballot_count()),
+        _ => String::new(),
+    };
+    let pairwise = match cfg!(feature = "rayon") {
+        true => "in parallel",
+        false => "sequentially",
+    };
+
+    note!(cli, "Strategy: {storage} storage{chosen}, with head-to-head counts made {pairwise}");
+    note!(cli);
+    note!(cli);
+}
+
+fn run_demo(cli: &Cli, name: Option<&str>) -> Result<()> {
+    let examples: Vec<_> = demo::EXAMPLES
+        .iter()
+        .filter(|example| name.is_none_or(|name| name == example.name))
+        .collect();
+
+    if let (true, Some(name)) = (examples.is_empty(), name) {
+        let names: Vec<_> = demo::EXAMPLES.iter().map(|example| example.name).collect();
+        let names = names.join(", ");
+
+        return Err(anyhow!("no example called {name:?} (try one of {names})"));
+    }
+
+    for example in examples {
+        let votes = example.ballot();
+
+        if !cli.raw {
+            println!("{} ({})", example.title, example.name);
+            println!();
+            println!("{}", example.description);
+            println!();
+
+            for (voters, ranking) in example.profile {
+                let ranking: Vec<_> = ranking.iter().map(|c| example.candidates[*c]).collect();
+                let ranking = ranking.join(" > ");
+
+                println!("{voters} voters: {ranking}");
+            }
+
+            println!();
+            println!();
+        }
+
+        let names = match cli.candidate_ids {
+            true => DisplayNames::default().with_ids(votes.labels()),
+            false => DisplayNames::default(),
+        };
+
+        tally(cli, votes, &names)?;
+    }
+
+    Ok(())
+}
+
+fn run_inspect(file: Option<&Path>, dialect: ranks::Dialect) -> Result<()> {
+    let (rows, columns) = match file {
+        Some(path) => {
+            let file = std::fs::File::open(path)
+                .with_context(|| format!("couldn't open {}", path.display()))?;
+
+            inspect::columns(file, dialect)?
+        }
+        None => inspect::columns(std::io::stdin(), dialect)?,
+    };
+
+    println!("{rows} records, {} columns", columns.len());
+    println!();
+
+    for (i, column) in columns.iter().enumerate() {
+        let name = &column.name;
+        let kind = match column.kind {
+            inspect::Kind::Empty => String::from("empty"),
+            inspect::Kind::Integer { min, max } => format!("whole numbers {min} to {max}"),
+            inspect::Kind::Number => String::from("numbers"),
+            inspect::Kind::Text => String::from("text"),
+        };
+        let distinct = column.distinct.len();
+        let blank = match column.blank {
+            0 => String::new(),
+            blank => format!(", {blank} blank"),
+        };
+
+        println!("Column {i} {name:?}: {kind}, {distinct} distinct values{blank}");
+
+        // listing every value is only useful when there's a handful of them
+        if (1..=10).contains(&distinct) {
+            let values: Vec<_> = column.distinct.iter().map(String::as_str).collect();
+            println!("    {}", values.join(", "));
+        }
+    }
+
+    println!();
+
+    match inspect::guess_ranks(&columns) {
+        Some(guess) => {
+            let (start, len, indexed_at) = (guess.start, guess.len, guess.indexed_at);
+            let end = start + len - 1;
+
+            println!("Columns {start} to {end} look like ranks, indexed at {indexed_at}. Try:");
+            println!("    rankit --start {start} --indexed-at {indexed_at} {len}");
+        }
+        None => println!("No columns look like ranks"),
+    }
+
+    Ok(())
+}
+
+fn run_enter(output: &Path, candidates: &[String]) -> Result<()> {
+    let mut writer = csv::Writer::from_path(output)
+        .with_context(|| format!("couldn't create {}", output.display()))?;
+    let mut all_ranks = vec![];
+
+    writer.write_record(candidates).context("couldn't write the headers")?;
+    writer.flush().context("couldn't write the headers")?;
+
+    println!("Enter each ballot's candidates from most to least preferred, by name or number:");
+
+    for (i, candidate) in candidates.iter().enumerate() {
+        println!("    {} {candidate}", i + 1);
+    }
+
+    println!("Enter a blank line when done.");
+
+    let mut lines = std::io::stdin().lines();
+
+    loop {
+        print!("Ballot {}: ", all_ranks.len() / candidates.len() + 1);
+        std::io::Write::flush(&mut std::io::stdout()).context("couldn't show the prompt")?;
+
+        let Some(line) = lines.next().transpose().context("couldn't read the ballot")? else {
+            println!();
+            break;
+        };
+
+        if line.trim().is_empty() {
+            break;
+        }
+
+        let ranks = match entry::parse_ranking(&line, candidates) {
+            Ok(ranks) => ranks,
+            Err(e) => {
+                println!("    {e}, so it wasn't recorded");
+                continue;
+            }
+        };
+
+        // written straight away so nothing is lost if the count is interrupted
+        let cells: Vec<_> = ranks.iter().map(|rank| (rank + 1).to_string()).collect();
+        writer.write_record(&cells).context("couldn't write the ballot")?;
+        writer.flush().context("couldn't write the ballot")?;
+        all_ranks.extend(ranks);
+
+        let votes = Ballot::new(candidates.to_vec(), all_ranks.clone()).expect("entered ranks are valid");
+        let firsts: Vec<_> = candidates
+            .iter()
+            .zip(votes.tier())
+            .map(|(candidate, count)| format!("{candidate} {}", format_votes(count)))
+            .collect();
+        let leading = votes.runoff().next().map(|(winner, _, _)| winner).unwrap_or_default();
+
+        println!("    First preferences: {}; leading: {leading}", firsts.join(", "));
+    }
+
+    let ballots = all_ranks.len() / candidates.len();
+    println!("Wrote {ballots} ballots to {}", output.display());
+
+    Ok(())
+}
+
+/// Runs the count and prints the results, along with any extra reports asked for.
+fn tally(cli: &Cli, votes: Ballot<String>, names: &DisplayNames) -> Result<()> {
+    // the count would still pick "winners" here, just arbitrarily
+    if votes.count() == 0 || votes.ballot_count() == 0 {
+        let problem = match votes.count() {
+            0 => "there are no candidates",
+            _ => "no ballots were cast",
+        };
+
+        if cli.strict {
+            return Err(anyhow!("{problem}"));
+        }
+
+        match cli.raw {
+            true => eprintln!("warning: {problem}, so nobody was elected"),
+            false => note!(cli, "Nobody was elected, since {problem}"),
+        }
+
+        return Ok(());
+    }
+
+    // checked up front, since only the irv and coombs counts look at them and
+    // the other methods would quietly count as if nobody withdrew
+    let withdrawn = withdrawals(cli, votes.labels())?;
+
+    if !withdrawn.is_empty() && cli.finalists.is_some() {
+        return Err(anyhow!("--withdraw doesn't work with --finalists"));
+    }
+
+    // these only make sense for the tiered count's rounds, and the other
+    // methods would quietly skip them
+    let tiered_only =
+        cli.tie_break.is_some() || cli.rcvis.is_some() || cli.retention || cli.sensitivity.is_some() || cli.highlight_changes || cli.tie_stability.is_some();
+
+    if tiered_only && (cli.method != Method::Tiered || cli.seats.is_some() || cli.finalists.is_some()) {
+        return Err(anyhow!(
+            "--tie-break, --rcvis, --retention, --sensitivity, --highlight-changes and --tie-stability only work with the tiered method"
+        ));
+    }
+
+    if cli.condorcet && (cli.seats.is_some() || cli.finalists.is_some() || !matches!(cli.method, Method::Tiered | Method::Irv | Method::Coombs)) {
+        return Err(anyhow!("--condorcet only works with the tiered, irv and coombs methods"));
+    }
+
+    if let Some(amount) = cli.finalists {
+        print_finalists(cli, &finalists::finalists(&votes, amount), names);
+
+        return Ok(());
+    }
+
+    if cli.collapse_below.is_some_and(|percent| !(0.0..=100.0).contains(&percent)) {
+        return Err(anyhow!("--collapse-below is a percentage, from 0 to 100"));
+    }
+
+    if cli.dowdall && cli.method != Method::Borda {
+        return Err(anyhow!("--dowdall only works with --method borda"));
+    }
+
+    if (cli.approve_up_to.is_some() || cli.approve_above_average || cli.pass_threshold.is_some()) && cli.method != Method::Approval {
+        return Err(anyhow!("--approve-up-to, --approve-above-average and --pass-threshold only work with --method approval"));
+    }
+
+    if cli.pass_threshold.is_some_and(|threshold| !(0.0..100.0).contains(&threshold)) {
+        return Err(anyhow!("the threshold has to be a percentage from 0 up to 100"));
+    }
+
+    if (cli.sankey.is_some() || cli.dot.is_some()) && (cli.seats.is_some() || !matches!(cli.method, Method::Irv | Method::Coombs)) {
+        return Err(anyhow!("--sankey and --dot only work with --method irv or coombs"));
+    }
+
+    if cli.sign_cmd.is_some() && cli.rcvis.is_none() && cli.sankey.is_none() && cli.dot.is_none() && cli.elimination_order.is_none() && cli.bundle.is_none() {
+        return Err(anyhow!("--sign-cmd signs results files, so it needs --rcvis, --sankey, --dot, --elimination-order or --bundle"));
+    }
+
+    // these are printed along with the text report, so they'd end up mixed in
+    // with (or left out of) the results in any other format
+    let text_only = cli.retention || cli.sensitivity.is_some() || cli.highlight_changes || cli.common_patterns.is_some() || cli.tie_stability.is_some();
+
+    if text_only && cli.format != Format::Text {
+        return Err(anyhow!(
+            "--retention, --sensitivity, --highlight-changes, --common-patterns and --tie-stability are part of the text report, so they only work with --format text"
+        ));
+    }
+
+    // the JSON has it beside the count, but the other formats have nowhere to put it
+    if cli.condorcet && !matches!(cli.format, Format::Text | Format::Json) {
+        return Err(anyhow!("--condorcet only works with --format text or json"));
+    }
+
+    write_results_files(cli, &votes, names)?;
+
+    if cli.format != Format::Text {
+        return print_structured(cli, &votes, names);
+    }
+
+    let patterns = cli.common_patterns.map(|amount| common_patterns(&votes, amount, names));
+
+    if let Some(seats) = cli.seats {
+        if seats == 0 {
+            return Err(anyhow!("there has to be at least 1 seat"));
+        }
+
+        print_stv(cli, &count_stv(cli, &votes, seats)?, seats, names);
+
+        if let Some(patterns) = &patterns {
+            print_patterns(patterns);
+        }
+
+        return Ok(());
+    }
+
+    if cli.method == Method::Kemeny {
+        if cli.kemeny_search == KemenySearch::Exact && votes.count() > cli.max_candidates {
+            let (count, max) = (votes.count(), cli.max_candidates);
+
+            return Err(anyhow!(
+                "an exact kemeny-young ranking could take far too long with {count} candidates (the limit is {max}, which --max-candidates can raise, or try --kemeny-search heuristic)"
+            ));
+        }
+
+        print_kemeny(cli, &condorcet::Pairwise::new(&votes), votes.labels(), names);
+
+        if let Some(patterns) = &patterns {
+            print_patterns(patterns);
+        }
+
+        return Ok(());
+    }
+
+    if cli.method == Method::Copeland {
+        if !(0.0..=1.0).contains(&cli.copeland_ties) {
+            return Err(anyhow!("a head-to-head tie has to be worth 0 to 1 points"));
+        }
+
+        print_copeland(cli, &condorcet::Pairwise::new(&votes), votes.labels(), names);
+
+        if let Some(patterns) = &patterns {
+            print_patterns(patterns);
+        }
+
+        return Ok(());
+    }
+
+    if cli.method == Method::Schulze {
+        print_schulze(cli, &condorcet::Pairwise::new(&votes), votes.labels(), names);
+
+        if let Some(patterns) = &patterns {
+            print_patterns(patterns);
+        }
+
+        return Ok(());
+    }
+
+    if cli.method == Method::Borda {
+        let scoring = match cli.dowdall {
+            true => borda::Scoring::Dowdall,
+            false => borda::Scoring::Standard,
+        };
+
+        print_borda(cli, &borda::scores(&votes, scoring), names);
+
+        if let Some(patterns) = &patterns {
+            print_patterns(patterns);
+        }
+
+        return Ok(());
+    }
+
+    if cli.method == Method::Approval {
+        if cli.approve_up_to == Some(0) {
+            return Err(anyhow!("ballots have to approve up to at least rank 1"));
+        }
+
+        let cutoff = match (cli.approve_up_to, cli.approve_above_average) {
+            (Some(top), _) => approval::Cutoff::Top(top),
+            (None, true) => approval::Cutoff::AboveAverage,
+            (None, false) => approval::Cutoff::Ranked,
+        };
+        let total: f64 = votes.weights().iter().sum();
+
+        match cli.pass_threshold {
+            Some(threshold) => print_threshold(cli, &approval::approvals(&votes, cutoff), threshold, total, names),
+            None => print_approval(cli, &approval::approvals(&votes, cutoff), cutoff, total, names),
+        }
+
+        if let Some(patterns) = &patterns {
+            print_patterns(patterns);
+        }
+
+        return Ok(());
+    }
+
+    if cli.method == Method::Plurality {
+        print_plurality(cli, &votes, names);
+
+        if let Some(patterns) = &patterns {
+            print_patterns(patterns);
+        }
+
+        return Ok(());
+    }
+
+    if let Method::Baldwin | Method::Nanson = cli.method {
+        let elimination = match cli.method {
+            Method::Nanson => borda::Elimination::BelowAverage,
+            _ => borda::Elimination::Lowest,
+        };
+        let (rounds, winner) = borda::eliminate(&votes, elimination);
+        print_borda_elimination(cli, &rounds, winner.as_deref(), names);
+
+        if let Some(patterns) = &patterns {
+            print_patterns(patterns);
+        }
+
+        return Ok(());
+    }
+
+    if cli.method == Method::Bucklin {
+        let (rounds, winner) = bucklin::count(&votes, rounding(cli));
+        let total: f64 = votes.weights().iter().sum();
+        print_bucklin(cli, &rounds, winner.as_deref(), total, names);
+
+        if let Some(patterns) = &patterns {
+            print_patterns(patterns);
+        }
+
+        return Ok(());
+    }
+
+    if let Method::Irv | Method::Coombs = cli.method {
+        let (rounds, winner) = irv::count_withdrawing(&votes, rounding(cli), elimination(cli), &withdrawn);
+
+        print_irv(cli, &rounds, winner.as_deref(), names);
+
+        if cli.condorcet {
+            let pairwise = condorcet::Pairwise::new(&votes);
+
+            println!();
+            println!();
+            warn_condorcet(&pairwise, votes.labels(), winner.as_deref(), names);
+            print_condorcet(&pairwise, votes.labels(), names);
+        }
+
+        if !withdrawn.is_empty() && !cli.raw {
+            let (_, without) = irv::count_by(&votes, rounding(cli), elimination(cli));
+
+            if without != winner {
+                let without = without.as_deref().map_or("nobody", |winner| names.get(winner));
+
+                println!("Without the withdrawals, {without} would have won");
+            }
+        }
+
+        if let Some(patterns) = &patterns {
+            print_patterns(patterns);
+        }
+
+        return Ok(());
+    }
+
+    let pairwise = (!cli.raw).then(|| condorcet::Pairwise::new(&votes));
+    let majority_loser = (!cli.raw).then(|| condorcet::majority_loser(&votes)).flatten();
+    let sensitivity = cli.sensitivity.map(|margin| sensitivity::close_rounds(&votes, margin));
+    let tie_stability = match cli.tie_stability {
+        Some(noise) => {
+            let mut rng = rng::Rng::new(cli.seed).with_logging(cli.draw_log.is_some());
+            let stability = sensitivity::tie_stability(&votes, noise, cli.trials, &mut rng);
+            write_draw_log(cli, &rng)?;
+
+            Some(stability)
+        }
+        None => None,
+    };
+    let labels = votes.labels().to_vec();
+    let identical = votes.ballot_count() > 1 && votes.ballots().all(|row| votes.ballots().next() == Some(row));
+    let counted = cli.retention.then(|| votes.clone());
+    // the candidates ranked by each ballot that doesn't rank everyone, which
+    // is all it takes to tell when they run out
+    let partial: Vec<_> = votes
+        .ballots()
+        .zip(votes.weights())
+        .filter(|(row, _)| !cli.raw && row.contains(&UNRANKED))
+        .map(|(row, weight)| ((0..row.len()).filter(|candidate| row[*candidate] != UNRANKED).collect(), *weight))
+        .collect();
+    let (results, ties) = run_count(cli, votes)?;
+    let retention: Vec<HashMap<_, _>> = match counted {
+        Some(votes) => {
+            let rounds: Vec<Vec<_>> = results
+                .iter()
+                .map(|(winner, _, others)| {
+                    std::iter::once(winner).chain(others.iter().map(|(label, _)| label)).cloned().collect()
+                })
+                .collect();
+
+            rounds
+                .iter()
+                .zip(retention::retention(&votes, &rounds))
+                .map(|(round, retention)| round.iter().cloned().zip(retention).collect())
+                .collect()
+        }
+        None => vec![],
+    };
+    let winner = results.first().map(|(winner, _, _)| winner.clone());
+
+    if cli.raw {
+        // raw output is only winners, but how a tie was broken still needs a record
+        for tie in &ties {
+            eprintln!("{}", describe_tie(cli, tie, names));
+        }
+
+        for winner in results.into_iter().map(|(winner, _, _)| winner) {
+            println!("{}", names.label(&winner));
+        }
+    }
+    else {
+        // a round with hundreds of candidates in it would scroll off the
+        // terminal, so the smallest are summed up in one line instead
+        let collapse_below = match cli.collapse_below {
+            Some(percent) => percent,
+            None if labels.len() >= COLLAPSE_FROM => 1.0,
+            None => 0.0,
+        };
+        let mut any_collapsed = false;
+        let winners: Vec<_> = results.iter().map(|(winner, _, _)| winner.as_str()).collect();
+        let exhausted = exhausted_by_round(&partial, &labels, &winners);
+
+        let rounds: Vec<_> = results.into_iter().map(standings).collect();
+        let highlighted = match cli.highlight_changes {
+            true => highlight(&rounds),
+            false => vec![],
+        };
+
+        for (i, standings) in rounds.iter().enumerate() {
+            let cardinal = i + 1;
+            let changes: Vec<_> = standings
+                .iter()
+                .enumerate()
+                .map(|(position, (label, _))| {
+                    let change = highlighted.get(i).map_or("", |round| round[position].as_str());
+                    let retained = retention
+                        .get(i)
+                        .and_then(|round| round.get(label))
+                        .map(describe_retention)
+                        .unwrap_or_default();
+
+                    format!("{change}{retained}")
+                })
+                .collect();
+
+            let (winner, counts) = &standings[0];
+
+            // the winner has the most votes, so nobody left has any and
+            // the order they'd be picked in is meaningless
+            if *counts == 0.0 {
+                let unplaced: Vec<_> = standings.iter().map(|(label, _)| names.get(label)).collect();
+                let unplaced = unplaced.join(", ");
+
+                println!("Unplaced, since no ballots rank them: {unplaced}");
+                println!();
+                println!();
+
+                break;
+            }
+
+            let winner = names.get(winner);
+            let counts = rounding(cli).format(*counts);
+            let change = &changes[0];
+
+            if labels.len() == 1 {
+                println!("Winner #{cardinal}: {winner}, elected unopposed with {counts} votes");
+            } else {
+                println!("Winner #{cardinal}: {winner} with {counts} votes{change}");
+            }
+
+            let round_total: f64 = standings.iter().map(|(_, count)| count).sum();
+            let cutoff = round_total * collapse_below / 100.0;
+            let (mut collapsed, mut collapsed_votes) = (0, 0.0);
+
+            for ((label, count), change) in standings.iter().zip(&changes).skip(1) {
+                if *count < cutoff {
+                    collapsed += 1;
+                    collapsed_votes += count;
+                    continue;
+                }
+
+                let label = names.get(label);
+                let count = rounding(cli).format(*count);
+                println!("{label}: {count}{change}");
+            }
+
+            if collapsed > 0 {
+                any_collapsed = true;
+                let collapsed_votes = rounding(cli).format(collapsed_votes);
+                println!("...and {collapsed} more with under {collapse_below}% of the votes ({collapsed_votes} between them)");
+            }
+
+            if exhausted[i] > 0.0 {
+                println!("Exhausted: {}", rounding(cli).format(exhausted[i]));
+            }
+
+            if let Some(tie) = ties.iter().find(|tie| tie.round == cardinal) {
+                println!("{}", describe_tie(cli, tie, names));
+            }
+
+            println!();
+            println!();
+        }
+
+        if any_collapsed {
+            println!("Candidates with under {collapse_below}% of a round's votes were summed up; --collapse-below 0 lists them all, and --rcvis writes every round in full.");
+            println!();
+        }
+    }
+
+    if identical && labels.len() > 1 && !cli.raw {
+        println!("Note: every ballot is identical, so the winners simply follow its ranking");
+        println!();
+    }
+
+    if let Some(pairwise) = pairwise {
+        let index = labels.iter().position(|l| Some(l) == winner.as_ref());
+        let shown = names.get(winner.as_deref().unwrap_or_default());
+
+        if index.is_some() && index == pairwise.loser() {
+            println!("Warning: {shown} won, but is a Condorcet loser (every other candidate beats them head-to-head)");
+            println!();
+        }
+
+        if index.is_some() && index == majority_loser {
+            println!("Warning: {shown} won, but is a majority loser (most voters ranked them last)");
+            println!();
+        }
+
+        if cli.condorcet {
+            warn_condorcet(&pairwise, &labels, winner.as_deref(), names);
+            print_condorcet(&pairwise, &labels, names);
+        }
+    }
+
+    if let Some(patterns) = &patterns {
+        print_patterns(patterns);
+    }
+
+    if let (Some(sensitivity), Some(margin)) = (sensitivity, cli.sensitivity) {
+        print_sensitivity(&sensitivity, margin, names);
+    }
+
+    if let (Some(stability), Some(noise)) = (tie_stability, cli.tie_stability) {
+        print_tie_stability(&stability, noise, names);
+    }
+
+    Ok(())
+}
+
+/// Writes the results files asked for (--rcvis, --sankey, --dot and
+/// --elimination-order), which are the same whatever --format the results are
+/// written in.
+fn write_results_files(cli: &Cli, votes: &Ballot<String>, names: &DisplayNames) -> Result<()> {
+    if cli.sankey.is_some() || cli.dot.is_some() {
+        let (rounds, winner) = irv::count_withdrawing(votes, rounding(cli), elimination(cli), &withdrawals(cli, votes.labels())?);
+
+        if let Some(path) = &cli.sankey {
+            let json = serde_json::to_string_pretty(&sankey::plotly(&rounds, names)).expect("json values always serialize");
+
+            std::fs::write(path, json).with_context(|| format!("couldn't write the Sankey diagram to {}", path.display()))?;
+            sign(cli, path)?;
+        }
+
+        if let Some(path) = &cli.dot {
+            std::fs::write(path, dot::graph(&rounds, winner.as_deref(), names))
+                .with_context(|| format!("couldn't write the graph to {}", path.display()))?;
+            sign(cli, path)?;
+        }
+    }
+
+    if let Some(path) = &cli.rcvis {
+        let total: f64 = votes.weights().iter().sum();
+        let (results, _) = run_count(cli, votes.clone())?;
+        let json = serde_json::to_string_pretty(&rcvis::summary(&results, total, names)).expect("json values always serialize");
+
+        std::fs::write(path, json)
+            .with_context(|| format!("couldn't write RCVIS results to {}", path.display()))?;
+        sign(cli, path)?;
+    }
+
+    if let Some(path) = &cli.elimination_order {
+        let result = structured(cli, votes, "--elimination-order")?;
+        let mut writer = csv::Writer::from_path(path).with_context(|| format!("couldn't create {}", path.display()))?;
+        let header = match cli.candidate_ids {
+            true => &["round", "id", "candidate", "outcome"][..],
+            false => &["round", "candidate", "outcome"],
+        };
+        writer.write_record(header).context("couldn't write the elimination order")?;
+
+        for (round, candidate, outcome) in json::order(&result) {
+            let mut record = vec![round.to_string()];
+
+            if cli.candidate_ids {
+                record.push(id_cell(names, &candidate));
+            }
+
+            record.extend([candidate, outcome.to_owned()]);
+            writer.write_record(record).context("couldn't write the elimination order")?;
+        }
+
+        writer.flush().context("couldn't write the elimination order")?;
+        sign(cli, path)?;
+    }
+
+    Ok(())
+}
+
+/// The whole count as JSON, for the methods counted in rounds.
+fn structured(cli: &Cli, votes: &Ballot<String>, what: &str) -> Result<serde_json::Value> {
+    let method = cli.method.to_possible_value().map(|value| value.get_name().to_owned()).unwrap_or_default();
+
+    let mut result = match (cli.seats, cli.method) {
+        (Some(0), _) => return Err(anyhow!("there has to be at least 1 seat")),
+        (Some(seats), _) => json::stv(&count_stv(cli, votes, seats)?, seats),
+        (None, Method::Tiered) => {
+            let total: f64 = votes.weights().iter().sum();
+            let (rounds, _) = run_count(cli, votes.clone())?;
+
+            json::tiered(&rounds, total)
+        }
+        (None, Method::Irv | Method::Coombs) => {
+            let withdrawals = withdrawals(cli, votes.labels())?;
+            let (rounds, winner) = irv::count_withdrawing(votes, rounding(cli), elimination(cli), &withdrawals);
+
+            json::irv(&method, &rounds, winner.as_deref())
+        }
+        (None, Method::Bucklin) => {
+            let (rounds, winner) = bucklin::count(votes, rounding(cli));
+
+            json::bucklin(&rounds, winner.as_deref())
+        }
+        (None, Method::Baldwin | Method::Nanson) => {
+            let elimination = match cli.method {
+                Method::Nanson => borda::Elimination::BelowAverage,
+                _ => borda::Elimination::Lowest,
+            };
+            let (rounds, winner) = borda::eliminate(votes, elimination);
+
+            json::borda_elimination(&method, &rounds, winner.as_deref())
+        }
+        _ => return Err(anyhow!("{what} only works with methods counted in rounds (tiered, irv, coombs, bucklin, baldwin, nanson, or --seats)")),
+    };
+
+    if cli.condorcet {
+        result["condorcet"] = json::condorcet(&condorcet::Pairwise::new(votes), votes.labels());
+    }
+
+    Ok(result)
+}
+
+/// Writes the whole count as JSON, a CSV table, an HTML page or Markdown, for
+/// the methods counted in rounds.
+fn print_structured(cli: &Cli, votes: &Ballot<String>, names: &DisplayNames) -> Result<()> {
+    let format = cli.format.to_possible_value().map(|value| value.get_name().to_owned()).unwrap_or_default();
+    let mut result = structured(cli, votes, &format!("--format {format}"))?;
+
+    if let Some(ids) = json::ids(votes.labels(), names) {
+        result["candidate_ids"] = ids;
+    }
+
+    match cli.format {
+        Format::Json => {
+            println!("{}", serde_json::to_string_pretty(&result).expect("json values always serialize"));
+
+            return Ok(());
+        }
+        Format::Html => {
+            print!("{}", html::report(&result, names));
+
+            return Ok(());
+        }
+        Format::Markdown => {
+            print!("{}", markdown::report(&result, names));
+
+            return Ok(());
+        }
+        Format::Text | Format::Csv => {}
+    }
+
+    let mut writer = csv::Writer::from_writer(std::io::stdout());
+    let header = match cli.candidate_ids {
+        true => &["round", "id", "candidate", "votes", "status"][..],
+        false => &["round", "candidate", "votes", "status"],
+    };
+    writer.write_record(header).context("couldn't write the table")?;
+
+    for row in json::rows(&result) {
+        let mut record = vec![row.round.to_string()];
+
+        if cli.candidate_ids {
+            record.push(id_cell(names, &row.candidate));
+        }
+
+        record.extend([row.candidate, format_votes(row.votes), row.status.to_owned()]);
+        writer.write_record(record).context("couldn't write the table")?;
+    }
+
+    writer.flush().context("couldn't write the table")?;
+
+    Ok(())
+}
+
+/// The candidate's number for a column of its own in the CSV outputs, blank
+/// for rows that aren't a candidate (like exhausted votes).
+fn id_cell(names: &DisplayNames, label: &str) -> String {
+    names.id(label).map(|id| id.to_string()).unwrap_or_default()
+}
+
+/// Pipes a results file through --sign-cmd, if there is one, and writes the
+/// signature it prints to the same path with .sig on the end.
+fn sign(cli: &Cli, path: &Path) -> Result<()> {
+    if cli.sign_cmd.is_none() {
+        return Ok(());
+    }
+
+    let contents = std::fs::read(path).with_context(|| format!("couldn't read {} to sign it", path.display()))?;
+    let output = signature(cli, &contents, &path.display().to_string())?.expect("there's a --sign-cmd");
+
+    let mut signature = path.as_os_str().to_owned();
+    signature.push(".sig");
+    let signature = PathBuf::from(signature);
+
+    std::fs::write(&signature, output).with_context(|| format!("couldn't write the signature to {}", signature.display()))
+}
+
+/// What --sign-cmd prints for the contents of a results file, or nothing
+/// without a --sign-cmd.
+fn signature(cli: &Cli, contents: &[u8], what: &str) -> Result<Option<Vec<u8>>> {
+    let Some(command) = &cli.sign_cmd else {
+        return Ok(None);
+    };
+
+    let mut child = std::process::Command::new("sh")
+        .args(["-c", command])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .with_context(|| format!("couldn't run {command:?}"))?;
+
+    // the command might not read everything before failing, which it reports itself
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = std::io::Write::write_all(&mut stdin, contents);
+    }
+
+    let output = child.wait_with_output().with_context(|| format!("couldn't run {command:?}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = match stderr.trim() {
+            "" => output.status.to_string(),
+            stderr => stderr.to_owned(),
+        };
+
+        return Err(anyhow!("{command:?} failed to sign {what}: {reason}"));
+    }
+
+    Ok(Some(output.stdout))
+}
+
+/// The votes of ballots that don't rank anyone still standing in each round
+/// of a tiered count, given the candidates (by index) each partial ballot
+/// ranks and who won each round: a ballot runs out once everyone it ranked
+/// has won.
+fn exhausted_by_round(partial: &[(Vec<usize>, f64)], labels: &[String], winners: &[&str]) -> Vec<f64> {
+    let won_in: Vec<_> = labels.iter().map(|label| winners.iter().position(|winner| winner == label)).collect();
+    let mut exhausted = vec![0.0; winners.len()];
+
+    for (ranked, weight) in partial {
+        // the round the ballot runs out in, unless someone it ranked never wins
+        let from = ranked.iter().try_fold(0, |from: usize, candidate| won_in[*candidate].map(|round| from.max(round + 1)));
+
+        for round in exhausted.iter_mut().skip(from.unwrap_or(usize::MAX)) {
+            *round += weight;
+        }
+    }
+
+    exhausted
+}
+
+/// Counts with whichever method was asked for, along with any ties that the
+/// tie-break command settled.
+fn run_count(cli: &Cli, votes: Ballot<String>) -> Result<(Vec<rcvis::Round>, Vec<tiebreak::TieBreak>)> {
+    #[cfg(feature = "plugins")]
+    if let Some(path) = &cli.plugin {
+        return Ok((plugin::Plugin::load(path)?.tally(&votes)?, vec![]));
+    }
+
+    match &cli.tie_break {
+        Some(command) => tiebreak::runoff(votes, command, rounding(cli)),
+        None => Ok((votes.runoff_rounded(rounding(cli)).collect(), vec![])),
+    }
+}
+
+fn elimination(cli: &Cli) -> irv::Elimination {
+    match cli.method {
+        Method::Coombs => irv::Elimination::MostLast,
+        _ => irv::Elimination::FewestFirst,
+    }
+}
+
+fn rounding(cli: &Cli) -> Rounding {
+    match cli.rounding {
+        RoundingRule::Exact => Rounding::Exact,
+        RoundingRule::Truncate => Rounding::Truncate(cli.decimals),
+        RoundingRule::HalfUp => Rounding::HalfUp(cli.decimals),
+    }
+}
+
+fn describe_tie(cli: &Cli, tie: &tiebreak::TieBreak, names: &DisplayNames) -> String {
+    let round = tie.round;
+    let tied: Vec<_> = tie.tied.iter().map(|label| names.get(label)).collect();
+    let tied = tied.join(", ");
+    let picked = names.get(&tie.picked);
+    let command = cli.tie_break.as_deref().unwrap_or_default();
+
+    format!("Round {round} tied between {tied}; {command:?} picked {picked}")
+}
+
+/// The `amount` most common rankings (by weight) in A>B=C>D notation, most
+/// common first.
+fn common_patterns(votes: &Ballot<String>, amount: usize, names: &DisplayNames) -> Vec<(String, f64)> {
+    let labels: Vec<_> = votes.labels().iter().map(|label| names.get(label)).collect();
+    let mut patterns: Vec<(String, f64)> = vec![];
+    // where each pattern is in the list, so big elections aren't searched
+    // through once per ballot
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for (row, weight) in votes.ballots().zip(votes.weights()) {
+        let pattern = notation::format(&row, &labels);
+
+        match positions.get(&pattern) {
+            Some(position) => patterns[*position].1 += weight,
+            None => {
+                positions.insert(pattern.clone(), patterns.len());
+                patterns.push((pattern, *weight));
+            }
+        }
+    }
+
+    // stable, so equally common rankings stay in the order they first appeared
+    patterns.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    patterns.truncate(amount);
+    patterns
+}
+
+fn print_patterns(patterns: &[(String, f64)]) {
+    println!("Most common rankings:");
+
+    for (pattern, count) in patterns {
+        let voters = match *count == 1.0 {
+            true => String::from("1 voter"),
+            false => format!("{} voters", format_votes(*count)),
+        };
+
+        match pattern.is_empty() {
+            true => println!("{voters} didn't rank anyone"),
+            false => println!("{voters} ranked {pattern}"),
+        }
+    }
+
+    println!();
+}
+
+/// Takes every candidate not in --only off the ballots.
+fn only(cli: &Cli, mut votes: Ballot<String>, names: &DisplayNames) -> Result<Ballot<String>> {
+    let only: Vec<_> = cli.only.iter().map(|candidate| candidate.trim()).collect();
+
+    if let Some(unknown) = only.iter().find(|candidate| !votes.labels().iter().any(|label| label == *candidate)) {
+        return Err(anyhow!("no candidate called {unknown:?} to count with --only"));
+    }
+
+    let mut removed = vec![];
+
+    // from the end, so the indexes of the ones still to go don't move
+    for index in (0..votes.count()).rev() {
+        if !only.contains(&votes.labels()[index].as_str()) {
+            removed.push(names.get(&votes.take(index)).to_owned());
+        }
+    }
+
+    if !cli.raw && !removed.is_empty() {
+        removed.reverse();
+
+        let kept: Vec<_> = votes.labels().iter().map(|label| names.get(label)).collect();
+
+        note!(cli, "Counting only {}, without {}", kept.join(", "), removed.join(", "));
+        note!(cli);
+        note!(cli);
+    }
+
+    Ok(votes)
+}
+
+/// The --withdraw candidates (by index) and the rounds they withdraw after.
+fn withdrawals(cli: &Cli, labels: &[String]) -> Result<Vec<(usize, usize)>> {
+    if !cli.withdraw.is_empty() && (cli.seats.is_some() || !matches!(cli.method, Method::Irv | Method::Coombs)) {
+        return Err(anyhow!("--withdraw only works with --method irv or coombs"));
+    }
+
+    cli.withdraw
+        .iter()
+        .map(|withdrawal| {
+            let (round, candidate) = withdrawal
+                .split_once(':')
+                .with_context(|| format!("{withdrawal:?} isn't ROUND:CANDIDATE"))?;
+            let round: usize = round
+                .trim()
+                .parse()
+                .ok()
+                .filter(|round| *round > 0)
+                .with_context(|| format!("invalid round {round:?}, rounds are numbered from 1"))?;
+            let candidate = labels
+                .iter()
+                .position(|label| label == candidate.trim())
+                .with_context(|| format!("no candidate called {:?} to withdraw", candidate.trim()))?;
+
+            Ok((candidate, round))
+        })
+        .collect()
+}
+
+fn print_irv(cli: &Cli, rounds: &[irv::Round<String>], winner: Option<&str>, names: &DisplayNames) {
+    if cli.raw {
+        if let Some(winner) = winner {
+            println!("{}", names.label(winner));
+        }
+
+        return;
+    }
+
+    for (i, round) in rounds.iter().enumerate() {
+        println!("Round {}:", i + 1);
+
+        for (label, votes) in &round.tally {
+            println!("{}: {}", names.get(label), rounding(cli).format(*votes));
+        }
+
+        if round.exhausted > 0.0 {
+            println!("Exhausted: {}", rounding(cli).format(round.exhausted));
+        }
+
+        if let Some(eliminated) = &round.eliminated {
+            let eliminated = names.get(eliminated);
+            let last = rounding(cli).format(round.eliminated_with);
+
+            match (elimination(cli), round.tied) {
+                (irv::Elimination::FewestFirst, true) => {
+                    println!("Eliminated: {eliminated} (tied for the fewest votes, and listed first)")
+                }
+                (irv::Elimination::FewestFirst, false) => println!("Eliminated: {eliminated}"),
+                (irv::Elimination::MostLast, true) => {
+                    println!("Eliminated: {eliminated}, ranked last by {last} (tied for the most, and listed first)")
+                }
+                (irv::Elimination::MostLast, false) => println!("Eliminated: {eliminated}, ranked last by {last}"),
+            }
+        }
+
+        for withdrawn in &round.withdrawn {
+            println!("Withdrew: {}", names.get(withdrawn));
+        }
+
+        println!();
+        println!();
+    }
+
+    if let (Some(winner), Some(last)) = (winner, rounds.last()) {
+        let votes = last.tally.iter().map(|(_, votes)| votes).sum::<f64>();
+        let (_, winning) = &last.tally[0];
+        let winning = rounding(cli).format(*winning);
+        let votes = rounding(cli).format(votes);
+
+        println!("Winner: {} with {winning} of {votes} continuing votes", names.get(winner));
+    }
+}
+
+fn print_kemeny(cli: &Cli, pairwise: &condorcet::Pairwise, labels: &[String], names: &DisplayNames) {
+    let exact = match cli.kemeny_search {
+        KemenySearch::Auto => labels.len() <= cli.max_candidates,
+        KemenySearch::Exact => true,
+        KemenySearch::Heuristic => false,
+    };
+    let (order, agreement) = match exact {
+        true => pairwise.kemeny(),
+        false => pairwise.kemeny_heuristic(),
+    };
+
+    if cli.raw {
+        for label in order.iter().map(|i| &labels[*i]) {
+            println!("{}", names.label(label));
+        }
+
+        return;
+    }
+
+    match (exact, cli.kemeny_search) {
+        (true, _) => println!("Kemeny-Young ranking (exact):"),
+        (false, KemenySearch::Auto) => println!(
+            "Kemeny-Young ranking (heuristic, since there are more than {} candidates, so it may not be the best there is):",
+            cli.max_candidates
+        ),
+        (false, _) => println!("Kemeny-Young ranking (heuristic, so it may not be the best there is):"),
+    }
+
+    for (place, candidate) in order.iter().enumerate() {
+        println!("{}. {}", place + 1, names.get(&labels[*candidate]));
+    }
+
+    let total: f64 = pairwise.prefer.iter().flatten().sum();
+
+    println!();
+    println!(
+        "It agrees with {} of the {} head-to-head preferences on the ballots",
+        format_votes(agreement),
+        format_votes(total)
+    );
+    println!();
+}
+
+fn print_copeland(cli: &Cli, pairwise: &condorcet::Pairwise, labels: &[String], names: &DisplayNames) {
+    let scores = pairwise.copeland(cli.copeland_ties);
+
+    if cli.raw {
+        for label in scores.iter().map(|(i, _)| &labels[*i]) {
+            println!("{}", names.label(label));
+        }
+
+        return;
+    }
+
+    println!("Copeland scores:");
+
+    for (place, (candidate, score)) in scores.iter().enumerate() {
+        let (wins, losses, ties) = pairwise.record(*candidate);
+        let name = names.get(&labels[*candidate]);
+        let score = format_votes(*score);
+
+        println!("{}. {name}: score {score} (won {wins}, lost {losses}, tied {ties})", place + 1);
+    }
+
+    println!();
+}
+
+fn print_schulze(cli: &Cli, pairwise: &condorcet::Pairwise, labels: &[String], names: &DisplayNames) {
+    let order = pairwise.schulze();
+
+    if cli.raw {
+        for label in order.iter().map(|i| &labels[*i]) {
+            println!("{}", names.label(label));
+        }
+
+        return;
+    }
+
+    let name = |i: usize| names.get(&labels[i]);
+    let paths = pairwise.strongest_paths();
+
+    println!("Schulze ranking:");
+
+    for (i, candidate) in order.iter().copied().enumerate() {
+        println!("{}. {}", i + 1, name(candidate));
+    }
+
+    if order.len() > 1 {
+        println!();
+        println!("Strongest paths between each place and the next:");
+    }
+
+    for pair in order.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let for_a = format_votes(paths[a][b]);
+        let for_b = format_votes(paths[b][a]);
+
+        match paths[a][b] > paths[b][a] {
+            true => println!("{} > {}: {for_a} to {for_b}", name(a), name(b)),
+            false => println!("{} = {}: {for_a} each way", name(a), name(b)),
+        }
+    }
+
+    println!();
+}
+
+fn print_borda(cli: &Cli, scores: &[(String, f64)], names: &DisplayNames) {
+    if cli.raw {
+        for (label, _) in scores {
+            println!("{}", names.label(label));
+        }
+
+        return;
+    }
+
+    let scoring = if cli.dowdall { "Dowdall scores" } else { "Borda scores" };
+    println!("{scoring}:");
+
+    for (i, (label, score)) in scores.iter().enumerate() {
+        println!("{}. {}: {}", i + 1, names.get(label), rounding(cli).format(*score));
+    }
+
+    println!();
+}
+
+fn check_score_range(cli: &Cli) -> Result<()> {
+    match cli.min_score.is_finite() && cli.max_score.is_finite() && cli.min_score < cli.max_score {
+        true => Ok(()),
+        false => Err(anyhow!("the lowest score has to be below the highest")),
+    }
+}
+
+/// Puts each candidate's scores on the same scale for --normalize, and
+/// reports the scale they had, since the count depends on it.
+fn normalize_scores(cli: &Cli, labels: &[String], scores: &mut [Vec<Option<f64>>]) {
+    let Some(normalize) = cli.normalize else {
+        return;
+    };
+    let normalization = match normalize {
+        Normalize::MinMax => score::Normalization::MinMax,
+        Normalize::ZScore => score::Normalization::ZScore,
+    };
+    let scales = score::normalize(scores, normalization);
+
+    if cli.raw {
+        return;
+    }
+
+    note!(cli, "Scores normalized for each candidate, from:");
+
+    for (label, scale) in labels.iter().zip(scales) {
+        match normalize {
+            Normalize::MinMax => note!(cli, "{label}: {} to {}", format_votes(scale.center), format_votes(scale.center + scale.spread)),
+            Normalize::ZScore => note!(cli, "{label}: {} on average, with a standard deviation of {}", format_votes(scale.center), format_votes(scale.spread)),
+        }
+    }
+
+    note!(cli);
+    note!(cli);
+}
+
+fn run_scores(cli: &Cli) -> Result<()> {
+    if !matches!(cli.input_format, InputFormat::Csv | InputFormat::Scores) || cli.manifest.is_some() || cli.files.len() > 1 || cli.command.is_some() {
+        return Err(anyhow!("scores can only be read from csv input, from a single file or stdin"));
+    }
+
+    check_score_range(cli)?;
+
+    let (labels, mut ballots) = match input_file(cli) {
+        Some(path) => {
+            let file = std::fs::File::open(path).with_context(|| format!("couldn't open {}", path.display()))?;
+
+            ranks::read_scores(file, cli.start, cli.len, cli.min_score, cli.max_score).with_context(|| format!("in {}", path.display()))?
+        }
+        None => ranks::read_scores(std::io::stdin(), cli.start, cli.len, cli.min_score, cli.max_score)?,
+    };
+
+    if labels.is_empty() || ballots.is_empty() {
+        return Err(anyhow!("there has to be at least one candidate and one ballot"));
+    }
+
+    let names = match &cli.display_names {
+        Some(path) => DisplayNames::read(path, &labels)?,
+        None => DisplayNames::default(),
+    };
+    let names = match cli.candidate_ids {
+        true => names.with_ids(&labels),
+        false => names,
+    };
+
+    normalize_scores(cli, &labels, &mut ballots);
+
+    let tally = score::tally(&labels, &ballots);
+    let runoff = match cli.method {
+        Method::Star => score::star(&labels, &ballots),
+        _ => None,
+    };
+    let winner = match &runoff {
+        Some(runoff) => &runoff.winner,
+        None => &tally[0].label,
+    };
+
+    if cli.raw {
+        println!("{}", names.label(winner));
+
+        return Ok(());
+    }
+
+    match cli.normalize {
+        Some(_) => println!("Normalized scores:"),
+        None => println!("Scores ({} to {}):", cli.min_score, cli.max_score),
+    }
+
+    for (i, candidate) in tally.iter().enumerate() {
+        println!(
+            "{}. {}: {} in total, {} on average from {} ballots",
+            i + 1,
+            names.get(&candidate.label),
+            rounding(cli).format(candidate.total),
+            rounding(cli).format(candidate.average()),
+            candidate.scored
+        );
+    }
+
+    println!();
+    println!();
+
+    if let Some(runoff) = &runoff {
+        let [first, second] = runoff.finalists.each_ref().map(|label| names.get(label));
+        let [first_preferred, second_preferred] = runoff.preferred;
+
+        println!("Runoff between {first} and {second}:");
+        println!("{first}: scored higher on {first_preferred} ballots");
+        println!("{second}: scored higher on {second_preferred} ballots");
+        println!("No preference: {}", runoff.no_preference);
+        println!();
+        println!();
+    }
+
+    println!("Winner: {}", names.get(winner));
+
+    Ok(())
+}
+
+fn print_approval(cli: &Cli, approvals: &[(String, f64)], cutoff: approval::Cutoff, total: f64, names: &DisplayNames) {
+    let most = approvals.first().map(|(_, approved)| *approved);
+    let winners: Vec<_> = approvals
+        .iter()
+        .filter(|(_, approved)| Some(*approved) == most)
+        .map(|(label, _)| label)
+        .collect();
+
+    if cli.raw {
+        for winner in winners {
+            println!("{}", names.label(winner));
+        }
+
+        return;
+    }
+
+    // the conversion is part of the result, since another would give another
+    match cutoff {
+        approval::Cutoff::Top(1) => println!("Approvals (first preferences only):"),
+        approval::Cutoff::Top(top) => println!("Approvals (ranked {top} or higher):"),
+        approval::Cutoff::AboveAverage => println!("Approvals (ranked at or above the average rank of each ballot):"),
+        approval::Cutoff::Ranked => println!("Approvals (every ranked candidate):"),
+    }
+
+    for (i, (label, approved)) in approvals.iter().enumerate() {
+        let share = approved / total * 100.0;
+
+        println!("{}. {}: {} ({share:.1}% of ballots)", i + 1, names.get(label), rounding(cli).format(*approved));
+    }
+
+    println!();
+
+    let most = most.map(|most| rounding(cli).format(most)).unwrap_or_default();
+    let winners: Vec<_> = winners.into_iter().map(|winner| names.get(winner)).collect();
+
+    match winners.as_slice() {
+        [] => {}
+        [winner] => println!("Winner: {winner} with {most} approvals"),
+        winners => println!("Tied winners: {} with {most} approvals each", winners.join(", ")),
+    }
+}
+
+fn print_threshold(cli: &Cli, approvals: &[(String, f64)], threshold: f64, total: f64, names: &DisplayNames) {
+    let passed: Vec<_> = approvals
+        .iter()
+        .filter(|(_, approved)| approval::passes(*approved, total, threshold))
+        .map(|(label, _)| label)
+        .collect();
+
+    if cli.raw {
+        for option in passed {
+            println!("{}", names.label(option));
+        }
+
+        return;
+    }
+
+    println!("Options passing with more than {threshold}% approval:");
+
+    for (i, (label, approved)) in approvals.iter().enumerate() {
+        let share = approved / total * 100.0;
+        let result = match passed.contains(&label) {
+            true => "passes",
+            false => "fails",
+        };
+
+        println!("{}. {}: {} ({share:.1}% of ballots), {result}", i + 1, names.get(label), rounding(cli).format(*approved));
+    }
+
+    println!();
+
+    match passed.len() {
+        0 => println!("Nothing passed"),
+        count => println!("{count} of {} options passed", approvals.len()),
+    }
+}
+
+/// Each candidate's first preferences, most first, and whoever has the most
+/// once rounded (all of them, if that's a tie).
+fn plurality<'a>(cli: &Cli, votes: &'a Ballot<String>) -> (Vec<(&'a String, f64)>, Vec<&'a String>) {
+    let mut firsts: Vec<_> = votes.labels().iter().zip(votes.tier()).collect();
+    firsts.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    let most = firsts.first().map(|(_, votes)| rounding(cli).apply(*votes));
+    let winners = firsts
+        .iter()
+        .filter(|(_, votes)| Some(rounding(cli).apply(*votes)) == most)
+        .map(|(label, _)| *label)
+        .collect();
+
+    (firsts, winners)
+}
+
+fn print_plurality(cli: &Cli, votes: &Ballot<String>, names: &DisplayNames) {
+    let total: f64 = votes.weights().iter().sum();
+    let (firsts, winners) = plurality(cli, votes);
+    let most = firsts.first().map(|(_, votes)| rounding(cli).apply(*votes));
+
+    if cli.raw {
+        for winner in winners {
+            println!("{}", names.label(winner));
+        }
+
+        return;
+    }
+
+    println!("First preferences:");
+
+    for (label, firsts) in &firsts {
+        let share = match total {
+            0.0 => 0.0,
+            total => firsts / total * 100.0,
+        };
+
+        println!("{}: {} ({share:.1}%)", names.get(label), rounding(cli).format(rounding(cli).apply(*firsts)));
+    }
+
+    let nobody = total - firsts.iter().map(|(_, votes)| votes).sum::<f64>();
+
+    if nobody > 0.0 {
+        println!("No first preference: {}", rounding(cli).format(rounding(cli).apply(nobody)));
+    }
+
+    println!();
+
+    let most = most.map(|most| rounding(cli).format(most)).unwrap_or_default();
+    let winners: Vec<_> = winners.into_iter().map(|winner| names.get(winner)).collect();
+
+    match winners.as_slice() {
+        [] => {}
+        [winner] => println!("Winner: {winner} with {most} votes"),
+        winners => println!("Tied winners: {} with {most} votes each", winners.join(", ")),
+    }
+}
+
+fn print_borda_elimination(cli: &Cli, rounds: &[borda::Round<String>], winner: Option<&str>, names: &DisplayNames) {
+    if cli.raw {
+        if let Some(winner) = winner {
+            println!("{}", names.label(winner));
+        }
+
+        return;
+    }
+
+    for (i, round) in rounds.iter().enumerate() {
+        println!("Round {}:", i + 1);
+
+        for (label, score) in &round.scores {
+            println!("{}: {}", names.get(label), rounding(cli).format(*score));
+        }
+
+        if !round.eliminated.is_empty() {
+            let eliminated: Vec<_> = round.eliminated.iter().map(|label| names.get(label)).collect();
+            let eliminated = eliminated.join(", ");
+
+            match (cli.method, round.tied) {
+                (Method::Nanson, _) => println!("Eliminated (below the average score): {eliminated}"),
+                (_, true) => println!("Eliminated: {eliminated} (tied for the lowest score, and listed first)"),
+                (_, false) => println!("Eliminated: {eliminated}"),
+            }
+        }
+
+        println!();
+        println!();
+    }
+
+    if let (Some(winner), Some(last)) = (winner, rounds.last()) {
+        match last.scores.len() {
+            1 => println!("Winner: {}", names.get(winner)),
+            _ => println!("Winner: {} (everyone left had the same score, and they're listed first)", names.get(winner)),
+        }
+    }
+}
+
+fn print_bucklin(cli: &Cli, rounds: &[bucklin::Round<String>], winner: Option<&str>, total: f64, names: &DisplayNames) {
+    if cli.raw {
+        if let Some(winner) = winner {
+            println!("{}", names.label(winner));
+        }
+
+        return;
+    }
+
+    println!("A majority is over {} of {} ballots", rounding(cli).format(total / 2.0), format_votes(total));
+    println!();
+    println!();
+
+    for round in rounds {
+        let ranks = match round.depth {
+            1 => String::from("first preferences"),
+            depth => format!("preferences down to rank {depth}"),
+        };
+        println!("Round {} ({ranks}):", round.depth);
+
+        for (label, votes) in &round.tally {
+            println!("{}: {}", names.get(label), rounding(cli).format(*votes));
+        }
+
+        println!();
+        println!();
+    }
+
+    if let (Some(winner), Some(last)) = (winner, rounds.last()) {
+        let (_, votes) = &last.tally[0];
+        let majority = match votes * 2.0 > total {
+            true => "a majority",
+            false => "the most, though nobody reached a majority",
+        };
+
+        println!("Winner: {} with {} ({majority})", names.get(winner), rounding(cli).format(*votes));
+    }
+}
+
+/// Fills --seats seats, drawing surpluses at random with --seed if --surplus
+/// says to.
+fn count_stv(cli: &Cli, votes: &Ballot<String>, seats: usize) -> Result<stv::Count<String>> {
+    let constraints = constraints(cli, votes.labels())?;
+    let mut rng = rng::Rng::new(cli.seed).with_logging(cli.draw_log.is_some());
+    let surplus = match cli.surplus {
+        SurplusRule::Fractional => stv::Surplus::Fractional,
+        SurplusRule::RandomDraw => stv::Surplus::RandomDraw(&mut rng),
+    };
+    let count = stv::count_constrained(votes, seats, rounding(cli), surplus, &constraints);
+
+    if cli.surplus == SurplusRule::RandomDraw {
+        write_draw_log(cli, &rng)?;
+    }
+
+    Ok(count)
+}
+
+/// The --at-most and --at-least constraints, in that order, with the
+/// candidates by index.
+fn constraints(cli: &Cli, labels: &[String]) -> Result<Vec<stv::Constraint>> {
+    let parse = |constraint: &String| -> Result<(usize, Vec<usize>)> {
+        let (amount, candidates) = constraint
+            .split_once(':')
+            .with_context(|| format!("{constraint:?} isn't N:CANDIDATES"))?;
+        let amount = amount.trim().parse().with_context(|| format!("invalid number of seats {amount:?}"))?;
+        let candidates = candidates
+            .split(',')
+            .map(|candidate| {
+                labels
+                    .iter()
+                    .position(|label| label == candidate.trim())
+                    .with_context(|| format!("no candidate called {:?} in {constraint:?}", candidate.trim()))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok((amount, candidates))
+    };
+
+    let at_most = cli.at_most.iter().map(|constraint| parse(constraint).map(|(most, group)| stv::Constraint::AtMost(most, group)));
+    let at_least = cli.at_least.iter().map(|constraint| parse(constraint).map(|(least, group)| stv::Constraint::AtLeast(least, group)));
+
+    at_most.chain(at_least).collect()
+}
+
+/// The constraint a candidate was skipped for, as it was given.
+fn describe_constraint(cli: &Cli, constraint: usize) -> String {
+    match cli.at_most.get(constraint) {
+        Some(at_most) => format!("--at-most {at_most}"),
+        None => format!("--at-least {}", cli.at_least[constraint - cli.at_most.len()]),
+    }
+}
+
+fn print_stv(cli: &Cli, count: &stv::Count<String>, seats: usize, names: &DisplayNames) {
+    if cli.raw {
+        for elected in &count.elected {
+            println!("{}", names.label(elected));
+        }
+
+        return;
+    }
+
+    println!("Quota: {} votes for {seats} seats", format_votes(count.quota));
+    println!();
+    println!();
+
+    for (i, round) in count.rounds.iter().enumerate() {
+        println!("Round {}:", i + 1);
+
+        for skipped in count.skipped.iter().filter(|skipped| skipped.round == i + 1) {
+            println!("Skipped: {} ({})", names.get(&skipped.candidate), describe_constraint(cli, skipped.constraint));
+        }
+
+        for (label, votes) in &round.tally {
+            println!("{}: {}", names.get(label), rounding(cli).format(*votes));
+        }
+
+        if round.exhausted > 0.0 {
+            println!("Exhausted: {}", rounding(cli).format(round.exhausted));
+        }
+
+        let elected: Vec<_> = round.elected.iter().map(|label| names.get(label)).collect();
+        let elected = elected.join(", ");
+
+        match (&round.eliminated, round.transfer_value) {
+            (Some(eliminated), _) if round.tied => {
+                println!("Eliminated: {} (tied for the fewest votes, and listed first)", names.get(eliminated))
+            }
+            (Some(eliminated), _) => println!("Eliminated: {}", names.get(eliminated)),
+            (None, Some(_)) if round.drawn => {
+                let surplus = round.tally.first().map_or(0.0, |(_, votes)| votes - count.quota);
+
+                println!("Elected: {elected}, with {} of their ballots drawn at random and passed on whole", rounding(cli).format(surplus))
+            }
+            (None, Some(transfer_value)) => {
+                println!("Elected: {elected}, with their ballots passed on at {transfer_value:.5} of a vote each")
+            }
+            (None, None) => println!("Elected: {elected}, as only enough candidates were left to fill the seats"),
+        }
+
+        println!();
+        println!();
+    }
+
+    println!("Seats:");
+
+    for (i, seat) in stv::seats(count).iter().enumerate() {
+        println!("{}. {}", i + 1, describe_seat(cli, seat, names));
+    }
+
+    println!();
+    println!();
+    println!("Quota attainment:");
+
+    for seat in stv::seats(count) {
+        let attainment = seat.votes / count.quota * 100.0;
+        let name = names.get(&seat.candidate);
+
+        match seat.reached_quota {
+            true => println!("{name}: {attainment:.1}% of the quota, with a surplus of {} votes", rounding(cli).format(seat.votes - count.quota)),
+            false => println!("{name}: {attainment:.1}% of the quota"),
+        }
+    }
+
+    println!();
+    println!();
+
+    // only with affiliations in the --display-names file
+    if names.has_affiliations() {
+        println!("By affiliation:");
+
+        for group in stv::groups(count, |candidate| names.affiliation(candidate)) {
+            let share = group.share * 100.0;
+            let seat_share = group.seats as f64 / seats as f64 * 100.0;
+            let affiliation = group.group.unwrap_or("No affiliation");
+
+            println!("{affiliation}: {share:.1}% of first preferences, {} of {seats} seats ({seat_share:.1}%)", group.seats);
+        }
+
+        println!();
+        println!();
+    }
+
+    let elected: Vec<_> = count.elected.iter().map(|label| names.get(label)).collect();
+
+    match elected.len() {
+        filled if filled < seats => println!("Elected: {} (only {filled} of {seats} seats could be filled)", elected.join(", ")),
+        _ => println!("Elected: {}", elected.join(", ")),
+    }
+}
+
+/// Tells how a seat was filled, and whose ballots helped fill it.
+fn describe_seat(cli: &Cli, seat: &stv::Seat<String>, names: &DisplayNames) -> String {
+    let name = names.get(&seat.candidate);
+    let (round, votes) = (seat.round, rounding(cli).format(seat.votes));
+    let how = match seat.reached_quota {
+        true => format!("{name} reached the quota in round {round} with {votes} votes"),
+        false => format!("{name} was elected in round {round} with {votes} votes, as one of the last candidates left"),
+    };
+
+    let gained: Vec<_> = seat
+        .gained
+        .iter()
+        .map(|gain| {
+            let (from, votes) = (names.get(&gain.from), rounding(cli).format(gain.votes));
+
+            match gain.surplus {
+                true => format!("{votes} from {from}'s surplus"),
+                false => format!("{votes} from {from}, who was eliminated"),
+            }
+        })
+        .collect();
+
+    match gained.as_slice() {
+        [] if round == 1 => format!("{how}, all first preferences"),
+        [] => how,
+        [gain] => format!("{how}, picking up {gain}"),
+        [gains @ .., last] => format!("{how}, picking up {} and {last}", gains.join(", ")),
+    }
+}
+
+fn print_finalists(cli: &Cli, finalists: &finalists::Finalists<String>, names: &DisplayNames) {
+    if cli.raw {
+        for (label, _) in &finalists.finalists {
+            println!("{}", names.label(label));
+        }
+
+        return;
+    }
+
+    for (i, (label, votes)) in finalists.eliminated.iter().enumerate() {
+        let round = i + 1;
+        let label = names.get(label);
+        let votes = format_votes(*votes);
+
+        println!("Round {round}: {label} eliminated with {votes} votes");
+    }
+
+    if !finalists.eliminated.is_empty() {
+        println!();
+    }
+
+    println!("Finalists:");
+
+    for (label, votes) in &finalists.finalists {
+        let label = names.get(label);
+        let votes = format_votes(*votes);
+
+        println!("{label}: {votes}");
+    }
+}
+
+fn print_weights(cli: &Cli, groups: &[weighting::Stratum], weights: &[f64], capped: &[(usize, f64)]) {
+    note!(cli, "Weights by stratum:");
+
+    for group in groups {
+        let value = &group.value;
+        let ballots = group.ballots;
+        let target = group.target * 100.0;
+        let weight = group.weight;
+
+        note!(cli, "{value}: {ballots} ballots, {target:.1}% of the population, weight {weight:.3}");
+    }
+
+    if let Some(summary) = weighting::summarize(weights) {
+        let total = format_votes(summary.total);
+        let ballots = weights.len();
+        let (smallest, median, largest) = (summary.smallest, summary.median, summary.largest);
+
+        note!(cli);
+        note!(cli, "Total weight {total} across {ballots} ballots (smallest {smallest:.3}, median {median:.3}, largest {largest:.3})");
+    }
+
+    for (record, weight) in capped {
+        let max = weights[*record];
+
+        note!(cli, "Record {record}: weight {weight:.3} capped at {max:.3}");
+    }
+
+    note!(cli);
+    note!(cli);
+}
+
+fn print_sensitivity(sensitivity: &Sensitivity<String>, margin: f64, names: &DisplayNames) {
+    let join = |order: &[String]| order.iter().map(|l| names.get(l)).collect::<Vec<_>>().join(", ");
+
+    if sensitivity.close.is_empty() {
+        println!("No rounds were within {margin} votes");
+        return;
+    }
+
+    println!("Rounds within {margin} votes:");
+
+    for close in &sensitivity.close {
+        let round = close.round + 1;
+        let picked = names.get(&close.picked);
+        let alternative = names.get(&close.alternative);
+        let behind = format_votes(close.margin);
+        let effect = if close.order == sensitivity.order {
+            "the same order of winners"
+        } else if close.order.first() == sensitivity.order.first() {
+            "a different order of winners"
+        } else {
+            "a different winner"
+        };
+
+        println!();
+        println!("Round {round}: {picked} picked over {alternative} by {behind} votes");
+        println!("Picking {alternative} instead gives {effect}: {}", join(&close.order));
+    }
+
+    println!();
+
+    if sensitivity.fragile() {
+        println!("The result is fragile: a close round could have changed the winner");
+    } else {
+        println!("The winner is the same however the close rounds went");
+    }
+}
+
+fn print_tie_stability(stability: &TieStability<String>, noise: f64, names: &DisplayNames) {
+    let percent = |count: usize| count as f64 * 100.0 / stability.trials as f64;
+
+    if stability.ties.is_empty() {
+        println!("No rounds were tied, so there were no tie-breaks to check");
+        return;
+    }
+
+    println!("Tie-breaks, with {} reruns each scaling every ballot by up to {}% either way:", stability.trials, noise * 100.0);
+
+    for tie in &stability.ties {
+        let tied = tie.tied.iter().map(|label| names.get(label)).collect::<Vec<_>>().join(", ");
+
+        println!();
+        println!("Round {}: {tied} tied and {} was picked", tie.round + 1, names.get(&tie.picked));
+        println!("Another of them came out ahead in {:.1}% of reruns", percent(tie.flipped));
+    }
+
+    println!();
+    println!("The winner changed in {:.1}% of reruns", percent(stability.winner_changed));
+}
+
+fn write_pairwise(mut out: impl std::io::Write, pairwise: &condorcet::Pairwise, labels: &[String], csv: bool, names: &DisplayNames) -> Result<()> {
+    let shown: Vec<_> = labels.iter().map(|label| names.get(label)).collect();
+
+    if csv {
+        let mut writer = csv::Writer::from_writer(out);
+        let header = std::iter::once("").chain(shown.iter().copied());
+
+        writer.write_record(header).context("couldn't write the matrix")?;
+
+        for (a, name) in shown.iter().enumerate() {
+            // nobody prefers a candidate over themselves, so that's left blank
+            let cells = (0..labels.len()).map(|b| match a == b {
+                true => String::new(),
+                false => format_votes(pairwise.prefer[a][b]),
+            });
+
+            writer
+                .write_record(std::iter::once(name.to_string()).chain(cells))
+                .context("couldn't write the matrix")?;
+        }
+
+        return writer.flush().context("couldn't write the matrix");
+    }
+
+    let width = shown.iter().map(|name| name.chars().count()).max().unwrap_or(0);
+    let header: Vec<_> = shown.iter().map(|name| format!("{name:>6}")).collect();
+    let mut table = String::from("Voters preferring the row's candidate over the column's:\n");
+
+    table += &format!("{:width$}  {}\n", "", header.join("  "));
+
+    for (a, name) in shown.iter().enumerate() {
+        let cells: Vec<_> = shown
+            .iter()
+            .enumerate()
+            .map(|(b, column)| {
+                let cell = column.chars().count().max(6);
+
+                match a == b {
+                    true => format!("{:>cell$}", "-"),
+                    false => format!("{:>cell$}", format_votes(pairwise.prefer[a][b])),
+                }
+            })
+            .collect();
+
+        table += &format!("{name:width$}  {}\n", cells.join("  "));
+    }
+
+    out.write_all(table.as_bytes()).context("couldn't write the matrix")
+}
+
+/// Warns when there's a Condorcet winner and it isn't who the count elected.
+fn warn_condorcet(pairwise: &condorcet::Pairwise, labels: &[String], winner: Option<&str>, names: &DisplayNames) {
+    if let Some(warning) = condorcet_warning(pairwise, labels, winner, names) {
+        println!("{warning}");
+        println!();
+    }
+}
+
+fn condorcet_warning(pairwise: &condorcet::Pairwise, labels: &[String], winner: Option<&str>, names: &DisplayNames) -> Option<String> {
+    let condorcet = pairwise.winner().map(|i| &labels[i])?;
+    let winner = winner.filter(|winner| winner != condorcet)?;
+    let (winner, condorcet) = (names.get(winner), names.get(condorcet));
+
+    Some(format!("Warning: {winner} won, but {condorcet} is the Condorcet winner (they beat every other candidate head-to-head)"))
+}
+
+fn print_condorcet(pairwise: &condorcet::Pairwise, labels: &[String], names: &DisplayNames) {
+    let name = |i: usize| names.get(&labels[i]);
+
+    if let Some(winner) = pairwise.winner() {
+        println!("Condorcet winner: {}", name(winner));
+        return;
+    }
+
+    let cycles = pairwise.cycles();
+
+    if cycles.is_empty() {
+        // without a cycle, the smith set can only be candidates tied head-to-head
+        let tied: Vec<_> = pairwise.smith_set().into_iter().map(name).collect();
+        let tied = tied.join(", ");
+
+        println!("No Condorcet winner, because of head-to-head ties between {tied}");
+        return;
+    }
+
+    println!("No Condorcet winner, because of the cycles:");
+
+    for cycle in cycles {
+        let mut order: Vec<_> = cycle.iter().copied().map(name).collect();
+        order.push(name(cycle[0]));
+
+        println!();
+        println!("{}", order.join(" > "));
+
+        for (i, a) in cycle.iter().copied().enumerate() {
+            let b = cycle[(i + 1) % cycle.len()];
+            let for_a = format_votes(pairwise.prefer[a][b]);
+            let for_b = format_votes(pairwise.prefer[b][a]);
+
+            println!("{} beats {} {for_a} to {for_b}", name(a), name(b));
+        }
+    }
+}
+
+/// Describes how much of a candidate's vote came from their own first preferences.
+fn describe_retention(retention: &retention::Retention) -> String {
+    let percent = |fraction: Option<f64>| fraction.map_or(0.0, |f| f * 100.0);
+    let transferred = percent(retention.transferred());
+
+    match retention.kept() {
+        Some(kept) => format!(" (keeps {:.0}% of first preferences, {transferred:.0}% transferred)", kept * 100.0),
+        None => format!(" (no first preferences, {transferred:.0}% transferred)"),
+    }
+}
+
+/// A round of a tiered count as it's shown: the winner, then everyone else
+/// with the most votes first.
+fn standings((winner, counts, mut other): rcvis::Round) -> Vec<(String, f64)> {
+    other.sort_by(|(_, count_a), (_, count_b)| count_b.total_cmp(count_a));
+
+    std::iter::once((winner, counts)).chain(other).collect()
+}
+
+/// How each candidate's votes and position changed since the round before,
+/// for every round's standings, for --highlight-changes. The first round has
+/// nothing to compare to.
+fn highlight(rounds: &[Vec<(String, f64)>]) -> Vec<Vec<String>> {
+    // the count and position of each candidate still standing after the
+    // previous round, so that a winner leaving doesn't move everyone up
+    let mut previous = HashMap::new();
+
+    rounds
+        .iter()
+        .map(|standings| {
+            let changes = standings
+                .iter()
+                .enumerate()
+                .map(|(position, (label, count))| match previous.get(label) {
+                    Some(before) => describe_change(*before, (*count, position)),
+                    None => String::new(),
+                })
+                .collect();
+
+            previous = standings.iter().skip(1).enumerate().map(|(position, (label, count))| (label, (*count, position))).collect();
+
+            changes
+        })
+        .collect()
+}
+
+/// Describes how a candidate's (count, position) changed since the last round.
+fn describe_change(before: (f64, usize), after: (f64, usize)) -> String {
+    let (count_before, position_before) = before;
+    let (count_after, position_after) = after;
+
+    let votes = match count_after.total_cmp(&count_before) {
+        Ordering::Greater => format!("+{}", format_votes(count_after - count_before)),
+        Ordering::Less => format!("-{}", format_votes(count_before - count_after)),
+        Ordering::Equal => String::from("+0"),
+    };
+    let position = match position_after.cmp(&position_before) {
+        Ordering::Less => format!(", up {}", position_before - position_after),
+        Ordering::Greater => format!(", down {}", position_after - position_before),
+        Ordering::Equal => String::new(),
+    };
+
+    format!(" ({votes}{position})")
+}
+
+fn print_audit_sample(cli: &Cli, votes: &Ballot<String>, names: &DisplayNames, size: usize) -> Result<()> {
+    let mut rng = rng::Rng::new(cli.seed);
+    let strata = audit::sample(votes, size, cli.stratify, &mut rng);
+    let seed = cli.seed;
+    write_draw_log(cli, &rng)?;
+
+    println!("Audit sample with seed {seed}");
+    println!();
+
+    for stratum in strata {
+        let drawn = stratum.records.len();
+        let total = stratum.total;
+
+        match stratum.first_preference {
+            Some(first) => {
+                let first = names.get(first);
+                println!("First preference {first} ({drawn} of {total} ballots)")
+            }
+            None => println!("All ballots ({drawn} of {total} ballots)"),
+        }
+
+        for record in stratum.records {
+            let ranking: Vec<_> = votes.ranking(record).map(|l| names.get(l)).collect();
+            let ranking = match ranking.is_empty() {
+                true => String::from("(nobody ranked)"),
+                false => ranking.join(" > "),
+            };
+
+            println!("Record {record}: {ranking}");
+        }
+
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Writes every value drawn from `rng` to the --draw-log, if there is one.
+fn write_draw_log(cli: &Cli, rng: &rng::Rng) -> Result<()> {
+    let Some(path) = &cli.draw_log else {
+        return Ok(());
+    };
+
+    let draws: Vec<_> = rng
+        .draws()
+        .iter()
+        .enumerate()
+        .map(|(i, draw)| {
+            serde_json::json!({
+                "draw": i + 1,
+                "context": draw.context,
+                "bound": draw.bound,
+                "value": draw.value,
+                "rejected": draw.rejected,
+            })
+        })
+        .collect();
+    let log = serde_json::json!({ "generator": "splitmix64", "seed": cli.seed, "draws": draws });
+    let json = serde_json::to_string_pretty(&log).expect("json values always serialize");
+
+    std::fs::write(path, json).with_context(|| format!("couldn't write the draw log to {}", path.display()))
+}
+
+/// Values from other columns of each ballot's record, which only csv input
+/// has.
+#[derive(Default)]
+struct Columns {
+    /// Each respondent's stratum, if weighting is used
+    strata: Vec<String>,
+    /// Each respondent's group, if --group-by is used
+    groups: Vec<String>,
+}
+
+/// Reads the ballots in whichever format was chosen.
+fn read_input(cli: &Cli, reader: impl Read) -> Result<(Ballot<String>, Columns)> {
+    match cli.input_format {
+        InputFormat::Csv if cli.layout == Layout::ByRank => Ok((read_by_rank(cli, reader)?, Columns::default())),
+        _ if cli.layout == Layout::ByRank => Err(anyhow!("--layout by-rank needs csv input")),
+        InputFormat::Csv if cli.columns.is_some() => read_with_rules(cli, reader),
+        InputFormat::Csv => read_data(cli, reader),
+        _ if cli.columns.is_some() => Err(anyhow!("a rules file needs csv input")),
+        _ if cli.validation.is_some() => Err(anyhow!("--validation needs csv input")),
+        _ if cli.group_by.is_some() => Err(anyhow!("grouping needs csv input")),
+        _ if cli.checksum_column.is_some() => Err(anyhow!("checksums need csv input")),
+        InputFormat::Handcount | InputFormat::Blt | InputFormat::Preflib | InputFormat::Json if cli.no_headers => {
+            Err(anyhow!("--no-headers needs input in columns, like csv"))
+        }
+        InputFormat::Handcount | InputFormat::Notation | InputFormat::Approvals | InputFormat::Scores | InputFormat::Blt | InputFormat::Preflib | InputFormat::Json if cli.strata_column.is_some() => {
+            Err(anyhow!("weighting by strata needs csv input"))
+        }
+        InputFormat::Handcount => Ok((handcount::read(reader)?, Columns::default())),
+        InputFormat::Preflib => Ok((preflib::read(reader)?, Columns::default())),
+        InputFormat::Json => Ok((json_ballots::read(reader)?, Columns::default())),
+        InputFormat::Blt => {
+            let mut reader = reader;
+            let mut text = String::new();
+            reader.read_to_string(&mut text).context("couldn't read the BLT file")?;
+
+            let election = blt::read(&text).map_err(|e| anyhow!("invalid BLT file: {e}"))?;
+
+            if !election.withdrawn.is_empty() {
+                eprintln!("Withdrawn before the count: {}", election.withdrawn.join(", "));
+            }
+
+            if election.seats > 1 && cli.seats.is_none() {
+                eprintln!("The BLT file is for {} seats, which --seats {} counts", election.seats, election.seats);
+            }
+
+            Ok((election.ballot, Columns::default()))
+        }
+        InputFormat::Approvals => {
+            let (headers, records, _) = read_table(cli, reader, ranks::Ragged::Reject)?;
+
+            Ok((ranks::parse_approvals(&headers, &records, cli.start, cli.len, &vocabulary(cli)?)?, Columns::default()))
+        }
+        InputFormat::Scores => {
+            check_score_range(cli)?;
+
+            let (headers, records, _) = read_table(cli, reader, ranks::Ragged::Reject)?;
+            let (labels, mut scores) = ranks::parse_scores(&headers, &records, cli.start, cli.len, cli.min_score, cli.max_score)?;
+            normalize_scores(cli, &labels, &mut scores);
+            let votes = Ballot::from_tied_rankings(labels, &score::rankings(&scores)).expect("rankings only contain known candidates");
+
+            Ok((votes, Columns::default()))
+        }
+        InputFormat::Notation => {
+            let (labels, rankings) = cvr::read_notation(reader, cli.start, dialect(cli))?;
+            let votes = Ballot::from_tied_rankings(labels, &rankings).expect("rankings only contain known candidates");
+
+            Ok((votes, Columns::default()))
+        }
+        format => Ok((read_cvr(cli, format, reader)?, Columns::default())),
+    }
+}
+
+/// Reads and combines every file listed in a manifest (or given as FILE) into
+/// one contest, keeping a subtotal for each file.
+fn read_batches(cli: &Cli, paths: Vec<PathBuf>) -> Result<(Ballot<String>, Columns, Vec<batch::Batch>)> {
+    if cli.input_format == InputFormat::Dominion {
+        return Err(anyhow!("batches are csv or ess files, not dominion exports"));
+    }
+
+    let mut combined: Option<Ballot<String>> = None;
+    let mut all_columns = Columns::default();
+    let mut batches = vec![];
+
+    for path in paths {
+        let file = std::fs::File::open(&path)
+            .with_context(|| format!("couldn't open batch {}", path.display()))?;
+        let (votes, columns) = read_input(cli, std::io::BufReader::new(file))
+            .with_context(|| format!("in batch {}", path.display()))?;
+
+        batches.push(batch::Batch::new(path.display().to_string(), &votes));
+        all_columns.strata.extend(columns.strata);
+        all_columns.groups.extend(columns.groups);
+
+        combined = Some(match combined {
+            // csv headers are the candidates, so a mismatch means a different contest
+            Some(combined) if matches!(cli.input_format, InputFormat::Csv | InputFormat::Approvals | InputFormat::Scores) && combined.labels() != votes.labels() => {
+                return Err(anyhow!("batch {} has different candidates to the batches before it", path.display()));
+            }
+            Some(mut combined) => {
+                combined.append(votes);
+                combined
+            }
+            None => votes,
+        });
+    }
+
+    let combined = combined.context("the manifest doesn't list any batches")?;
+
+    Ok((combined, all_columns, batches))
+}
+
+fn print_batches(cli: &Cli, batches: &[batch::Batch]) {
+    note!(cli, "Batches:");
+
+    for batch in batches {
+        let name = &batch.name;
+        let ballots = batch.ballots;
+        let firsts: Vec<_> = batch
+            .first_preferences
+            .iter()
+            .map(|(label, votes)| format!("{label} {}", format_votes(*votes)))
+            .collect();
+        let firsts = firsts.join(", ");
+
+        note!(cli, "{name}: {ballots} ballots, first preferences {firsts}");
+    }
+
+    note!(cli);
+    note!(cli);
+}
+
+/// The words approval input can use, with any --approved-words and
+/// --unapproved-words.
+fn vocabulary(cli: &Cli) -> Result<ranks::Vocabulary> {
+    let mut vocabulary = ranks::Vocabulary::default();
+
+    vocabulary
+        .extend(&cli.approved_words, &cli.unapproved_words)
+        .map_err(|word| anyhow!("{word:?} can't mean both approved and not approved"))?;
+
+    Ok(vocabulary)
+}
+
+/// Reads a --delimiter, which has to be a single ASCII character.
+fn parse_delimiter(value: &str) -> Result<u8, String> {
+    match value {
+        "tab" | "\\t" => Ok(b'\t'),
+        value if value.len() == 1 && value.is_ascii() => Ok(value.as_bytes()[0]),
+        _ => Err(String::from("the delimiter has to be a single ASCII character, or tab")),
+    }
+}
+
+/// How csv input is laid out.
+fn dialect(cli: &Cli) -> ranks::Dialect {
+    let delimiter = match (cli.tsv, cli.delimiter) {
+        (true, _) => b'\t',
+        (false, delimiter) => delimiter.unwrap_or(b','),
+    };
+
+    ranks::Dialect { delimiter, headers: !cli.no_headers }
+}
+
+/// Reads csv input as a table, naming the candidates' columns from --labels
+/// (or Candidate 1, Candidate 2...) if it has no headers.
+fn read_table(cli: &Cli, reader: impl Read, ragged: ranks::Ragged) -> Result<(csv::StringRecord, Vec<csv::StringRecord>, Vec<usize>)> {
+    let (mut headers, records, repaired) = ranks::read_ragged_table(reader, ragged, dialect(cli))?;
+
+    if cli.no_headers {
+        let count = ranks::labels(&headers, cli.start, cli.len).len();
+
+        if !cli.labels.is_empty() && cli.labels.len() != count {
+            return Err(anyhow!("there are {} --labels, but {count} candidates", cli.labels.len()));
+        }
+
+        headers = headers
+            .iter()
+            .enumerate()
+            .map(|(column, number)| match column.checked_sub(cli.start).filter(|candidate| *candidate < count) {
+                Some(candidate) => cli.labels.get(candidate).cloned().unwrap_or_else(|| format!("Candidate {}", candidate + 1)),
+                None => number.to_owned(),
+            })
+            .collect();
+    }
+
+    Ok((headers, records, repaired))
+}
+
+/// Reads the ballots from a voting system's CVR export.
+fn read_cvr(cli: &Cli, format: InputFormat, reader: impl Read) -> Result<Ballot<String>> {
+    if cli.strata_column.is_some() {
+        return Err(anyhow!("weighting by strata needs csv input"));
+    }
+
+    let (labels, rankings) = match (format, &cli.cvr) {
+        (InputFormat::Dominion, Some(dir)) => cvr::read_dominion(dir, cli.contest.as_deref())?,
+        _ => cvr::read_ess(reader, cli.start, cli.len, dialect(cli))?,
+    };
+
+    if labels.is_empty() {
+        return Err(anyhow!("no candidates were found in the export"));
+    }
+
+    Ok(Ballot::from_rankings(labels, &rankings).expect("rankings only contain known candidates"))
+}
+
+/// Reads the ballots from a survey export according to the --columns rules,
+/// along with each respondent's group if there's a group column.
+fn read_with_rules(cli: &Cli, reader: impl Read) -> Result<(Ballot<String>, Columns)> {
+    let path = cli.columns.as_deref().expect("only called with a rules file");
+    let (headers, records, _) = read_table(cli, reader, ranks::Ragged::Reject)?;
+    let rules = std::fs::File::open(path).with_context(|| format!("couldn't open the rules file {}", path.display()))?;
+    let kinds = ranks::read_rules(rules, &headers)?;
+
+    let of_kind = |kind| -> Vec<_> { (0..kinds.len()).filter(|i| kinds[*i] == kind).collect() };
+    let single = |kind, what: &str| match of_kind(kind).as_slice() {
+        [] => Ok(None),
+        [column] => Ok(Some(*column)),
+        _ => Err(anyhow!("only one column can hold the {what}")),
+    };
+
+    let (ballot_kind, ballot_columns) = match [ranks::Kind::Rank, ranks::Kind::Score, ranks::Kind::Approval]
+        .into_iter()
+        .map(|kind| (kind, of_kind(kind)))
+        .filter(|(_, columns)| !columns.is_empty())
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [] => return Err(anyhow!("the rules don't say which columns hold the ballot")),
+        [(kind, columns)] => (*kind, columns.clone()),
+        _ => return Err(anyhow!("the ballot columns have to be all ranks, all scores or all approvals")),
+    };
+
+    let ballot_headers = ranks::select(&headers, &ballot_columns);
+    let ballot_records: Vec<_> = records.iter().map(|record| ranks::select(record, &ballot_columns)).collect();
+    let count = ballot_columns.len();
+
+    let mut votes = match ballot_kind {
+        ranks::Kind::Score => {
+            check_score_range(cli)?;
+
+            let (labels, mut scores) = ranks::parse_scores(&ballot_headers, &ballot_records, 0, None, cli.min_score, cli.max_score)?;
+            normalize_scores(cli, &labels, &mut scores);
+
+            Ballot::from_tied_rankings(labels, &score::rankings(&scores)).expect("rankings only contain known candidates")
+        }
+        _ if cli.normalize.is_some() => return Err(anyhow!("--normalize only works with score --columns")),
+        ranks::Kind::Approval => ranks::parse_approvals(&ballot_headers, &ballot_records, 0, None, &vocabulary(cli)?)?,
+        _ => {
+            let summary = inspect::summarize(&ballot_headers, &ballot_records);
+            inspect::check_ranks(&summary, 0, count, cli.indexed_at)?;
+
+            let labels = ranks::labels(&ballot_headers, 0, None);
+            let all_ranks = ranks::parse(&ballot_records, 0, None, cli.indexed_at, count)?;
+
+            Ballot::new(labels, all_ranks).expect("labels and votes mismatch")
+        }
+    };
+
+    let cell = |record: &csv::StringRecord, column| record.get(column).unwrap_or_default().trim().to_owned();
+
+    if let Some(column) = single(ranks::Kind::Weight, "weights")? {
+        let weights = records
+            .iter()
+            .enumerate()
+            .map(|(i, record)| {
+                let weight = cell(record, column);
+
+                weight
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|weight| weight.is_finite() && *weight >= 0.0)
+                    .with_context(|| format!("invalid weight {weight:?}, record {i}"))
+            })
+            .collect::<Result<_>>()?;
+
+        votes = votes.with_weights(weights).expect("a weight for every ballot");
+    }
+
+    // survey tools can submit the same response twice
+    if let Some(column) = single(ranks::Kind::Id, "ids")? {
+        let mut seen = HashMap::new();
+
+        for (i, record) in records.iter().enumerate() {
+            if let Some(first) = seen.insert(cell(record, column), i) {
+                return Err(anyhow!("records {first} and {i} have the same id {:?}", cell(record, column)));
+            }
+        }
+    }
+
+    let groups = match single(ranks::Kind::Group, "groups")? {
+        Some(_) if cli.raw => return Err(anyhow!("a group column can't be used with --raw")),
+        Some(column) => records.iter().map(|record| cell(record, column)).collect(),
+        None => vec![],
+    };
+
+    Ok((votes, Columns { strata: vec![], groups }))
+}
+
+fn ragged(cli: &Cli) -> ranks::Ragged {
+    match cli.ragged {
+        RaggedRows::Reject => ranks::Ragged::Reject,
+        RaggedRows::Pad => ranks::Ragged::Pad,
+        RaggedRows::Truncate => ranks::Ragged::Truncate,
+    }
+}
+
+/// Reads csv input with a column per rank, holding candidates' names.
+fn read_by_rank(cli: &Cli, reader: impl Read) -> Result<Ballot<String>> {
+    let per_candidate = [
+        (cli.columns.is_some(), "a rules file"),
+        (cli.validation.is_some(), "--validation"),
+        (cli.group_by.is_some(), "grouping"),
+        (cli.checksum_column.is_some(), "checksums"),
+        (cli.strata_column.is_some(), "weighting by strata"),
+        (!cli.labels.is_empty(), "--labels"),
+    ];
+
+    if let Some((_, what)) = per_candidate.iter().find(|(given, _)| *given) {
+        return Err(anyhow!("{what} needs a column per candidate, not --layout by-rank"));
+    }
+
+    let (_, records, _) = ranks::read_ragged_table(reader, ragged(cli), dialect(cli))?;
+
+    ranks::parse_by_rank(&records, cli.start, cli.len)
+}
+
+/// Reads the ballots, along with each respondent's stratum if weighting is
+/// used and group if grouping is.
+fn read_data(cli: &Cli, reader: impl Read) -> Result<(Ballot<String>, Columns)> {
+    let ragged = ragged(cli);
+    let (headers, records, repaired) = read_table(cli, reader, ragged)?;
+
+    match (repaired.len(), ragged) {
+        (0, _) => {}
+        (1, ranks::Ragged::Pad) => eprintln!("Padded record {} with unranked candidates", repaired[0]),
+        (count, ranks::Ragged::Pad) => eprintln!("Padded {count} short records with unranked candidates"),
+        (1, _) => eprintln!("Dropped the extra cells of record {}", repaired[0]),
+        (count, _) => eprintln!("Dropped the extra cells of {count} long records"),
+    }
+    let labels = ranks::labels(&headers, cli.start, cli.len);
+
+    let columns = inspect::summarize(&headers, &records);
+    let checked = inspect::check_ranks(&columns, cli.start, labels.len(), cli.indexed_at);
+    let warnings = inspect::suspicious_columns(&headers, &records, cli.start, labels.len());
+
+    // written before anything fails, since that's when it's most useful
+    if let Some(path) = &cli.validation {
+        let problem = checked.as_ref().err().map(|e| e.to_string());
+        let summary = validation::summary(&columns, &records, cli.start, labels.len(), cli.indexed_at, problem, &warnings);
+        let json = serde_json::to_string_pretty(&summary).expect("json values always serialize");
+
+        std::fs::write(path, json)
+            .with_context(|| format!("couldn't write the validation summary to {}", path.display()))?;
+    }
+
+    checked?;
+
+    for warning in warnings {
+        if cli.strict {
+            return Err(anyhow!("{warning}"));
+        }
+
+        eprintln!("warning: {warning}");
+    }
+
+    if let Some(column) = cli.checksum_column {
+        if (cli.start..cli.start + labels.len()).contains(&column) {
+            return Err(anyhow!("the checksum column can't be one of the rank columns (LEN can leave it out)"));
+        }
+
+        let mismatched = ranks::mismatched_checksums(&records, cli.start, labels.len(), column, &cli.checksum_salt);
+        let shown: Vec<_> = mismatched.iter().take(10).map(|i| i.to_string()).collect();
+
+        match mismatched.len() {
+            0 => {}
+            1 => return Err(anyhow!("the checksum doesn't match the ranks on record {}", shown[0])),
+            2..=10 => return Err(anyhow!("the checksum doesn't match the ranks on records {}", shown.join(", "))),
+            more => {
+                return Err(anyhow!(
+                    "the checksum doesn't match the ranks on records {} and {} more",
+                    shown.join(", "),
+                    more - shown.len()
+                ))
+            }
+        }
+    }
+
+    let column_values = |column: Option<usize>, what: &str| match column {
+        Some(column) => records
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                row.get(column)
+                    .map(String::from)
+                    .with_context(|| format!("no {what} column, record {i}"))
+            })
+            .collect::<Result<_>>(),
+        None => Ok(vec![]),
+    };
+    let strata = column_values(cli.strata_column, "strata")?;
+    let groups = column_values(cli.group_by, "group")?;
+    let all_ranks = match ragged {
+        ranks::Ragged::Pad => ranks::parse_padded(&records, cli.start, cli.len, cli.indexed_at, labels.len())?,
+        _ => ranks::parse(&records, cli.start, cli.len, cli.indexed_at, labels.len())?,
+    };
+
+    let ballot = Ballot::new(labels, all_ranks).expect("labels and votes mismatch");
+
+    Ok((ballot, Columns { strata, groups }))
+}
+
+#[cfg(test)]
+mod test {
+    use clap::Parser;
+    use rankit::Ballot;
+
+    fn labels(labels: &[&str]) -> Vec<String> {
+        labels.iter().map(|label| String::from(*label)).collect()
+    }
+
+    #[test]
+    fn highlights_changes() {
+        let mut rankings = vec![vec![0, 2, 1]; 4];
+        rankings.extend(vec![vec![1, 0, 2]; 3]);
+        rankings.extend(vec![vec![2, 1, 0]; 2]);
+        let ballot = Ballot::from_rankings(labels(&["a", "b", "c"]), &rankings).expect("valid rankings");
+
+        // once a wins, their ballots take c past b
+        let rounds: Vec<_> = ballot.runoff().map(super::standings).collect();
+        let highlighted = super::highlight(&rounds);
+        assert_eq!(highlighted[0], ["", "", ""]);
+        assert_eq!(highlighted[1], [" (+4, up 1)", " (+0, down 1)"]);
+        assert_eq!(highlighted[2], [" (+6)"]);
+    }
+
+    #[test]
+    fn counts_only_some() {
+        let cli = super::Cli::parse_from(["rankit", "--only", "a, c"]);
+        let rankings = [vec![1, 3, 0], vec![3, 1, 2], vec![0], vec![1]];
+        let ballot = Ballot::from_rankings(labels(&["a", "b", "c", "d"]), &rankings).expect("valid rankings");
+
+        // ballots for b and d go on to a or c, unless they ranked neither
+        let only = super::only(&cli, ballot.clone(), &Default::default()).expect("known candidates");
+        assert_eq!(only.labels(), ["a", "c"]);
+        assert_eq!(only.tier(), vec![2.0, 1.0]);
+        assert_eq!(only.ranking(3).count(), 0);
+
+        let cli = super::Cli::parse_from(["rankit", "--only", "a,e"]);
+        assert!(super::only(&cli, ballot, &Default::default()).is_err());
+    }
+
+    #[test]
+    fn limits_kemeny() {
+        let rankings = [vec![0, 1, 2], vec![2, 1, 0]];
+        let ballot = Ballot::from_rankings(labels(&["a", "b", "c"]), &rankings).expect("valid rankings");
+
+        let cli = super::Cli::parse_from(["rankit", "--method", "kemeny", "--kemeny-search", "exact", "--max-candidates", "2"]);
+        let error = super::tally(&cli, ballot.clone(), &Default::default()).expect_err("3 candidates is over the limit");
+        assert!(error.to_string().contains("--max-candidates"));
+
+        // over the limit, the heuristic is used instead
+        let cli = super::Cli::parse_from(["rankit", "--method", "kemeny", "--max-candidates", "2"]);
+        assert!(super::tally(&cli, ballot.clone(), &Default::default()).is_ok());
+
+        let cli = super::Cli::parse_from(["rankit", "--method", "kemeny", "--max-candidates", "3"]);
+        assert!(super::tally(&cli, ballot, &Default::default()).is_ok());
+    }
+
+    #[test]
+    fn plurality_ties() {
+        let rankings = [vec![0, 1], vec![1, 0], vec![2], vec![1]];
+        let ballot = Ballot::from_rankings(labels(&["a", "b", "c"]), &rankings).expect("valid rankings");
+        let ballot = ballot.with_weights(vec![1.0, 1.0, 1.0, 0.25]).expect("a weight for every ballot");
+
+        // later preferences don't count, but every first preference does
+        let cli = super::Cli::parse_from(["rankit", "--method", "plurality"]);
+        let (firsts, winners) = super::plurality(&cli, &ballot);
+        let firsts: Vec<_> = firsts.into_iter().map(|(label, votes)| (label.as_str(), votes)).collect();
+        assert_eq!(firsts, [("b", 1.25), ("a", 1.0), ("c", 1.0)]);
+        assert_eq!(winners, ["b"]);
+
+        // a quarter of a vote rounds away, leaving a three-way tie
+        let cli = super::Cli::parse_from(["rankit", "--method", "plurality", "--rounding", "truncate", "--decimals", "0"]);
+        assert_eq!(super::plurality(&cli, &ballot).1, ["b", "a", "c"]);
+    }
+
+    #[test]
+    fn irv_squeezes_condorcet_winner() {
+        let mut rankings = vec![vec![0, 1, 2]; 35];
+        rankings.extend(vec![vec![2, 1, 0]; 33]);
+        rankings.extend(vec![vec![1, 0, 2]; 32]);
+        let ballot = Ballot::from_rankings(labels(&["a", "b", "c"]), &rankings).expect("valid rankings");
+        let pairwise = rankit::condorcet::Pairwise::new(&ballot);
+
+        // b is squeezed out first, though they beat a and c head-to-head
+        let (_, winner) = rankit::irv::count(&ballot, Default::default());
+        assert_eq!(winner.as_deref(), Some("a"));
+        assert_eq!(
+            super::condorcet_warning(&pairwise, ballot.labels(), winner.as_deref(), &Default::default()).as_deref(),
+            Some("Warning: a won, but b is the Condorcet winner (they beat every other candidate head-to-head)")
+        );
+        assert_eq!(super::condorcet_warning(&pairwise, ballot.labels(), Some("b"), &Default::default()), None);
+    }
+
+    #[test]
+    fn pairwise_matrix() {
+        let rankings = [vec![0, 1], vec![0, 1], vec![1, 0], vec![0]];
+        let ballot = Ballot::from_rankings(labels(&["a", "bee"]), &rankings).expect("valid rankings");
+        let pairwise = rankit::condorcet::Pairwise::new(&ballot);
+
+        let mut csv = vec![];
+        super::write_pairwise(&mut csv, &pairwise, ballot.labels(), true, &Default::default()).expect("writes to memory");
+        assert_eq!(String::from_utf8(csv).expect("utf-8"), ",a,bee\na,,3\nbee,1,\n");
+
+        // the row labels are padded to the longest name
+        let mut table = vec![];
+        super::write_pairwise(&mut table, &pairwise, ballot.labels(), false, &Default::default()).expect("writes to memory");
+        let table = String::from_utf8(table).expect("utf-8");
+        assert_eq!(table.lines().nth(2), Some("a         -       3"));
+        assert_eq!(table.lines().nth(3), Some("bee       1       -"));
+    }
+
+    #[test]
+    fn sorts_len_and_file() {
+        // a number is LEN, whichever order they come in
+        let mut cli = super::Cli::parse_from(["rankit", "ballots.csv", "3"]);
+        super::sort_positionals(&mut cli).expect("one LEN and one FILE");
+        assert_eq!(cli.len, Some(3));
+        assert_eq!(super::input_file(&cli), Some(std::path::Path::new("ballots.csv")));
+
+        let mut cli = super::Cli::parse_from(["rankit", "-"]);
+        super::sort_positionals(&mut cli).expect("stdin as FILE");
+        assert_eq!(super::input_file(&cli), None);
+
+        let mut cli = super::Cli::parse_from(["rankit", "3", "4"]);
+        assert!(super::sort_positionals(&mut cli).is_err());
+
+        let mut cli = super::Cli::parse_from(["rankit", "--manifest", "batches.txt", "ballots.csv"]);
+        assert!(super::sort_positionals(&mut cli).is_err());
+    }
+
+    #[test]
+    fn logs_every_audit_draw() {
+        let dir = tempfile::tempdir().expect("temp dir is writable");
+        let path = dir.path().join("draw-log.json");
+        let options = ["rankit", "--audit-sample", "2", "--stratify", "--seed", "7", "--draw-log"];
+        let cli = super::Cli::parse_from(options.into_iter().chain([path.to_str().expect("utf-8 temp dir")]));
+        let rankings = [vec![0, 1], vec![1, 0], vec![0, 1], vec![0], vec![1]];
+        let ballot = Ballot::from_rankings(labels(&["a", "b"]), &rankings).expect("valid rankings");
+
+        super::print_audit_sample(&cli, &ballot, &Default::default(), 2).expect("temp dir is writable");
+        let log: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).expect("log was written")).expect("log is json");
+
+        // 2 of a's 3 ballots and both of b's, a draw for each
+        let drawn: usize = rankit::audit::sample(&ballot, 2, true, &mut rankit::rng::Rng::new(7)).iter().map(|stratum| stratum.records.len()).sum();
+        let draws = log["draws"].as_array().expect("a list of draws");
+        let contexts: Vec<_> = draws.iter().map(|draw| draw["context"].as_str().unwrap_or_default()).collect();
+        assert_eq!(draws.len(), drawn);
+        assert_eq!(contexts, ["first preference a", "first preference a", "first preference b", "first preference b"]);
+        assert_eq!(log["seed"], 7);
+    }
+
+    #[test]
+    fn describes_seats() {
+        let mut rankings = vec![vec![0, 1]; 8];
+        rankings.extend(vec![vec![1]; 2]);
+        rankings.extend(vec![vec![2]; 2]);
+        let ballot = Ballot::from_rankings(labels(&["a", "b", "c"]), &rankings).expect("valid rankings");
+        let seats = rankit::stv::seats(&rankit::stv::count(&ballot, 2, Default::default()));
+        let cli = super::Cli::parse_from(["rankit", "--seats", "2"]);
+
+        let described: Vec<_> = seats.iter().map(|seat| super::describe_seat(&cli, seat, &Default::default())).collect();
+        assert_eq!(described, [
+            "a reached the quota in round 1 with 8 votes, all first preferences",
+            "b reached the quota in round 2 with 5 votes, picking up 3 from a's surplus",
+        ]);
+    }
+
+    #[test]
+    fn results_files_in_any_format() {
+        let dir = tempfile::tempdir().expect("temp dir is writable");
+        let path = dir.path().join("format-rcvis.json");
+        let cli = super::Cli::parse_from(["rankit", "--format", "json", "--rcvis", path.to_str().expect("utf-8 temp dir")]);
+        let rankings = [vec![0, 1], vec![1, 0], vec![0]];
+        let ballot = Ballot::from_rankings(labels(&["a", "b"]), &rankings).expect("valid rankings");
+
+        super::tally(&cli, ballot.clone(), &Default::default()).expect("temp dir is writable");
+        let summary: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).expect("rcvis file was written")).expect("rcvis file is json");
+        assert_eq!(summary["results"][0]["tallyResults"][0]["elected"], "a");
+
+        // the text report's extras would otherwise be dropped without a word
+        let cli = super::Cli::parse_from(["rankit", "--format", "csv", "--retention"]);
+        assert!(super::tally(&cli, ballot, &Default::default()).is_err());
+    }
+
+    #[test]
+    fn sankey_in_any_format() {
+        let dir = tempfile::tempdir().expect("temp dir is writable");
+        let path = dir.path().join("format-sankey.json");
+        let graph = dir.path().join("format-graph.dot");
+        let options = ["rankit", "--method", "irv", "--format", "csv", "--sankey"];
+        let files = [path.to_str().expect("utf-8 temp dir"), "--dot", graph.to_str().expect("utf-8 temp dir")];
+        let cli = super::Cli::parse_from(options.into_iter().chain(files));
+        let rankings = [vec![0], vec![0], vec![1, 0], vec![2]];
+        let ballot = Ballot::from_rankings(labels(&["a", "b", "c"]), &rankings).expect("valid rankings");
+
+        super::tally(&cli, ballot, &Default::default()).expect("temp dir is writable");
+        let plot: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).expect("sankey file was written")).expect("sankey file is json");
+        assert_eq!(plot["node"]["label"][0], "a (round 1)");
+        assert!(std::fs::read_to_string(&graph).expect("graph was written").starts_with("digraph"));
+    }
+
+    #[test]
+    fn checks_withdrawals_for_every_method() {
+        let ballot = || Ballot::from_rankings(labels(&["a", "b"]), &[vec![0, 1], vec![1]]).expect("valid rankings");
+        let unknown = super::Cli::parse_from(["rankit", "--method", "irv", "--withdraw", "1:z"]);
+        let borda = super::Cli::parse_from(["rankit", "--method", "borda", "--withdraw", "1:a"]);
+        let tiered = super::Cli::parse_from(["rankit", "--withdraw", "1:a"]);
+
+        assert!(super::tally(&unknown, ballot(), &Default::default()).is_err());
+        assert!(super::tally(&borda, ballot(), &Default::default()).is_err());
+        assert!(super::tally(&tiered, ballot(), &Default::default()).is_err());
+    }
+
+    #[test]
+    fn numbered_elimination_order() {
+        let dir = tempfile::tempdir().expect("temp dir is writable");
+        let path = dir.path().join("numbered-order.csv");
+        let cli = super::Cli::parse_from(["rankit", "--method", "irv", "--candidate-ids", "--elimination-order", path.to_str().expect("utf-8 temp dir")]);
+        let ballot = Ballot::from_rankings(labels(&["a", "b"]), &[vec![0], vec![0], vec![1]]).expect("valid rankings");
+        let names = super::DisplayNames::default().with_ids(ballot.labels());
+
+        super::write_results_files(&cli, &ballot, &names).expect("temp dir is writable");
+        let order = std::fs::read_to_string(&path).expect("elimination order was written");
+        assert_eq!(order, "round,id,candidate,outcome\n1,1,a,elected\n1,2,b,defeated\n");
+    }
+
+    #[test]
+    fn signs_the_whole_bundle() {
+        let dir = tempfile::tempdir().expect("temp dir is writable");
+        let path = dir.path().join("signed-bundle.zip");
+        let cli = super::Cli::parse_from(["rankit", "--method", "irv", "--sign-cmd", "wc -c", "--bundle", path.to_str().expect("utf-8 temp dir")]);
+        let ballot = Ballot::from_rankings(labels(&["a", "b"]), &[vec![0], vec![0], vec![1]]).expect("valid rankings");
+        let result = super::structured(&cli, &ballot, "--bundle").expect("irv counts in rounds");
 
-        let ballot = super::Ballot::new(labels, values).expect("label/values mismatch");
-        let winners_exp: Vec<_> = ballot.runoff().map(|(winner, _, _)| winner).collect();
+        super::write_bundle(&cli, &path, &result, &Default::default()).expect("temp dir is writable");
+        let bundle = std::fs::read(&path).expect("bundle was written");
+        let has = |name: &[u8]| bundle.windows(name.len()).any(|window| window == name);
 
-        assert_eq!(winners_known, winners_exp);
+        assert!(has(b"results.json.sig") && has(b"report.md.sig"));
+        assert!(std::fs::metadata(path.with_extension("zip.sig")).is_ok());
     }
 }
@@ -0,0 +1,86 @@
+use serde_json::Value;
+use std::fmt::Write;
+
+use crate::display::DisplayNames;
+use crate::json;
+use rankit::format_votes;
+
+/// Escapes the characters that would break out of a table cell or turn into
+/// formatting.
+fn escape(text: &str) -> String {
+    text.chars().fold(String::new(), |mut escaped, c| {
+        if matches!(c, '|' | '*' | '_' | '`' | '\\' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+
+        escaped.push(c);
+        escaped
+    })
+}
+
+/// Renders a count made by the `json` module as Markdown: a summary of who
+/// won, then a table for every round.
+pub fn report(result: &Value, names: &DisplayNames) -> String {
+    let rows = json::rows(result);
+    let method = result["method"].as_str().unwrap_or_default();
+    let shown = |label: &Value| label.as_str().map(|label| escape(names.get(label)));
+    let mut report = String::new();
+
+    let list = |labels: &Value| -> Vec<_> { labels.as_array().into_iter().flatten().filter_map(shown).collect() };
+    // whoever won, however the method says it
+    let outcome = match (&result["winner"], &result["elected"], &result["winners"]) {
+        (winner @ Value::String(_), _, _) => format!("Winner: **{}**", shown(winner).unwrap_or_default()),
+        (_, elected @ Value::Array(_), _) => format!("Elected: {}", list(elected).join(", ")),
+        (_, _, picked @ Value::Array(_)) => format!("Ranking: {}", list(picked).join(", ")),
+        _ => "Winner: nobody".to_owned(),
+    };
+    let rounds = rows.last().map_or(0, |row| row.round);
+
+    // writing to a String can't fail
+    let _ = writeln!(report, "## Summary\n");
+    let _ = writeln!(report, "- Method: {}", escape(method));
+    let _ = writeln!(report, "- Rounds: {rounds}");
+    let _ = writeln!(report, "- {outcome}");
+
+    for (i, row) in rows.iter().enumerate() {
+        if i == 0 || rows[i - 1].round != row.round {
+            let _ = writeln!(report, "\n## Round {}\n\n| Candidate | Votes | |\n| --- | ---: | --- |", row.round);
+        }
+
+        let name = match row.candidate.as_str() {
+            "" => "*Exhausted*".to_owned(),
+            candidate => escape(names.get(candidate)),
+        };
+        let status = match row.status {
+            "continuing" | "exhausted" => "",
+            status => status,
+        };
+
+        let _ = writeln!(report, "| {name} | {} | {status} |", format_votes(row.votes));
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use crate::display::DisplayNames;
+    use serde_json::json;
+
+    #[test]
+    fn summary_and_tables() {
+        let result = json!({
+            "method": "irv",
+            "rounds": [
+                { "round": 1, "tally": [{ "candidate": "a|b", "votes": 2.0 }, { "candidate": "c", "votes": 1.0 }], "exhausted": 0.0, "eliminated": "c", "withdrawn": [] },
+                { "round": 2, "tally": [{ "candidate": "a|b", "votes": 3.0 }], "exhausted": 0.0, "eliminated": null, "withdrawn": [] },
+            ],
+            "winner": "a|b",
+        });
+        let report = super::report(&result, &DisplayNames::default());
+
+        assert!(report.contains("- Rounds: 2\n- Winner: **a\\|b**\n"));
+        assert!(report.contains("| c | 1 | eliminated |"));
+        assert!(report.contains("| a\\|b | 3 | elected |"));
+    }
+}
@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use csv::StringRecord;
+
+/// Shrinks a table that makes something fail, by dropping as many records and
+/// then columns as it can while `fails` still says it does. Records are
+/// dropped in halves, then quarters and so on, so a single bad record in a
+/// big file is found quickly. The result is as small as dropping any one more
+/// record or column gets it, not necessarily the smallest there is.
+pub fn minimize(
+    mut headers: StringRecord,
+    mut records: Vec<StringRecord>,
+    mut fails: impl FnMut(&StringRecord, &[StringRecord]) -> bool,
+) -> (StringRecord, Vec<StringRecord>) {
+    let mut chunk = records.len().div_ceil(2);
+
+    while chunk > 0 {
+        let mut i = 0;
+
+        while i < records.len() {
+            let mut fewer = records.clone();
+            fewer.drain(i..(i + chunk).min(records.len()));
+
+            // staying put after a drop, since the next chunk has moved here
+            if fails(&headers, &fewer) {
+                records = fewer;
+            } else {
+                i += chunk;
+            }
+        }
+
+        chunk /= 2;
+    }
+
+    // from the last column, so the ones before keep their positions
+    for column in (0..headers.len()).rev() {
+        let without = |record: &StringRecord| -> StringRecord {
+            record.iter().enumerate().filter(|(i, _)| *i != column).map(|(_, cell)| cell).collect()
+        };
+        let fewer_headers = without(&headers);
+        let fewer: Vec<_> = records.iter().map(without).collect();
+
+        if fails(&fewer_headers, &fewer) {
+            headers = fewer_headers;
+            records = fewer;
+        }
+    }
+
+    (headers, records)
+}
+
+/// Writes a table back out as CSV.
+pub fn to_csv(headers: &StringRecord, records: &[StringRecord]) -> Result<String> {
+    let mut writer = csv::WriterBuilder::new().flexible(true).from_writer(vec![]);
+
+    for record in std::iter::once(headers).chain(records) {
+        writer.write_record(record).context("couldn't write the table")?;
+    }
+
+    let bytes = writer.into_inner().context("couldn't write the table")?;
+
+    String::from_utf8(bytes).context("the table isn't valid UTF-8")
+}
+
+#[cfg(test)]
+mod test {
+    use csv::StringRecord;
+
+    #[test]
+    fn finds_the_bad_record() {
+        let headers = StringRecord::from(vec!["a", "b", "c"]);
+        let mut records: Vec<_> = (0..40).map(|i| StringRecord::from(vec![i.to_string(), String::from("1"), String::from("2")])).collect();
+        records[27] = StringRecord::from(vec!["27", "x", "2"]);
+
+        // fails while the x is there under a b column
+        let fails = |headers: &StringRecord, records: &[StringRecord]| {
+            let column = headers.iter().position(|name| name == "b");
+
+            column.is_some_and(|column| records.iter().any(|record| record.get(column) == Some("x")))
+        };
+        let (headers, records) = super::minimize(headers, records, fails);
+
+        assert_eq!(headers, StringRecord::from(vec!["b"]));
+        assert_eq!(records, vec![StringRecord::from(vec!["x"])]);
+    }
+}
@@ -0,0 +1,81 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+use crate::UNRANKED;
+
+/// Splits a ranking written like `A>B=C>D` into groups of candidate names,
+/// from most to least preferred, where `=` joins candidates ranked equally.
+/// Spaces around names are ignored, as are empty names, so that `A>>B` or a
+/// blank ranking are fine. Fails with the name of a candidate listed twice.
+pub fn parse(ranking: &str) -> Result<Vec<Vec<&str>>, &str> {
+    let mut groups: Vec<Vec<&str>> = Vec::new();
+
+    for group in ranking.split('>') {
+        let names: Vec<_> = group.split('=').map(str::trim).filter(|name| !name.is_empty()).collect();
+
+        for name in names.iter().copied() {
+            if groups.iter().flatten().chain(&names).filter(|n| **n == name).count() > 1 {
+                return Err(name);
+            }
+        }
+
+        if !names.is_empty() {
+            groups.push(names);
+        }
+    }
+
+    Ok(groups)
+}
+
+/// Parses a ranking like `parse`, giving candidates by their index in
+/// `labels` and adding any candidates that aren't there yet.
+pub fn parse_into<'a>(ranking: &'a str, labels: &mut Vec<String>) -> Result<Vec<Vec<usize>>, &'a str> {
+    let groups = parse(ranking)?;
+    let mut index = |name: &str| match labels.iter().position(|label| label == name) {
+        Some(candidate) => candidate,
+        None => {
+            labels.push(name.to_string());
+            labels.len() - 1
+        }
+    };
+
+    Ok(groups
+        .into_iter()
+        .map(|group| group.into_iter().map(&mut index).collect())
+        .collect())
+}
+
+/// Writes one ballot's ranks (in the same order as the labels) in `A>B=C>D`
+/// notation, leaving out unranked candidates.
+pub fn format<T: Display>(row: &[usize], labels: &[T]) -> String {
+    let mut ranked: Vec<_> = (0..row.len()).filter(|c| row[*c] != UNRANKED).collect();
+    ranked.sort_by_key(|c| row[*c]);
+
+    let mut text = String::new();
+
+    for (i, candidate) in ranked.iter().copied().enumerate() {
+        if i > 0 {
+            text.push(if row[candidate] == row[ranked[i - 1]] { '=' } else { '>' });
+        }
+
+        text.push_str(&labels[candidate].to_string());
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use crate::UNRANKED;
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(super::parse(" A > B=C >D "), Ok(vec![vec!["A"], vec!["B", "C"], vec!["D"]]));
+        assert_eq!(super::parse("A>B=A"), Err("A"));
+        assert_eq!(super::parse(""), Ok(vec![]));
+
+        let labels = ["A", "B", "C", "D"];
+        assert_eq!(super::format(&[0, 1, 1, UNRANKED], &labels), "A>B=C");
+    }
+}
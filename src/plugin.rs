@@ -0,0 +1,184 @@
+use anyhow::{anyhow, Context, Result};
+use std::path::Path;
+use wasmtime::{Engine, Instance, Memory, Module, Store};
+
+use rankit::{Ballot, UNRANKED};
+
+/// One round of a count: the candidate picked, their votes, and everyone
+/// else's votes.
+type Round<T> = (T, f64, Vec<(T, f64)>);
+
+/// A tally method loaded from a WASM module, so that new methods can be tried
+/// without forking rankit.
+///
+/// The module has to export its `memory` and two functions:
+///
+/// - `alloc(len: i32) -> i32`, which returns a pointer to `len` free bytes
+///   for the ballots to be written to.
+/// - `tally(ptr: i32, len: i32) -> i32`, which counts the ballots written at
+///   `ptr` and returns a pointer to the round results.
+///
+/// All values are little endian. The ballots are laid out as the amount of
+/// candidates (u32), the amount of ballots (u32), every ballot's rank for each
+/// candidate (u32 each, ranked from 0, with u32::MAX for unranked), then every
+/// ballot's weight (f64 each).
+///
+/// The round results are laid out as the amount of rounds (u32), then for
+/// each round the candidate picked in that round (u32) followed by every
+/// candidate's votes that round (f64 each, NaN for candidates no longer in
+/// the count).
+pub struct Plugin {
+    store: Store<()>,
+    instance: Instance,
+    memory: Memory,
+}
+
+impl Plugin {
+    /// Loads a plugin from a .wasm (or .wat) file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let engine = Engine::default();
+        let module = Module::from_file(&engine, path)
+            .map_err(|e| anyhow!("{e}"))
+            .with_context(|| format!("couldn't load plugin {}", path.display()))?;
+        let mut store = Store::new(&engine, ());
+        let instance = Instance::new(&mut store, &module, &[])
+            .map_err(|e| anyhow!("couldn't start plugin: {e}"))?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .context("plugin doesn't export its memory")?;
+
+        Ok(Self {
+            store,
+            instance,
+            memory,
+        })
+    }
+
+    /// Runs the plugin's count, giving rounds in the same shape as
+    /// `Ballot::runoff`.
+    pub fn tally<T: Clone>(&mut self, ballot: &Ballot<T>) -> Result<Vec<Round<T>>> {
+        let count = ballot.count();
+        let input = encode(ballot);
+
+        let alloc = self
+            .instance
+            .get_typed_func::<i32, i32>(&mut self.store, "alloc")
+            .map_err(|e| anyhow!("plugin has no alloc function: {e}"))?;
+        let tally = self
+            .instance
+            .get_typed_func::<(i32, i32), i32>(&mut self.store, "tally")
+            .map_err(|e| anyhow!("plugin has no tally function: {e}"))?;
+
+        let len = i32::try_from(input.len()).context("too many ballots for a plugin")?;
+        let ptr = alloc
+            .call(&mut self.store, len)
+            .map_err(|e| anyhow!("plugin alloc failed: {e}"))?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, &input)
+            .context("plugin alloc returned memory out of bounds")?;
+
+        let output = tally
+            .call(&mut self.store, (ptr, len))
+            .map_err(|e| anyhow!("plugin tally failed: {e}"))?;
+        let mut reader = Reader {
+            data: self.memory.data(&self.store),
+            at: output as u32 as usize,
+        };
+
+        let rounds = reader.u32()?;
+        let mut results = vec![];
+
+        for round in 0..rounds {
+            let picked = reader.u32()? as usize;
+            let votes = (0..count).map(|_| reader.f64()).collect::<Result<Vec<_>>>()?;
+
+            if picked >= count || votes[picked].is_nan() {
+                return Err(anyhow!("plugin picked a candidate not in the count, round {round}"));
+            }
+
+            let others = (0..count)
+                .filter(|c| *c != picked && !votes[*c].is_nan())
+                .map(|c| (ballot.labels()[c].clone(), votes[c]))
+                .collect();
+
+            results.push((ballot.labels()[picked].clone(), votes[picked], others));
+        }
+
+        Ok(results)
+    }
+}
+
+fn encode<T: Clone>(ballot: &Ballot<T>) -> Vec<u8> {
+    let mut input = vec![];
+
+    input.extend((ballot.count() as u32).to_le_bytes());
+    input.extend((ballot.ballot_count() as u32).to_le_bytes());
+
+    for rank in ballot.ballots().flat_map(|row| row.into_owned()) {
+        let rank = if rank == UNRANKED { u32::MAX } else { rank as u32 };
+        input.extend(rank.to_le_bytes());
+    }
+
+    for weight in ballot.weights() {
+        input.extend(weight.to_le_bytes());
+    }
+
+    input
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let bytes = self
+            .data
+            .get(self.at..self.at + N)
+            .context("plugin results run out of bounds")?;
+        self.at += N;
+
+        Ok(bytes.try_into().expect("slice is N long"))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        Ok(f64::from_le_bytes(self.take()?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Ballot;
+
+    // a plugin that ignores the ballots and always picks candidate 1 then 0
+    const FIXED: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+            (func (export "tally") (param i32 i32) (result i32) (i32.const 0))
+            (data (i32.const 0)
+                "\02\00\00\00"
+                "\01\00\00\00"
+                "\00\00\00\00\00\00\f0\3f" "\00\00\00\00\00\00\00\40"
+                "\00\00\00\00"
+                "\00\00\00\00\00\00\08\40" "\00\00\00\00\00\00\f8\7f"))
+    "#;
+
+    #[test]
+    fn reads_round_results() {
+        let dir = tempfile::tempdir().expect("temp dir is writable");
+        let path = dir.path().join("fixed-plugin.wat");
+        std::fs::write(&path, FIXED).expect("temp dir is writable");
+
+        let ballot = Ballot::new(vec!['a', 'b'], vec![0, 1, 1, 0, 0, 1]).expect("valid ballot");
+        let mut plugin = super::Plugin::load(&path).expect("plugin loads");
+        let rounds = plugin.tally(&ballot).expect("plugin tallies");
+
+        assert_eq!(rounds, vec![('b', 2.0, vec![('a', 1.0)]), ('a', 3.0, vec![])]);
+    }
+}
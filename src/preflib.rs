@@ -0,0 +1,124 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeMap;
+use std::io::Read;
+
+use crate::Ballot;
+
+/// Reads a PrefLib file of orders: strict and complete (.soc), strict and
+/// incomplete (.soi), or with ties (.toc). The candidates are named by the
+/// "# ALTERNATIVE NAME" lines of the header, and each line after it is how
+/// many voters cast an order and the order itself, like "12: 1,{2,3},4",
+/// where the candidates in braces are ranked equally. Each line becomes a
+/// single ballot weighted by its count.
+pub fn read(mut reader: impl Read) -> Result<Ballot<String>> {
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents).context("couldn't read the PrefLib file")?;
+
+    let mut count = None;
+    let mut names = BTreeMap::new();
+    let mut rankings = vec![];
+    let mut counts = vec![];
+
+    for (i, line) in contents.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+        if let Some(header) = line.strip_prefix('#') {
+            let Some((key, value)) = header.split_once(':') else {
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            if key == "NUMBER ALTERNATIVES" {
+                count = Some(value.parse::<usize>().with_context(|| format!("invalid number of alternatives, line {i}"))?);
+            } else if let Some(number) = key.strip_prefix("ALTERNATIVE NAME ") {
+                let number: usize = number.trim().parse().with_context(|| format!("invalid alternative number, line {i}"))?;
+                names.insert(number, value.to_owned());
+            }
+
+            continue;
+        }
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let count = count.with_context(|| format!("line {i} comes before the NUMBER ALTERNATIVES header"))?;
+        let (voters, order) = line.split_once(':').with_context(|| format!("line {i} isn't a count and an order, like \"3: 1,2\""))?;
+        let voters: f64 = voters
+            .trim()
+            .parse()
+            .ok()
+            .filter(|voters: &f64| voters.is_finite() && *voters >= 0.0)
+            .with_context(|| format!("invalid count {:?}, line {i}", voters.trim()))?;
+
+        rankings.push(parse_order(order, count).with_context(|| format!("invalid order, line {i}"))?);
+        counts.push(voters);
+    }
+
+    let count = count.context("the file has no NUMBER ALTERNATIVES header")?;
+    // alternatives are numbered from 1, and ones without a name go by that
+    let labels = (1..=count).map(|number| names.remove(&number).unwrap_or_else(|| number.to_string())).collect();
+
+    let ballot = Ballot::from_tied_rankings(labels, &rankings).map_err(|_| anyhow!("an order ranks an alternative twice"))?;
+
+    Ok(ballot.with_weights(counts).expect("a count for every order"))
+}
+
+/// Parses an order like "1,{2,3},4" into groups of equally ranked
+/// alternatives (by index), most preferred first.
+fn parse_order(order: &str, count: usize) -> Result<Vec<Vec<usize>>> {
+    let mut groups = vec![];
+    let mut group: Option<Vec<usize>> = None;
+    let mut number = String::new();
+
+    // a trailing comma makes sure the last number is read
+    for c in order.trim().chars().chain([',']) {
+        match c {
+            '{' if group.is_none() => group = Some(vec![]),
+            '0'..='9' => number.push(c),
+            ',' | '}' => {
+                if !number.is_empty() {
+                    let alternative: usize = number.parse()?;
+
+                    if !(1..=count).contains(&alternative) {
+                        return Err(anyhow!("{alternative} isn't one of the {count} alternatives"));
+                    }
+
+                    match &mut group {
+                        Some(group) => group.push(alternative - 1),
+                        None => groups.push(vec![alternative - 1]),
+                    }
+
+                    number.clear();
+                }
+
+                if c == '}' {
+                    let tied = group.take().context("a } without a {")?;
+
+                    if !tied.is_empty() {
+                        groups.push(tied);
+                    }
+                }
+            }
+            c if c.is_whitespace() => {}
+            c => return Err(anyhow!("unexpected {c:?}")),
+        }
+    }
+
+    match group {
+        Some(_) => Err(anyhow!("a {{ without a }}")),
+        None => Ok(groups),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn counts_and_ties() {
+        let data = "# DATA TYPE: toc\n# NUMBER ALTERNATIVES: 3\n# ALTERNATIVE NAME 1: Alice\n# ALTERNATIVE NAME 2: Bob\n12: 1,{2,3}\n5: 3\n";
+        let ballot = super::read(data.as_bytes()).expect("valid orders");
+
+        assert_eq!(ballot.labels(), ["Alice", "Bob", "3"]);
+        assert_eq!(ballot.weights(), [12.0, 5.0]);
+        assert_eq!(ballot.ballots().next().as_deref(), Some(&[0, 1, 1][..]));
+        assert!(super::read("# NUMBER ALTERNATIVES: 2\n1: 1,3\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,593 @@
+use anyhow::{anyhow, Context, Result};
+use csv::StringRecord;
+use std::io::Read;
+
+use crate::{cvr, inspect, Ballot, UNRANKED};
+
+/// Reads a CSV of ranks (one column per candidate, named in the header, and
+/// one row per respondent) into ballots. This is what the rankit command does
+/// with csv input, minus the warnings about suspicious columns.
+pub fn read(reader: impl Read, start: usize, len: Option<usize>, indexed_at: usize) -> Result<Ballot<String>> {
+    let (headers, records) = read_table(reader)?;
+    let labels = labels(&headers, start, len);
+
+    let columns = inspect::summarize(&headers, &records);
+    inspect::check_ranks(&columns, start, labels.len(), indexed_at)?;
+
+    let all_ranks = parse(&records, start, len, indexed_at, labels.len())?;
+
+    Ok(Ballot::new(labels, all_ranks).expect("labels and votes mismatch"))
+}
+
+/// Reads a CSV with a column per rank (after the header) holding the name of
+/// the candidate given it, as Google Forms and many survey tools export ranked
+/// questions, into ballots.
+pub fn read_by_rank(reader: impl Read, start: usize, len: Option<usize>) -> Result<Ballot<String>> {
+    let (_, records) = read_table(reader)?;
+
+    parse_by_rank(&records, start, len)
+}
+
+/// Like `read_by_rank`, for records that have already been read. The
+/// candidates are the names found, in the order they first appear. Blank ranks
+/// are skipped, as are candidates named again.
+pub fn parse_by_rank(records: &[StringRecord], start: usize, len: Option<usize>) -> Result<Ballot<String>> {
+    let (labels, rankings) = cvr::by_rank(records, start, len);
+
+    if labels.is_empty() {
+        return Err(anyhow!("no candidates were named in the rank columns"));
+    }
+
+    Ok(Ballot::from_rankings(labels, &rankings).expect("rankings only contain known candidates"))
+}
+
+/// The words an approval cell can hold, since survey exports often write
+/// "yes" or "oui" or a checkmark rather than 1. Case doesn't matter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vocabulary {
+    approved: Vec<String>,
+    unapproved: Vec<String>,
+}
+
+impl Default for Vocabulary {
+    /// 1 and 0, and yes and no in a few languages, and checkbox marks.
+    fn default() -> Self {
+        let words = |words: &[&str]| words.iter().map(|word| word.to_string()).collect();
+
+        Self {
+            approved: words(&["1", "yes", "y", "oui", "sí", "si", "ja", "true", "x", "✓", "✔", "☑", "☒", "[x]"]),
+            unapproved: words(&["0", "", "no", "n", "non", "nein", "false", "☐", "[ ]"]),
+        }
+    }
+}
+
+impl Vocabulary {
+    /// Adds more words for approved and not, failing with a word that would
+    /// end up meaning both.
+    pub fn extend(&mut self, approved: &[String], unapproved: &[String]) -> Result<(), String> {
+        self.approved.extend(approved.iter().map(|word| word.trim().to_lowercase()));
+        self.unapproved.extend(unapproved.iter().map(|word| word.trim().to_lowercase()));
+
+        match self.approved.iter().find(|word| self.unapproved.contains(word)) {
+            Some(word) => Err(word.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether a cell means approved, or nothing if it isn't a known word.
+    pub fn approves(&self, cell: &str) -> Option<bool> {
+        let cell = cell.trim().to_lowercase();
+
+        match (self.approved.contains(&cell), self.unapproved.contains(&cell)) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// Reads a CSV of approvals (one column per candidate, named in the header,
+/// holding 1 for approved and 0 or nothing for not, or words like yes and no)
+/// into ballots that rank every approved candidate equally first.
+pub fn read_approvals(reader: impl Read, start: usize, len: Option<usize>) -> Result<Ballot<String>> {
+    let (headers, records) = read_table(reader)?;
+
+    parse_approvals(&headers, &records, start, len, &Vocabulary::default())
+}
+
+/// Like `read_approvals`, for a table that's already been read, with cells
+/// read by `vocabulary`.
+pub fn parse_approvals(
+    headers: &StringRecord,
+    records: &[StringRecord],
+    start: usize,
+    len: Option<usize>,
+    vocabulary: &Vocabulary,
+) -> Result<Ballot<String>> {
+    let labels = labels(headers, start, len);
+
+    let approvals = records
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let cells: Vec<_> = row.iter().skip(start).take(labels.len()).collect();
+
+            if cells.len() != labels.len() {
+                let (count, got) = (labels.len(), cells.len());
+
+                return Err(anyhow!("invalid number of approvals, record {i} (expected {count}, got {got})"));
+            }
+
+            let mut approved = vec![];
+
+            for (j, cell) in cells.into_iter().enumerate() {
+                match vocabulary.approves(cell) {
+                    Some(true) => approved.push(j),
+                    Some(false) => {}
+                    None => {
+                        let other = cell.trim();
+
+                        return Err(anyhow!("invalid approval {other:?}, record {i}, value {j} (expected 0 or 1, or a word like yes or no)"));
+                    }
+                }
+            }
+
+            // nobody approved is an empty ballot, not a ballot ranking nobody first
+            Ok(match approved.is_empty() {
+                true => vec![],
+                false => vec![approved],
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Ballot::from_tied_rankings(labels, &approvals).expect("approvals only contain known candidates"))
+}
+
+/// The candidates, and every ballot's score (if any) for each of them.
+pub type Scores = (Vec<String>, Vec<Vec<Option<f64>>>);
+
+/// Reads a CSV of scores (one column per candidate, named in the header, and
+/// one row per respondent) into the candidates and each ballot's score for
+/// every one of them, or nothing where the cell is blank. Every score has to
+/// be from `min` to `max`.
+pub fn read_scores(
+    reader: impl Read,
+    start: usize,
+    len: Option<usize>,
+    min: f64,
+    max: f64,
+) -> Result<Scores> {
+    let (headers, records) = read_table(reader)?;
+
+    parse_scores(&headers, &records, start, len, min, max)
+}
+
+/// Like `read_scores`, for a table that's already been read.
+pub fn parse_scores(
+    headers: &StringRecord,
+    records: &[StringRecord],
+    start: usize,
+    len: Option<usize>,
+    min: f64,
+    max: f64,
+) -> Result<Scores> {
+    let labels = labels(headers, start, len);
+
+    let ballots = records
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let cells: Vec<_> = row.iter().skip(start).take(labels.len()).collect();
+
+            if cells.len() != labels.len() {
+                let (count, got) = (labels.len(), cells.len());
+
+                return Err(anyhow!("invalid number of scores, record {i} (expected {count}, got {got})"));
+            }
+
+            cells
+                .into_iter()
+                .enumerate()
+                .map(|(j, cell)| match cell.trim() {
+                    "" => Ok(None),
+                    cell => cell
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|score| (min..=max).contains(score))
+                        .map(Some)
+                        .with_context(|| format!("invalid score {cell:?}, record {i}, value {j} (expected {min} to {max})")),
+                })
+                .collect()
+        })
+        .collect::<Result<_>>()?;
+
+    Ok((labels, ballots))
+}
+
+/// How a column is read, according to a rules file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Rank,
+    Score,
+    Approval,
+    /// How much the record's ballot counts for
+    Weight,
+    /// The respondent's group, as with --group-by
+    Group,
+    /// Something that identifies the respondent, which can't be repeated
+    Id,
+    Ignore,
+}
+
+/// Reads a rules file: a CSV (with headers) naming a column in its first
+/// column, by its header or its index (from 0), and how to read it in its
+/// second (rank, score, approval, weight, group, id or ignore). Returns how
+/// to read every one of `headers`, where columns without a rule are ignored.
+pub fn read_rules(reader: impl Read, headers: &StringRecord) -> Result<Vec<Kind>> {
+    let (_, rules) = read_table(reader)?;
+    let mut kinds = vec![Kind::Ignore; headers.len()];
+    let mut ruled = vec![false; headers.len()];
+
+    for (i, rule) in rules.iter().enumerate() {
+        let (Some(column), Some(kind)) = (rule.get(0).map(str::trim), rule.get(1).map(str::trim)) else {
+            return Err(anyhow!("rule {i} needs a column and a kind"));
+        };
+
+        // a header that happens to be a number is matched as a header first
+        let index = headers
+            .iter()
+            .position(|header| header == column)
+            .or_else(|| column.parse().ok().filter(|index| *index < headers.len()))
+            .with_context(|| format!("rule {i} is for {column:?}, which isn't a column"))?;
+
+        kinds[index] = match kind.to_ascii_lowercase().as_str() {
+            "rank" => Kind::Rank,
+            "score" => Kind::Score,
+            "approval" => Kind::Approval,
+            "weight" => Kind::Weight,
+            "group" => Kind::Group,
+            "id" => Kind::Id,
+            "ignore" => Kind::Ignore,
+            _ => return Err(anyhow!("rule {i} has an unknown kind {kind:?} (expected rank, score, approval, weight, group, id or ignore)")),
+        };
+
+        if std::mem::replace(&mut ruled[index], true) {
+            return Err(anyhow!("column {column:?} has more than one rule"));
+        }
+    }
+
+    Ok(kinds)
+}
+
+/// Only the given columns of a record, in that order.
+pub fn select(record: &StringRecord, columns: &[usize]) -> StringRecord {
+    columns.iter().map(|column| record.get(*column).unwrap_or_default()).collect()
+}
+
+/// A record's checksum: the 64-bit FNV-1a hash, in hex, of the salt, a colon,
+/// and its rank cells (trimmed) joined by commas. A salt that's kept private
+/// means a checksum can't be made up to match a wrongly keyed ballot.
+pub fn checksum(salt: &str, cells: &[&str]) -> String {
+    let text = format!("{salt}:{}", cells.join(","));
+    let hash = text
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+
+    format!("{hash:016x}")
+}
+
+/// Which records (by index) have a checksum in `column` that doesn't match
+/// their `count` rank cells from `start`.
+pub fn mismatched_checksums(records: &[StringRecord], start: usize, count: usize, column: usize, salt: &str) -> Vec<usize> {
+    records
+        .iter()
+        .enumerate()
+        .filter(|(_, row)| {
+            let cells: Vec<_> = row.iter().skip(start).take(count).map(str::trim).collect();
+            let expected = checksum(salt, &cells);
+
+            !row.get(column).is_some_and(|given| given.trim().eq_ignore_ascii_case(&expected))
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Reads a CSV's header and every record after it.
+pub fn read_table(reader: impl Read) -> Result<(StringRecord, Vec<StringRecord>)> {
+    let mut csv_reader = csv::Reader::from_reader(reader);
+
+    let headers = csv_reader.headers().context("headers issue")?.clone();
+    let records: Vec<_> = csv_reader
+        .records()
+        .enumerate()
+        .map(|(i, r)| r.with_context(|| format!("bad record {i}")))
+        .collect::<Result<_>>()?;
+
+    Ok((headers, records))
+}
+
+/// How a CSV file is laid out, for exports that don't look like the usual
+/// comma-separated file with a header row.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dialect {
+    /// What separates cells, like b'\t' or b';'
+    pub delimiter: u8,
+    /// Whether the first row names the columns. If it doesn't, it's read as
+    /// a record like the rest, and columns are named by their number from 1.
+    pub headers: bool,
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Self { delimiter: b',', headers: true }
+    }
+}
+
+impl Dialect {
+    /// Starts reading a CSV file laid out this way.
+    pub fn reader<R: Read>(self, reader: R) -> csv::Reader<R> {
+        self.builder().from_reader(reader)
+    }
+
+    fn builder(self) -> csv::ReaderBuilder {
+        let mut builder = csv::ReaderBuilder::new();
+        builder.delimiter(self.delimiter).has_headers(self.headers);
+
+        builder
+    }
+
+    /// The names of the columns: the header row, or numbers without one.
+    pub fn headers<R: Read>(self, csv_reader: &mut csv::Reader<R>) -> Result<StringRecord> {
+        let headers = csv_reader.headers().context("headers issue")?;
+
+        Ok(match self.headers {
+            true => headers.clone(),
+            false => (1..=headers.len()).map(|column| column.to_string()).collect(),
+        })
+    }
+}
+
+/// What to do with records that have fewer or more cells than there are
+/// headers, which some tools export by leaving out trailing blank cells.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Ragged {
+    /// They're an error
+    #[default]
+    Reject,
+    /// Records that are short are kept, and the candidates they're missing
+    /// are unranked (see `parse_padded`)
+    Pad,
+    /// Records that are long have the cells past the last header dropped
+    Truncate,
+}
+
+/// Like `read_table`, but for a file laid out like `dialect`, and handling
+/// records without a cell for every header by `ragged`. Also returns which
+/// records (by index) were repaired.
+pub fn read_ragged_table(reader: impl Read, ragged: Ragged, dialect: Dialect) -> Result<(StringRecord, Vec<StringRecord>, Vec<usize>)> {
+    let mut csv_reader = dialect.builder().flexible(ragged != Ragged::Reject).from_reader(reader);
+    let headers = dialect.headers(&mut csv_reader)?;
+    let mut records = vec![];
+    let mut repaired = vec![];
+
+    for (i, r) in csv_reader.records().enumerate() {
+        let mut record = r.with_context(|| format!("bad record {i}"))?;
+        let (cells, width) = (record.len(), headers.len());
+
+        match (ragged, cells.cmp(&width)) {
+            (_, std::cmp::Ordering::Equal) => {}
+            (Ragged::Pad, std::cmp::Ordering::Less) => repaired.push(i),
+            (Ragged::Truncate, std::cmp::Ordering::Greater) => {
+                record.truncate(width);
+                repaired.push(i);
+            }
+            _ => return Err(anyhow!("record {i} has {cells} cells, but there are {width} headers")),
+        }
+
+        records.push(record);
+    }
+
+    Ok((headers, records, repaired))
+}
+
+/// The candidates' names, from the headers of the rank columns.
+pub fn labels(headers: &StringRecord, start: usize, len: Option<usize>) -> Vec<String> {
+    let headers_start = headers.iter().skip(start).map(String::from);
+
+    if let Some(len) = len {
+        headers_start.take(len).collect()
+    } else {
+        headers_start.collect()
+    }
+}
+
+/// Reads every record's ranks from `len` columns starting at `start` (or every
+/// column from `start` on), as one flattened list with the highest rank as 0
+/// and no gaps between ranks, ready for `Ballot::new`. `indexed_at` is the value of the highest rank, and
+/// every record has to have a rank for each of the `count` candidates.
+pub fn parse(
+    records: &[StringRecord],
+    start: usize,
+    len: Option<usize>,
+    indexed_at: usize,
+    count: usize,
+) -> Result<Vec<usize>> {
+    parse_rows(records, start, len, indexed_at, count, false)
+}
+
+/// Like `parse`, but a record that ends before its last rank cell is
+/// unranked for the candidates it's missing, for records kept by
+/// `Ragged::Pad`.
+pub fn parse_padded(
+    records: &[StringRecord],
+    start: usize,
+    len: Option<usize>,
+    indexed_at: usize,
+    count: usize,
+) -> Result<Vec<usize>> {
+    parse_rows(records, start, len, indexed_at, count, true)
+}
+
+fn parse_rows(
+    records: &[StringRecord],
+    start: usize,
+    len: Option<usize>,
+    indexed_at: usize,
+    count: usize,
+    pad: bool,
+) -> Result<Vec<usize>> {
+    let mut all_ranks = records.iter().enumerate().try_fold(
+        vec![],
+        |mut all_ranks, (i, row)| {
+            let row_ranks = {
+                let cells: Vec<_> = {
+                    let cols_start = row.iter().skip(start).map(String::from);
+
+                    if let Some(len) = len {
+                        cols_start.take(len).collect()
+                    } else {
+                        cols_start.collect()
+                    }
+                };
+
+                let merhaps: Result<Vec<_>, _> = cells
+                    .into_iter()
+                    .enumerate()
+                    .map(|(j, v)| match v.trim() {
+                        // a blank cell is a candidate the voter didn't rank
+                        "" => Ok(UNRANKED),
+                        _ => v.parse::<usize>().with_context(|| format!("invalid rank, record {i}, value {j}")),
+                    })
+                    .collect();
+
+                let mut row_ranks: Vec<_> = merhaps?;
+
+                if pad && row_ranks.len() < count {
+                    row_ranks.resize(count, UNRANKED);
+                }
+
+                row_ranks
+            };
+
+            let row_ranks_len = row_ranks.len();
+
+            if row_ranks_len != count {
+                Err(anyhow!("invalid number of ranks, record {i} (expected {count}, got {row_ranks_len})"))
+            } else {
+                all_ranks.extend(row_ranks);
+
+                Ok(all_ranks)
+            }
+        },
+    )?;
+
+    for value in all_ranks.iter_mut().filter(|value| **value != UNRANKED) {
+        if let Some(sub) = value.checked_sub(indexed_at) {
+            *value = sub;
+        } else {
+            return Err(anyhow!(
+                "bad index-at argument (ranks occur lower than the index)"
+            ));
+        }
+    }
+
+    // a ballot only counts for its highest rank still standing if that's 0,
+    // so gaps left by skipped or blank ranks are closed up, with equal ranks
+    // staying equal
+    for row in all_ranks.chunks_mut(count.max(1)) {
+        let mut used: Vec<_> = row.iter().copied().filter(|rank| *rank != UNRANKED).collect();
+        used.sort_unstable();
+        used.dedup();
+
+        for rank in row.iter_mut().filter(|rank| **rank != UNRANKED) {
+            *rank = used.binary_search(rank).expect("every rank is used");
+        }
+    }
+
+    Ok(all_ranks)
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn approvals_in_words() {
+        let data = "Pizza,Tacos,Sushi\nOui,non,\n☑,Sí,no\n";
+        let ballot = super::read_approvals(data.as_bytes(), 0, None).expect("known words");
+        assert_eq!(ballot.tier(), vec![2.0, 1.0, 0.0]);
+
+        let mut vocabulary = super::Vocabulary::default();
+        vocabulary.extend(&[String::from("Hai")], &[]).expect("a new word");
+        assert_eq!(vocabulary.approves(" hai "), Some(true));
+        assert_eq!(vocabulary.extend(&[String::from("NO")], &[]), Err(String::from("no")));
+    }
+
+    #[test]
+    fn names_by_rank() {
+        // Ann is named twice, Cat only ever second, and the last ranks are blank
+        let data = "Rank 1,Rank 2,Rank 3\nAnn,Ann,Bob\nBob, Cat ,\n,Ann,\nBob,,\n";
+        let ballot = super::read_by_rank(data.as_bytes(), 0, None).expect("named candidates");
+        let rankings: Vec<Vec<_>> = (0..4).map(|i| ballot.ranking(i).map(String::as_str).collect()).collect();
+
+        assert_eq!(ballot.labels(), ["Ann", "Bob", "Cat"]);
+        assert_eq!(rankings, [vec!["Ann", "Bob"], vec!["Bob", "Cat"], vec!["Ann"], vec!["Bob"]]);
+        assert!(super::read_by_rank("Rank 1,Rank 2\n,\nundervote,\n".as_bytes(), 0, None).is_err());
+    }
+
+    #[test]
+    fn catches_miskeyed_ranks() {
+        let sum = super::checksum("club", &["1", "2", "3"]);
+        let records: Vec<_> = [["1", "2", "3", &sum], ["1", "3", "2", &sum]]
+            .iter()
+            .map(|row| csv::StringRecord::from(&row[..]))
+            .collect();
+
+        assert_eq!(sum.len(), 16);
+        assert_ne!(super::checksum("other", &["1", "2", "3"]), sum);
+        assert_eq!(super::mismatched_checksums(&records, 0, 3, 3, "club"), vec![1]);
+    }
+
+    #[test]
+    fn pads_short_records() {
+        let dialect = super::Dialect { delimiter: b';', headers: true };
+        let data = "a;b;c\n1;2;3\n2;1\n";
+        assert!(super::read_ragged_table(data.as_bytes(), super::Ragged::Truncate, dialect).is_err());
+
+        let (_, records, repaired) = super::read_ragged_table(data.as_bytes(), super::Ragged::Pad, dialect).expect("paddable");
+        assert_eq!(repaired, vec![1]);
+        assert_eq!(super::parse_padded(&records, 0, None, 1, 3).expect("valid ranks"), [0, 1, 2, 1, 0, crate::UNRANKED]);
+
+        // a blank cell is unranked too, whether or not the record is short
+        let blank = [csv::StringRecord::from(vec!["2", " ", "1"])];
+        assert_eq!(super::parse(&blank, 0, None, 1, 3).expect("valid ranks"), [1, crate::UNRANKED, 0]);
+    }
+
+    #[test]
+    fn closes_gaps_in_ranks() {
+        // nobody's ranked 1 on the first ballot, and 2 is left blank on the second
+        let records: Vec<_> = [["2", "", "3"], ["1", "", "3"], ["1", "3", "3"]].iter().map(|row| csv::StringRecord::from(&row[..])).collect();
+        let ranks = super::parse(&records, 0, None, 1, 3).expect("valid ranks");
+        assert_eq!(ranks, [0, crate::UNRANKED, 1, 0, crate::UNRANKED, 1, 0, 1, 1]);
+
+        // the first ballot counts for a, and the second moves on to c once a is out
+        let ballot = crate::Ballot::new(vec!['a', 'b', 'c'], ranks[..6].to_vec()).expect("valid ranks");
+        let (rounds, _) = crate::irv::count(&ballot, Default::default());
+        assert_eq!(rounds[0].tally, vec![('a', 2.0), ('b', 0.0), ('c', 0.0)]);
+
+        let mut rest = ballot;
+        rest.take(0);
+        assert_eq!(rest.tier(), vec![0.0, 2.0]);
+    }
+
+    #[test]
+    fn reads_column_rules() {
+        use super::Kind;
+
+        let headers = csv::StringRecord::from(vec!["id", "Pizza", "Tacos", "region"]);
+        let rules = "column,kind\nPizza,rank\n2,rank\nregion,Group\nid,id\n";
+
+        let kinds = super::read_rules(rules.as_bytes(), &headers).expect("valid rules");
+        assert_eq!(kinds, [Kind::Id, Kind::Rank, Kind::Rank, Kind::Group]);
+        assert!(super::read_rules("column,kind\nPizza,rank\nPizza,score\n".as_bytes(), &headers).is_err());
+        assert!(super::read_rules("column,kind\nSushi,rank\n".as_bytes(), &headers).is_err());
+    }
+}
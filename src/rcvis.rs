@@ -0,0 +1,103 @@
+use serde_json::{json, Map, Value};
+
+use crate::display::DisplayNames;
+
+/// One round of the runoff: the winner, their votes, and everyone else's votes.
+pub type Round = (String, f64, Vec<(String, f64)>);
+
+/// The round-by-round results in the summary JSON format produced by RCTab,
+/// which RCVIS (and other public RCV visualizers) read directly. If the
+/// candidates were numbered, their numbers are added under `candidateIds`.
+pub fn summary(rounds: &[Round], total: f64, names: &DisplayNames) -> Value {
+    let results: Vec<_> = rounds
+        .iter()
+        .enumerate()
+        .map(|(i, (winner, votes, others))| {
+            let tally: Map<_, _> = std::iter::once((winner, votes))
+                .chain(others.iter().map(|(label, count)| (label, count)))
+                .map(|(label, count)| (label.clone(), number(*count)))
+                .collect();
+
+            // only the winner's ballots move on to the next round, so every
+            // gain in the next round was transferred from them
+            let transfers: Map<_, _> = rounds
+                .get(i + 1)
+                .map(|(next_winner, next_votes, next_others)| {
+                    std::iter::once((next_winner, next_votes))
+                        .chain(next_others.iter().map(|(label, count)| (label, count)))
+                        .filter_map(|(label, next)| {
+                            let (_, before) = others.iter().find(|(l, _)| l == label)?;
+                            let gained = next - before;
+
+                            (gained > 0.0).then(|| (label.clone(), number(gained)))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            json!({
+                "round": i + 1,
+                "tally": tally,
+                "tallyResults": [{ "elected": winner, "transfers": transfers }],
+            })
+        })
+        .collect();
+
+    let mut summary = json!({
+        "config": {
+            "contest": "",
+            "date": "",
+            "jurisdiction": "",
+            "office": "",
+            "threshold": number(total / 2.0),
+        },
+        "results": results,
+    });
+
+    let ids: Map<_, _> = rounds
+        .first()
+        .into_iter()
+        .flat_map(|(winner, _, others)| {
+            std::iter::once(winner).chain(others.iter().map(|(label, _)| label))
+        })
+        .filter_map(|label| Some((label.clone(), json!(names.id(label)?))))
+        .collect();
+
+    if !ids.is_empty() {
+        summary["config"]["candidateIds"] = Value::Object(ids);
+    }
+
+    summary
+}
+
+/// RCTab writes every count as a string
+fn number(votes: f64) -> Value {
+    Value::String(crate::format_votes(votes))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::display::DisplayNames;
+
+    #[test]
+    fn rctab_summary() {
+        let label = String::from;
+        let rounds = vec![
+            (label("a"), 3.0, vec![(label("b"), 2.0), (label("c"), 1.0)]),
+            (label("b"), 4.5, vec![(label("c"), 1.5)]),
+        ];
+        let summary = super::summary(&rounds, 6.0, &DisplayNames::default());
+
+        // a's ballots went on to b and c once a won
+        assert_eq!(summary["config"]["threshold"], "3");
+        assert_eq!(summary["results"][0]["tally"]["c"], "1");
+        assert_eq!(summary["results"][0]["tallyResults"][0]["elected"], "a");
+        assert_eq!(summary["results"][0]["tallyResults"][0]["transfers"], serde_json::json!({ "b": "2.50", "c": "0.50" }));
+        assert_eq!(summary["results"][1]["tallyResults"][0]["transfers"], serde_json::json!({}));
+        assert!(summary["config"].get("candidateIds").is_none());
+
+        let names = DisplayNames::default().with_ids(&[label("a"), label("b"), label("c")]);
+        let summary = super::summary(&rounds, 6.0, &names);
+        assert_eq!(summary["config"]["candidateIds"], serde_json::json!({ "a": 1, "b": 2, "c": 3 }));
+    }
+}
@@ -0,0 +1,104 @@
+use alloc::{vec, vec::Vec};
+
+use crate::Ballot;
+
+/// Where a candidate's votes in one round came from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Retention {
+    /// Their votes from voters who ranked them first to begin with
+    pub retained: f64,
+    /// Their first preferences in the first round
+    pub original: f64,
+    /// All of their votes this round
+    pub votes: f64,
+}
+
+impl Retention {
+    /// The fraction of their first-preference voters still counting for them
+    pub fn kept(&self) -> Option<f64> {
+        (self.original > 0.0).then(|| self.retained / self.original)
+    }
+
+    /// The fraction of their votes this round that were transferred to them
+    pub fn transferred(&self) -> Option<f64> {
+        (self.votes > 0.0).then(|| (self.votes - self.retained) / self.votes)
+    }
+}
+
+/// Works out where every candidate's votes came from in each round of a
+/// count, given the candidates still in the count in each round. Each round's
+/// retention is in the same order as its candidates.
+pub fn retention<T: Clone + PartialEq>(ballot: &Ballot<T>, rounds: &[Vec<T>]) -> Vec<Vec<Retention>> {
+    let mut current = ballot.clone();
+    // the candidates each ballot counted for in the first round
+    let mut original: Option<Vec<Vec<T>>> = None;
+    let mut retention = vec![];
+
+    for round in rounds {
+        while let Some(gone) = current.labels().iter().position(|label| !round.contains(label)) {
+            current.take(gone);
+        }
+
+        let counting: Vec<Vec<T>> = current
+            .ballots()
+            .map(|row| {
+                (0..row.len())
+                    .filter(|c| row[*c] == 0)
+                    .map(|c| current.labels()[c].clone())
+                    .collect()
+            })
+            .collect();
+        let original = original.get_or_insert_with(|| counting.clone());
+
+        let round_retention = round
+            .iter()
+            .map(|candidate| {
+                let mut totals = Retention {
+                    retained: 0.0,
+                    original: 0.0,
+                    votes: 0.0,
+                };
+
+                for ((now, first), weight) in counting.iter().zip(original.iter()).zip(ballot.weights()) {
+                    let now = now.contains(candidate);
+                    let first = first.contains(candidate);
+
+                    if now {
+                        totals.votes += weight;
+                    }
+
+                    if first {
+                        totals.original += weight;
+                    }
+
+                    if now && first {
+                        totals.retained += weight;
+                    }
+                }
+
+                totals
+            })
+            .collect();
+
+        retention.push(round_retention);
+    }
+
+    retention
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Ballot;
+
+    #[test]
+    fn transfers_after_a_win() {
+        let ballot = Ballot::new(vec!['a', 'b'], vec![0, 1, 0, 1, 1, 0]).expect("valid ballot");
+        let rounds = vec![vec!['a', 'b'], vec!['b']];
+        let retention = super::retention(&ballot, &rounds);
+
+        assert_eq!(retention[0][1].transferred(), Some(0.0));
+        assert_eq!((retention[1][0].votes, retention[1][0].kept()), (3.0, Some(1.0)));
+        assert_eq!(retention[1][0].transferred(), Some(2.0 / 3.0));
+    }
+}
@@ -0,0 +1,140 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A small seedable pseudo-random number generator (SplitMix64).
+///
+/// This isn't cryptographically secure, but it's fast, has no dependencies,
+/// and (most importantly) the same seed always gives the same sequence on
+/// every platform, which is what matters when someone wants to re-run a draw.
+///
+/// Every value drawn with `below` (and so `sample`) is logged, so that an
+/// observer can check each draw against the seed, unless logging is turned
+/// off for a simulation nobody's going to check.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+    context: String,
+    draws: Vec<Draw>,
+    logging: bool,
+}
+
+/// One value drawn from an `Rng`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Draw {
+    /// What it was drawn for, as last set with `Rng::set_context`
+    pub context: String,
+    /// It was drawn from `0..bound`
+    pub bound: usize,
+    pub value: usize,
+    /// How many raw values were thrown away before it to avoid bias
+    pub rejected: usize,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed,
+            context: String::new(),
+            draws: Vec::new(),
+            logging: true,
+        }
+    }
+
+    /// Turns the log of draws on or off. Simulations can draw millions of
+    /// values, which only need keeping if someone's going to read them.
+    pub fn with_logging(mut self, logging: bool) -> Self {
+        self.logging = logging;
+        self
+    }
+
+    /// Says what the draws after this are for, in the log.
+    pub fn set_context(&mut self, context: impl Into<String>) {
+        self.context = context.into();
+    }
+
+    /// Every value drawn so far, in order.
+    pub fn draws(&self) -> &[Draw] {
+        &self.draws
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly distributed value in `0..bound`.
+    ///
+    /// # Panics
+    ///
+    /// If `bound` is 0, since there's nothing to draw.
+    pub fn below(&mut self, bound: usize) -> usize {
+        assert!(bound > 0, "can't draw a value below 0");
+
+        let bound = bound as u64;
+        // rejection sampling so that small bounds aren't biased
+        let zone = u64::MAX - (u64::MAX % bound);
+        let mut rejected = 0;
+
+        loop {
+            let value = self.next_u64();
+
+            if value < zone {
+                let value = (value % bound) as usize;
+
+                if self.logging {
+                    self.draws.push(Draw {
+                        context: self.context.clone(),
+                        bound: bound as usize,
+                        value,
+                        rejected,
+                    });
+                }
+
+                return value;
+            }
+
+            rejected += 1;
+        }
+    }
+
+    /// Picks `amount` distinct values from `pool` (or all of them if there
+    /// aren't enough), in the order they were drawn.
+    pub fn sample<T: Copy>(&mut self, pool: &[T], amount: usize) -> Vec<T> {
+        let mut pool = pool.to_vec();
+        let amount = amount.min(pool.len());
+
+        // partial Fisher-Yates
+        for i in 0..amount {
+            let j = i + self.below(pool.len() - i);
+            pool.swap(i, j);
+        }
+
+        pool.truncate(amount);
+        pool
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Rng;
+
+    #[test]
+    fn draws_below_the_bound() {
+        let mut rng = Rng::new(7);
+
+        assert!((0..100).all(|_| rng.below(3) < 3));
+        assert_eq!(rng.below(1), 0);
+        assert_eq!(rng.draws().len(), 101);
+    }
+
+    #[test]
+    #[should_panic(expected = "can't draw a value below 0")]
+    fn nothing_below_zero() {
+        Rng::new(7).below(0);
+    }
+}
@@ -0,0 +1,65 @@
+use alloc::format;
+use alloc::string::String;
+
+/// How fractional counts are rounded before they're compared or shown, since
+/// election rules often spell this out (e.g. "truncated to 5 decimal places").
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Rounding {
+    /// Counts are used as they are
+    #[default]
+    Exact,
+    /// Counts are cut off after this many decimal places
+    Truncate(usize),
+    /// Counts are rounded to this many decimal places, with halves rounded up
+    HalfUp(usize),
+}
+
+impl Rounding {
+    /// Rounds a count, which should be 0 or more.
+    pub fn apply(self, votes: f64) -> f64 {
+        let (decimals, half) = match self {
+            Self::Exact => return votes,
+            Self::Truncate(decimals) => (decimals, 0.0),
+            Self::HalfUp(decimals) => (decimals, 0.5),
+        };
+
+        let scale = (0..decimals).fold(1.0, |scale, _| scale * 10.0);
+        let scaled = votes * scale;
+
+        // 0.3 * 10 comes out as 2.9999999999999996, which shouldn't truncate
+        // to 2, so anything within a hair of a whole number counts as it
+        let nearest = whole(scaled + 0.5);
+        let rounded = if (scaled - nearest).abs() < 1e-9 { nearest } else { whole(scaled + half) };
+
+        rounded / scale
+    }
+
+    /// Shows a rounded count, without decimals if it's whole.
+    pub fn format(self, votes: f64) -> String {
+        match self {
+            Self::Exact => crate::format_votes(votes),
+            Self::Truncate(_) | Self::HalfUp(_) if votes % 1.0 == 0.0 => format!("{votes}"),
+            Self::Truncate(decimals) | Self::HalfUp(decimals) => format!("{votes:.decimals$}"),
+        }
+    }
+}
+
+/// Rounds down to a whole number, for counts that can't be negative (floor()
+/// needs std).
+fn whole(value: f64) -> f64 {
+    value as u64 as f64
+}
+
+#[cfg(test)]
+mod test {
+    use super::Rounding;
+
+    #[test]
+    fn statute_rounding() {
+        assert_eq!(Rounding::Truncate(5).apply(2.0 / 3.0), 0.66666);
+        assert_eq!(Rounding::HalfUp(5).apply(2.0 / 3.0), 0.66667);
+        assert_eq!(Rounding::Truncate(1).apply(0.1 + 0.2), 0.3);
+        assert_eq!(Rounding::Exact.apply(2.0 / 3.0), 2.0 / 3.0);
+        assert_eq!(Rounding::Truncate(5).format(0.66666), "0.66666");
+    }
+}
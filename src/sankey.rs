@@ -0,0 +1,78 @@
+use serde_json::{json, Value};
+
+use crate::display::DisplayNames;
+use rankit::irv;
+
+/// Lays out an instant-runoff or Coombs count as a Sankey diagram, in the
+/// shape plotly's sankey trace takes: a node for every candidate (and the
+/// exhausted ballots) in every round, and links for the votes that stayed
+/// with a candidate or moved on from one round to the next.
+pub fn plotly(rounds: &[irv::Round<String>], names: &DisplayNames) -> Value {
+    let mut labels = vec![];
+    // each round's nodes, by candidate (or None for exhausted)
+    let mut nodes: Vec<Vec<(Option<&str>, usize)>> = vec![];
+
+    for (i, round) in rounds.iter().enumerate() {
+        let candidates = round.tally.iter().map(|(label, _)| Some(label.as_str()));
+        let mut round_nodes = vec![];
+
+        for candidate in candidates.chain([None]) {
+            round_nodes.push((candidate, labels.len()));
+            labels.push(format!("{} (round {})", candidate.map_or("Exhausted", |label| names.get(label)), i + 1));
+        }
+
+        nodes.push(round_nodes);
+    }
+
+    let node = |round: usize, candidate: Option<&str>| nodes[round].iter().find(|(c, _)| *c == candidate).map(|(_, node)| *node);
+    let (mut source, mut target, mut value) = (vec![], vec![], vec![]);
+
+    for (i, round) in rounds.iter().enumerate().take(rounds.len().saturating_sub(1)) {
+        let stays = round.tally.iter().map(|(label, votes)| (Some(label.as_str()), *votes));
+
+        // votes staying where they were, including ballots already exhausted
+        for (candidate, votes) in stays.chain([(None, round.exhausted)]) {
+            if let (Some(from), Some(to)) = (node(i, candidate), node(i + 1, candidate)) {
+                source.push(from);
+                target.push(to);
+                value.push(votes);
+            }
+        }
+
+        for transfer in &round.transfers {
+            if let (Some(from), Some(to)) = (node(i, Some(&transfer.from)), node(i + 1, transfer.to.as_deref())) {
+                source.push(from);
+                target.push(to);
+                value.push(transfer.votes);
+            }
+        }
+    }
+
+    json!({
+        "type": "sankey",
+        "node": { "label": labels },
+        "link": { "source": source, "target": target, "value": value },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use rankit::{irv, Ballot};
+    use serde_json::json;
+
+    #[test]
+    fn links_follow_transfers() {
+        let rankings = [vec![0], vec![0], vec![0], vec![1, 0], vec![2], vec![2]];
+        let ballot = Ballot::from_rankings(vec!["a".to_owned(), "b".to_owned(), "c".to_owned()], &rankings).expect("valid rankings");
+        let (rounds, _) = irv::count(&ballot, Default::default());
+        let plot = super::plotly(&rounds, &Default::default());
+
+        assert_eq!(plot["node"]["label"][2], "b (round 1)");
+        assert_eq!(plot["node"]["label"][6], "Exhausted (round 2)");
+
+        // a and c keep their votes, and b's one vote moves on to a
+        assert_eq!(plot["link"]["source"], json!([0, 1, 3, 2]));
+        assert_eq!(plot["link"]["target"], json!([4, 5, 6, 4]));
+        assert_eq!(plot["link"]["value"], json!([3.0, 2.0, 0.0, 1.0]));
+    }
+}
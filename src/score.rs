@@ -0,0 +1,261 @@
+use alloc::{vec, vec::Vec};
+use core::cmp::Ordering;
+
+/// One candidate's scores across every ballot.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Tally<T> {
+    pub label: T,
+    /// Every score they were given added up
+    pub total: f64,
+    /// How many ballots gave them a score at all
+    pub scored: usize,
+}
+
+impl<T> Tally<T> {
+    /// The average of the scores they were given, leaving out ballots that
+    /// didn't score them
+    pub fn average(&self) -> f64 {
+        match self.scored {
+            0 => 0.0,
+            scored => self.total / scored as f64,
+        }
+    }
+}
+
+/// The automatic runoff of STAR voting, between the two highest scoring
+/// candidates.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Runoff<T> {
+    /// The two finalists, highest total first
+    pub finalists: [T; 2],
+    /// How many ballots scored each finalist higher than the other
+    pub preferred: [usize; 2],
+    /// How many ballots scored them the same (or neither)
+    pub no_preference: usize,
+    pub winner: T,
+}
+
+/// Totals every candidate's scores, highest total first. Each ballot has a
+/// score (or nothing) for every candidate, in the same order as the labels.
+/// Candidates with the same total stay in the order they were read.
+pub fn tally<T: Clone>(labels: &[T], ballots: &[Vec<Option<f64>>]) -> Vec<Tally<T>> {
+    let mut totals = vec![0.0; labels.len()];
+    let mut scored = vec![0; labels.len()];
+
+    for ballot in ballots {
+        for (candidate, score) in ballot.iter().enumerate() {
+            if let Some(score) = score {
+                totals[candidate] += score;
+                scored[candidate] += 1;
+            }
+        }
+    }
+
+    let mut tally: Vec<_> = labels
+        .iter()
+        .cloned()
+        .zip(totals)
+        .zip(scored)
+        .map(|((label, total), scored)| Tally { label, total, scored })
+        .collect();
+    tally.sort_by(|a, b| b.total.total_cmp(&a.total));
+
+    tally
+}
+
+/// Picks a winner by STAR voting (score then automatic runoff): the two
+/// candidates with the highest totals go to a runoff, which the one scored
+/// higher on more ballots wins. A ballot that didn't score a finalist counts
+/// as scoring them lowest. A tied runoff goes to the one with the higher
+/// total, or the one read first. There's no runoff with fewer than two
+/// candidates.
+pub fn star<T: Clone>(labels: &[T], ballots: &[Vec<Option<f64>>]) -> Option<Runoff<T>> {
+    let mut order: Vec<_> = (0..labels.len()).collect();
+    let totals: Vec<f64> = (0..labels.len())
+        .map(|candidate| ballots.iter().filter_map(|ballot| ballot[candidate]).sum())
+        .collect();
+    order.sort_by(|a, b| totals[*b].total_cmp(&totals[*a]));
+
+    let (first, second) = match order[..] {
+        [first, second, ..] => (first, second),
+        _ => return None,
+    };
+
+    let mut preferred = [0, 0];
+    let mut no_preference = 0;
+
+    for ballot in ballots {
+        // None is less than any score, so unscored counts as lowest
+        match ballot[first].partial_cmp(&ballot[second]) {
+            Some(Ordering::Greater) => preferred[0] += 1,
+            Some(Ordering::Less) => preferred[1] += 1,
+            _ => no_preference += 1,
+        }
+    }
+
+    let winner = match preferred[1] > preferred[0] {
+        true => second,
+        false => first,
+    };
+
+    Some(Runoff {
+        finalists: [labels[first].clone(), labels[second].clone()],
+        preferred,
+        no_preference,
+        winner: labels[winner].clone(),
+    })
+}
+
+/// Turns each ballot's scores into a ranking, highest score first, for the
+/// ranked methods. Candidates with the same score are ranked equally, and
+/// candidates without a score are left unranked.
+pub fn rankings(ballots: &[Vec<Option<f64>>]) -> Vec<Vec<Vec<usize>>> {
+    ballots
+        .iter()
+        .map(|ballot| {
+            let mut scored: Vec<_> = ballot
+                .iter()
+                .enumerate()
+                .filter_map(|(candidate, score)| score.map(|score| (candidate, score)))
+                .collect();
+            scored.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+            let mut ranking: Vec<Vec<usize>> = vec![];
+            let mut previous = None;
+
+            for (candidate, score) in scored {
+                match ranking.last_mut() {
+                    Some(group) if previous == Some(score) => group.push(candidate),
+                    _ => ranking.push(vec![candidate]),
+                }
+
+                previous = Some(score);
+            }
+
+            ranking
+        })
+        .collect()
+}
+
+/// How each candidate's scores are put on the same scale before they're
+/// counted, for scores gathered on different scales.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Normalization {
+    /// From 0 for the lowest score the candidate was given to 1 for the highest
+    MinMax,
+    /// How many standard deviations each score is from the candidate's average
+    ZScore,
+}
+
+/// What a candidate's scores were normalized by: each became
+/// `(score - center) / spread`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Scale {
+    /// The lowest score (min-max) or the average (z-score)
+    pub center: f64,
+    /// The range of scores (min-max) or their standard deviation (z-score).
+    /// If it's 0, every score the candidate was given became 0.
+    pub spread: f64,
+}
+
+/// Normalizes every candidate's scores (each column of the ballots) on its
+/// own, returning the scale each was normalized by. Missing scores stay
+/// missing.
+pub fn normalize(ballots: &mut [Vec<Option<f64>>], normalization: Normalization) -> Vec<Scale> {
+    let candidates = ballots.first().map_or(0, Vec::len);
+    let mut scales = vec![];
+
+    for candidate in 0..candidates {
+        let scores: Vec<f64> = ballots.iter().filter_map(|ballot| ballot[candidate]).collect();
+        let count = scores.len().max(1) as f64;
+
+        let scale = match normalization {
+            Normalization::MinMax => {
+                let lowest = scores.iter().copied().fold(f64::INFINITY, f64::min);
+                let highest = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+                match scores.is_empty() {
+                    true => Scale { center: 0.0, spread: 0.0 },
+                    false => Scale { center: lowest, spread: highest - lowest },
+                }
+            }
+            Normalization::ZScore => {
+                let mean = scores.iter().sum::<f64>() / count;
+                let variance = scores.iter().map(|score| (score - mean) * (score - mean)).sum::<f64>() / count;
+
+                Scale { center: mean, spread: sqrt(variance) }
+            }
+        };
+
+        for score in ballots.iter_mut().filter_map(|ballot| ballot[candidate].as_mut()) {
+            *score = match scale.spread {
+                0.0 => 0.0,
+                spread => (*score - scale.center) / spread,
+            };
+        }
+
+        scales.push(scale);
+    }
+
+    scales
+}
+
+// sqrt() needs std, so this finds it by Newton's method
+fn sqrt(x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+
+    let mut root = x.max(1.0);
+
+    loop {
+        let next = (root + x / root) / 2.0;
+
+        if next >= root {
+            return root;
+        }
+
+        root = next;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn totals_and_runoff() {
+        // b has the highest total thanks to one 5, but more ballots prefer a
+        let ballots = [
+            vec![Some(3.0), Some(2.0), Some(0.0)],
+            vec![Some(3.0), Some(2.0), None],
+            vec![Some(0.0), Some(5.0), Some(1.0)],
+        ];
+        let labels = ['a', 'b', 'c'];
+        let tally = super::tally(&labels, &ballots);
+
+        assert_eq!((tally[0].label, tally[0].total), ('b', 9.0));
+        assert_eq!((tally[2].label, tally[2].average()), ('c', 0.5));
+
+        let runoff = super::star(&labels, &ballots).expect("enough candidates");
+        assert_eq!((runoff.finalists, runoff.preferred, runoff.winner), (['b', 'a'], [1, 2], 'a'));
+
+        let rankings = super::rankings(&ballots);
+        assert_eq!(rankings[1], vec![vec![0], vec![1]]);
+        assert_eq!(super::rankings(&[vec![Some(1.0), None, Some(4.0), Some(1.0)]]), vec![vec![vec![2], vec![0, 3]]]);
+    }
+
+    #[test]
+    fn normalized_per_candidate() {
+        // a was scored from 1 to 5 and b from 0 to 10
+        let mut ballots = vec![vec![Some(1.0), Some(0.0)], vec![Some(5.0), Some(10.0)], vec![Some(3.0), None]];
+        let scales = super::normalize(&mut ballots, super::Normalization::MinMax);
+
+        assert_eq!(scales[0], super::Scale { center: 1.0, spread: 4.0 });
+        assert_eq!(ballots, vec![vec![Some(0.0), Some(0.0)], vec![Some(1.0), Some(1.0)], vec![Some(0.5), None]]);
+
+        let mut ballots = vec![vec![Some(2.0)], vec![Some(4.0)], vec![Some(4.0)], vec![Some(4.0)], vec![Some(5.0)], vec![Some(5.0)], vec![Some(7.0)], vec![Some(9.0)]];
+        let scales = super::normalize(&mut ballots, super::Normalization::ZScore);
+        assert_eq!(scales[0], super::Scale { center: 5.0, spread: 2.0 });
+        assert_eq!(ballots[0], vec![Some(-1.5)]);
+    }
+}
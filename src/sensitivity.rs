@@ -0,0 +1,225 @@
+use alloc::{format, vec, vec::Vec};
+
+use crate::rng::Rng;
+use crate::Ballot;
+
+/// How finely perturbations are drawn, as steps from no change to the most
+/// noise either way
+const STEPS: usize = 1_000_000;
+
+/// A round where another candidate came within the margin of being picked.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CloseRound<T> {
+    /// The round, indexed at 0
+    pub round: usize,
+    /// The candidate that was actually picked
+    pub picked: T,
+    /// The candidate that came close
+    pub alternative: T,
+    /// How many more first preferences the picked candidate had
+    pub margin: f64,
+    /// The order of winners if the alternative had been picked instead
+    pub order: Vec<T>,
+}
+
+/// The outcome of checking how sensitive a count was to close rounds.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Sensitivity<T> {
+    /// The order of winners that the count actually produced
+    pub order: Vec<T>,
+    pub close: Vec<CloseRound<T>>,
+}
+
+impl<T: PartialEq> Sensitivity<T> {
+    /// Whether picking any close alternative would have changed the first winner
+    pub fn fragile(&self) -> bool {
+        self.close.iter().any(|c| c.order.first() != self.order.first())
+    }
+}
+
+/// A round where candidates tied for the most first preferences, so which of
+/// them was picked came down to the tie-break rather than the ballots.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Tie<T> {
+    /// The round, indexed at 0
+    pub round: usize,
+    /// Everyone who tied, including the candidate picked
+    pub tied: Vec<T>,
+    pub picked: T,
+    /// How many of the perturbed counts had another of the tied candidates
+    /// come out ahead of the one picked
+    pub flipped: usize,
+}
+
+/// How stable a count's ties were when the ballots were perturbed a little.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TieStability<T> {
+    /// The order of winners that the count actually produced
+    pub order: Vec<T>,
+    pub ties: Vec<Tie<T>>,
+    pub trials: usize,
+    /// How many of the perturbed counts had a different first winner
+    pub winner_changed: usize,
+}
+
+/// Finds every round where candidates tied for the most first preferences,
+/// then reruns the count `trials` times with each ballot's weight scaled by a
+/// random amount of up to `noise` either way (0.01 is up to 1%). A tie that
+/// flips about as often as a coin would was decided by the tie-break alone,
+/// while one that rarely flips was only a tie by a hair.
+/// Counts without ties aren't rerun at all. Each rerun draws a value for
+/// every ballot in order, logged as "tie stability rerun N".
+pub fn tie_stability<T: Clone + PartialEq>(ballot: &Ballot<T>, noise: f64, trials: usize, rng: &mut Rng) -> TieStability<T> {
+    let mut current = ballot.clone();
+    let mut ties = vec![];
+    let mut order = vec![];
+
+    for round in 0..ballot.count() {
+        let tier = current.tier();
+        let Some(picked) = Ballot::<T>::pick(&tier) else {
+            break;
+        };
+        let tied: Vec<_> = (0..tier.len()).filter(|i| tier[*i] == tier[picked]).map(|i| current.labels()[i].clone()).collect();
+
+        if tied.len() > 1 {
+            ties.push(Tie {
+                round,
+                tied,
+                picked: current.labels()[picked].clone(),
+                flipped: 0,
+            });
+        }
+
+        order.push(current.take(picked));
+    }
+
+    let mut winner_changed = 0;
+    let trials = if ties.is_empty() { 0 } else { trials };
+
+    for trial in 0..trials {
+        rng.set_context(format!("tie stability rerun {}", trial + 1));
+
+        // one draw per ballot, in order, from -1 to 1 in millionths
+        let weights = ballot
+            .weights()
+            .iter()
+            .map(|weight| {
+                let scale = (rng.below(2 * STEPS + 1) as f64 - STEPS as f64) / STEPS as f64;
+
+                weight * (1.0 + noise * scale)
+            })
+            .collect();
+        let perturbed = ballot.clone().with_weights(weights).expect("a weight for every ballot");
+        let rerun: Vec<_> = perturbed.runoff().map(|(winner, _, _)| winner).collect();
+
+        if rerun.first() != order.first() {
+            winner_changed += 1;
+        }
+
+        for tie in &mut ties {
+            // whoever of the tied candidates came out first won the tie
+            if rerun.iter().find(|winner| tie.tied.contains(winner)) != Some(&tie.picked) {
+                tie.flipped += 1;
+            }
+        }
+    }
+
+    TieStability {
+        order,
+        ties,
+        trials,
+        winner_changed,
+    }
+}
+
+/// Finds every round where a candidate came within `margin` votes of the one
+/// picked, and reruns the rest of the count with that candidate picked instead.
+pub fn close_rounds<T: Clone>(ballot: &Ballot<T>, margin: f64) -> Sensitivity<T> {
+    let mut current = ballot.clone();
+    let mut close = vec![];
+    let mut taken = vec![];
+
+    for round in 0..ballot.count() {
+        let tier = current.tier();
+        let Some(picked) = Ballot::<T>::pick(&tier) else {
+            break;
+        };
+
+        for alternative in (0..tier.len()).filter(|i| *i != picked) {
+            let behind = tier[picked] - tier[alternative];
+
+            if behind > margin {
+                continue;
+            }
+
+            let mut rerun = current.clone();
+            let first = rerun.take(alternative);
+            let order = taken
+                .iter()
+                .cloned()
+                .chain(core::iter::once(first))
+                .chain(rerun.runoff().map(|(winner, _, _)| winner))
+                .collect();
+
+            close.push(CloseRound {
+                round,
+                picked: current.labels()[picked].clone(),
+                alternative: current.labels()[alternative].clone(),
+                margin: behind,
+                order,
+            });
+        }
+
+        taken.push(current.take(picked));
+    }
+
+    Sensitivity { order: taken, close }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rng::Rng;
+    use crate::Ballot;
+
+    #[test]
+    fn close_rounds() {
+        let mut rankings = vec![vec![0, 2, 1]; 4];
+        rankings.extend(vec![vec![1, 0, 2]; 3]);
+        rankings.extend(vec![vec![2, 1, 0]; 2]);
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c'], &rankings).expect("valid rankings");
+
+        // only b is within a vote of a, and picking b first takes a's win
+        let sensitivity = super::close_rounds(&ballot, 1.0);
+        let close: Vec<_> = sensitivity.close.iter().map(|c| (c.round, c.picked, c.alternative, c.margin)).collect();
+        assert_eq!(sensitivity.order, vec!['a', 'c', 'b']);
+        assert_eq!(close, vec![(0, 'a', 'b', 1.0)]);
+        assert_eq!(sensitivity.close[0].order, vec!['b', 'a', 'c']);
+        assert!(sensitivity.fragile());
+
+        // c was 3 behind once a won, which isn't close under a margin of 2
+        assert_eq!(super::close_rounds(&ballot, 2.0).close.len(), 2);
+    }
+
+    #[test]
+    fn tie_stability() {
+        // a and b tie exactly, so either could come out ahead once perturbed
+        let rankings = [vec![0, 1], vec![1, 0]];
+        let ballot = Ballot::from_rankings(vec!['a', 'b'], &rankings).expect("valid rankings");
+        let mut rng = Rng::new(1);
+        let tied = super::tie_stability(&ballot, 0.01, 200, &mut rng);
+
+        assert_eq!((tied.ties[0].round, &tied.ties[0].tied), (0, &vec!['a', 'b']));
+        assert!(tied.ties[0].flipped > 50 && tied.ties[0].flipped < 150);
+        assert_eq!(tied.winner_changed, tied.ties[0].flipped);
+
+        // a draw for each ballot in each rerun, all logged
+        assert_eq!(rng.draws().len(), 400);
+        assert_eq!(rng.draws()[2].context, "tie stability rerun 2");
+
+        // without a tie, there's nothing to rerun
+        let ballot = ballot.with_weights(vec![101.0, 100.0]).expect("a weight for every ballot");
+        let untied = super::tie_stability(&ballot, 0.01, 200, &mut Rng::new(1));
+        assert!(untied.ties.is_empty());
+        assert_eq!(untied.trials, 0);
+    }
+}
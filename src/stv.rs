@@ -0,0 +1,556 @@
+use alloc::{format, vec, vec::Vec};
+
+use crate::irv;
+use crate::rng::Rng;
+use crate::rounding::Rounding;
+use crate::Ballot;
+
+/// One round of a single transferable vote count.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Round<T> {
+    /// Every candidate still in the count with their votes, most first
+    pub tally: Vec<(T, f64)>,
+    /// The votes of ballots that don't rank anyone still in the count
+    pub exhausted: f64,
+    /// Who was elected this round. There's more than one only when the
+    /// candidates left fill the remaining seats without reaching the quota.
+    pub elected: Vec<T>,
+    /// What each of the elected candidate's ballots was passed on at, if they
+    /// reached the quota. With their surplus drawn at random, it's the share
+    /// of their votes that was passed on.
+    pub transfer_value: Option<f64>,
+    /// Whether the surplus was passed on as whole ballots drawn at random,
+    /// rather than every ballot at the transfer value
+    pub drawn: bool,
+    /// Who was eliminated, if nobody reached the quota
+    pub eliminated: Option<T>,
+    /// Whether more than one candidate had the fewest votes, in which case
+    /// the first of them in the input was eliminated
+    pub tied: bool,
+}
+
+/// A whole count, from the quota to everyone elected.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Count<T> {
+    /// The Droop quota: the fewest votes that only `seats` candidates can reach
+    pub quota: f64,
+    pub rounds: Vec<Round<T>>,
+    /// Everyone elected, in the order they were
+    pub elected: Vec<T>,
+    /// The candidates the constraints kept from being elected, in the order
+    /// they were skipped
+    pub skipped: Vec<Skipped<T>>,
+}
+
+/// A rule about who can be elected together, as some party and union rules
+/// have, with candidates given by their index in the ballot's labels.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constraint {
+    /// No more than this many of these candidates can be elected, like "at
+    /// most one of X and Y"
+    AtMost(usize, Vec<usize>),
+    /// At least this many of these candidates have to be elected (or as many
+    /// as are left in the count), like a quota of seats for a region
+    AtLeast(usize, Vec<usize>),
+}
+
+/// A candidate taken out of the count because of a constraint: their ballots
+/// went on to their next preferences as if they'd been eliminated.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Skipped<T> {
+    pub candidate: T,
+    /// The round (from 1) they were skipped in
+    pub round: usize,
+    /// The constraint (indexed at 0) that skipped them
+    pub constraint: usize,
+}
+
+/// How one seat was filled, for following a count seat by seat rather than
+/// round by round.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Seat<T> {
+    pub candidate: T,
+    /// The round (from 1) they were elected in
+    pub round: usize,
+    /// Their votes in that round
+    pub votes: f64,
+    /// Whether they reached the quota, rather than being elected as one of
+    /// the last candidates left
+    pub reached_quota: bool,
+    /// The votes they picked up from other candidates' ballots before they
+    /// were elected, in the order they did
+    pub gained: Vec<Gain<T>>,
+}
+
+/// Votes that one candidate passed on to another.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Gain<T> {
+    pub from: T,
+    pub votes: f64,
+    /// Whether it was the surplus of an elected candidate, rather than the
+    /// ballots of an eliminated one
+    pub surplus: bool,
+}
+
+/// Goes through a count seat by seat, in the order they were filled. Only
+/// one candidate's ballots are passed on each round, so whatever a candidate
+/// gained from one round to the next came from them.
+pub fn seats<T: Clone + PartialEq>(count: &Count<T>) -> Vec<Seat<T>> {
+    let mut seats = vec![];
+    let votes = |round: &Round<T>, candidate: &T| round.tally.iter().find(|(label, _)| label == candidate).map(|(_, votes)| *votes);
+
+    for (i, round) in count.rounds.iter().enumerate() {
+        for candidate in &round.elected {
+            let gained = count.rounds[..i]
+                .iter()
+                .zip(&count.rounds[1..=i])
+                .filter_map(|(before, after)| {
+                    let from = before.eliminated.clone().or_else(|| before.elected.first().cloned())?;
+                    let gain = votes(after, candidate)? - votes(before, candidate)?;
+
+                    (gain > 0.0).then_some(Gain {
+                        from,
+                        votes: gain,
+                        surplus: before.eliminated.is_none(),
+                    })
+                })
+                .collect();
+
+            seats.push(Seat {
+                candidate: candidate.clone(),
+                round: i + 1,
+                votes: votes(round, candidate).unwrap_or_default(),
+                reached_quota: round.transfer_value.is_some(),
+                gained,
+            });
+        }
+    }
+
+    seats
+}
+
+/// How a group of candidates (like a party) fared in a count, for comparing
+/// the seats they won with their share of the vote.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Group<G> {
+    pub group: G,
+    /// Their candidates' first preferences
+    pub first_preferences: f64,
+    /// Their share of the first preferences for anyone, from 0 to 1
+    pub share: f64,
+    pub seats: usize,
+}
+
+/// Sums up a count by the group each candidate is in, in the order the
+/// groups' candidates first appear in the first round's tally.
+pub fn groups<T: PartialEq, G: PartialEq>(count: &Count<T>, group: impl Fn(&T) -> G) -> Vec<Group<G>> {
+    let Some(first) = count.rounds.first() else {
+        return vec![];
+    };
+    let total: f64 = first.tally.iter().map(|(_, votes)| votes).sum();
+    let mut groups: Vec<Group<G>> = vec![];
+
+    for (candidate, votes) in &first.tally {
+        let seats = usize::from(count.elected.contains(candidate));
+        let share = match total {
+            0.0 => 0.0,
+            total => votes / total,
+        };
+
+        match groups.iter_mut().find(|existing| existing.group == group(candidate)) {
+            Some(existing) => {
+                existing.first_preferences += votes;
+                existing.share += share;
+                existing.seats += seats;
+            }
+            None => groups.push(Group {
+                group: group(candidate),
+                first_preferences: *votes,
+                share,
+                seats,
+            }),
+        }
+    }
+
+    groups
+}
+
+/// How an elected candidate's surplus is passed on.
+pub enum Surplus<'a> {
+    /// Every ballot counting for them, at a fraction of its weight (the
+    /// Gregory method)
+    Fractional,
+    /// As many of those ballots as the surplus, drawn at random and passed on
+    /// whole, while the rest stay with them (the Hare method, still in some
+    /// constitutions). The draws are logged by the `Rng`.
+    RandomDraw(&'a mut Rng),
+}
+
+/// Fills `seats` seats by single transferable vote. Each round, the candidate
+/// with the most votes is elected if they reach the Droop quota, and every
+/// ballot counting for them is passed on to its next preference at a fraction
+/// of its weight, so that only their surplus transfers (the Gregory method).
+/// Otherwise the candidate with the fewest votes is eliminated, and their
+/// ballots are passed on at full weight.
+pub fn count<T: Clone>(ballot: &Ballot<T>, seats: usize, rounding: Rounding) -> Count<T> {
+    count_by(ballot, seats, rounding, Surplus::Fractional)
+}
+
+/// Like `count`, but passing surpluses on as `surplus` says.
+pub fn count_by<T: Clone>(ballot: &Ballot<T>, seats: usize, rounding: Rounding, surplus: Surplus) -> Count<T> {
+    run(ballot, seats, rounding, surplus, &[], |_, _| {})
+}
+
+/// Like `count_by`, but keeping to the `constraints`. Once a group has as
+/// many seats as it's allowed, the rest of it is skipped, and once the seats
+/// left are only enough for a group's minimum, everyone outside it is
+/// skipped. The candidates left to fill the last seats are elected most
+/// votes first, skipping any a constraint doesn't allow.
+pub fn count_constrained<T: Clone>(ballot: &Ballot<T>, seats: usize, rounding: Rounding, surplus: Surplus, constraints: &[Constraint]) -> Count<T> {
+    run(ballot, seats, rounding, surplus, constraints, |_, _| {})
+}
+
+/// Fills a casual vacancy by countback, as in Tasmania and the ACT: the ballots
+/// that elected `vacating` (the part of each that made up their quota, rather
+/// than their surplus) are counted again by instant runoff among the
+/// candidates `standing`, until one of them has a majority of that quota.
+/// Nothing is counted if `vacating` wasn't elected by reaching the quota.
+pub fn countback<T: Clone + PartialEq>(
+    ballot: &Ballot<T>,
+    seats: usize,
+    rounding: Rounding,
+    surplus: Surplus,
+    constraints: &[Constraint],
+    vacating: &T,
+    standing: &[T],
+) -> Option<(Vec<irv::Round<T>>, Option<T>)> {
+    let mut papers = None;
+
+    run(ballot, seats, rounding, surplus, constraints, |elected, kept| {
+        if elected == vacating {
+            papers = Some(kept);
+        }
+    });
+
+    let mut recount = ballot.clone().with_weights(papers?).expect("a weight for every ballot");
+
+    for i in (0..recount.count()).rev() {
+        if !standing.contains(&recount.labels()[i]) {
+            recount.take(i);
+        }
+    }
+
+    Some(irv::count(&recount, rounding))
+}
+
+/// The count itself, telling `quota_from` who was elected by reaching the
+/// quota and how much of each ballot stayed with them when they were.
+fn run<T: Clone>(
+    ballot: &Ballot<T>,
+    seats: usize,
+    rounding: Rounding,
+    mut surplus: Surplus,
+    constraints: &[Constraint],
+    mut quota_from: impl FnMut(&T, Vec<f64>),
+) -> Count<T> {
+    let total: f64 = ballot.weights().iter().sum();
+    // floor() needs std, and the total can't be negative
+    let quota = (total / (seats + 1) as f64) as u64 as f64 + 1.0;
+
+    let mut current = ballot.clone();
+    let mut rounds = vec![];
+    let mut elected = vec![];
+    let mut skipped = vec![];
+    // each candidate still in the count's index in the ballot, and the
+    // indexes of those elected, for the constraints
+    let mut indexes: Vec<_> = (0..ballot.count()).collect();
+    let mut elected_indexes = vec![];
+
+    while elected.len() < seats && current.count() > 0 {
+        for (i, constraint) in constraints.iter().enumerate() {
+            let in_group = |group: &[usize], candidate: &usize| group.contains(candidate);
+            let skip: Vec<_> = match constraint {
+                Constraint::AtMost(most, group) if elected_indexes.iter().filter(|candidate| in_group(group, candidate)).count() >= *most => {
+                    (0..indexes.len()).filter(|at| in_group(group, &indexes[*at])).collect()
+                }
+                Constraint::AtLeast(least, group) => {
+                    let short = least.saturating_sub(elected_indexes.iter().filter(|candidate| in_group(group, candidate)).count());
+                    let left = indexes.iter().filter(|candidate| in_group(group, candidate)).count();
+
+                    match seats - elected.len() <= short.min(left) {
+                        true => (0..indexes.len()).filter(|at| !in_group(group, &indexes[*at])).collect(),
+                        false => vec![],
+                    }
+                }
+                Constraint::AtMost(..) => vec![],
+            };
+
+            for at in skip.into_iter().rev() {
+                indexes.remove(at);
+                skipped.push(Skipped {
+                    candidate: current.take(at),
+                    round: rounds.len() + 1,
+                    constraint: i,
+                });
+            }
+        }
+
+        if current.count() == 0 {
+            break;
+        }
+
+        let tier: Vec<_> = current.tier().into_iter().map(|votes| rounding.apply(votes)).collect();
+        let exhausted = current
+            .ballots()
+            .zip(current.weights())
+            .filter(|(row, _)| Ballot::<T>::first_preference(row).is_none())
+            .fold(0.0, |total, (_, weight)| total + weight);
+
+        let mut tally: Vec<_> = current.labels().iter().cloned().zip(tier.iter().copied()).collect();
+        tally.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        let mut round = Round {
+            tally,
+            exhausted: rounding.apply(exhausted),
+            elected: vec![],
+            transfer_value: None,
+            drawn: false,
+            eliminated: None,
+            tied: false,
+        };
+
+        let Some(highest) = Ballot::<T>::pick(&tier) else {
+            break;
+        };
+        let lowest = (0..tier.len()).min_by(|a, b| tier[*a].total_cmp(&tier[*b])).unwrap_or(highest);
+
+        if elected.len() + current.count() <= seats {
+            // everyone left gets a seat, whether or not they reach the quota,
+            // unless that would go over a group's maximum
+            let mut order: Vec<_> = (0..tier.len()).collect();
+            order.sort_by(|a, b| tier[*b].total_cmp(&tier[*a]));
+
+            for at in order {
+                let over = constraints.iter().position(|constraint| match constraint {
+                    Constraint::AtMost(most, group) => {
+                        group.contains(&indexes[at]) && elected_indexes.iter().filter(|candidate| group.contains(candidate)).count() >= *most
+                    }
+                    Constraint::AtLeast(..) => false,
+                });
+                let candidate = current.labels()[at].clone();
+
+                match over {
+                    Some(constraint) => skipped.push(Skipped { candidate, round: rounds.len() + 1, constraint }),
+                    None => {
+                        elected_indexes.push(indexes[at]);
+                        round.elected.push(candidate);
+                    }
+                }
+            }
+
+            elected.extend(round.elected.iter().cloned());
+            rounds.push(round);
+
+            break;
+        }
+
+        if tier[highest] >= quota {
+            let transfer_value = (tier[highest] - quota) / tier[highest];
+            let weights = match &mut surplus {
+                Surplus::Fractional => current
+                    .ballots()
+                    .zip(current.weights())
+                    .map(|(row, weight)| match row[highest] {
+                        0 => weight * transfer_value,
+                        _ => *weight,
+                    })
+                    .collect(),
+                Surplus::RandomDraw(rng) => {
+                    rng.set_context(format!("surplus in round {}", rounds.len() + 1));
+                    draw_surplus(&current, highest, tier[highest] - quota, rng)
+                }
+            };
+
+            let kept = current.weights().iter().zip(&weights).map(|(before, after)| before - after).collect();
+            current = current.with_weights(weights).expect("a weight for every ballot");
+
+            let winner = current.take(highest);
+            elected_indexes.push(indexes.remove(highest));
+            quota_from(&winner, kept);
+
+            round.elected.push(winner.clone());
+            round.transfer_value = Some(transfer_value);
+            round.drawn = matches!(surplus, Surplus::RandomDraw(_));
+            elected.push(winner);
+        } else {
+            round.tied = tier.iter().filter(|votes| **votes == tier[lowest]).count() > 1;
+            round.eliminated = Some(current.take(lowest));
+            indexes.remove(lowest);
+        }
+
+        rounds.push(round);
+    }
+
+    Count {
+        quota,
+        rounds,
+        elected,
+        skipped,
+    }
+}
+
+/// The weights after drawing whole ballots counting for `candidate` at random
+/// until `surplus` votes have been drawn: those keep their weight to be passed
+/// on, and the rest stay behind with the candidate. The last ballot drawn only
+/// goes on with what's left of the surplus, in case ballots have weights.
+fn draw_surplus<T: Clone>(ballot: &Ballot<T>, candidate: usize, surplus: f64, rng: &mut Rng) -> Vec<f64> {
+    let mut theirs: Vec<_> = ballot.ballots().enumerate().filter(|(_, row)| row[candidate] == 0).map(|(i, _)| i).collect();
+    let mut weights: Vec<_> = ballot.weights().to_vec();
+    let mut left = surplus;
+
+    for &i in &theirs {
+        weights[i] = 0.0;
+    }
+
+    // a Fisher-Yates shuffle, stopped as soon as enough has been drawn
+    for drawn in 0..theirs.len() {
+        if left <= 0.0 {
+            break;
+        }
+
+        let pick = drawn + rng.below(theirs.len() - drawn);
+        theirs.swap(drawn, pick);
+
+        let i = theirs[drawn];
+        weights[i] = ballot.weights()[i].min(left);
+        left -= weights[i];
+    }
+
+    weights
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Ballot;
+
+    #[test]
+    fn surplus_transfers() {
+        let mut rankings = vec![vec![0, 1]; 6];
+        rankings.extend(vec![vec![2]; 2]);
+        rankings.extend(vec![vec![3, 2]; 1]);
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c', 'd'], &rankings).expect("valid rankings");
+        let count = super::count(&ballot, 2, Default::default());
+
+        // quota of 4, so a's surplus of 2 goes to b at a third of a vote each
+        assert_eq!(count.quota, 4.0);
+        assert_eq!(count.rounds[0].transfer_value, Some(2.0 / 6.0));
+        assert_eq!(count.rounds[1].eliminated, Some('d'));
+        assert_eq!(count.elected, vec!['a', 'c']);
+    }
+
+    #[test]
+    fn seat_by_seat() {
+        let mut rankings = vec![vec![0, 1]; 8];
+        rankings.extend(vec![vec![1]; 2]);
+        rankings.extend(vec![vec![2]; 2]);
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c'], &rankings).expect("valid rankings");
+        let seats = super::seats(&super::count(&ballot, 2, Default::default()));
+
+        // a's surplus of 3 takes b up to the quota of 5
+        assert_eq!((seats[0].candidate, seats[0].round, seats[0].votes), ('a', 1, 8.0));
+        assert!(seats[0].gained.is_empty());
+        assert_eq!((seats[1].candidate, seats[1].round, seats[1].reached_quota), ('b', 2, true));
+        assert_eq!(seats[1].gained.iter().map(|gain| (gain.from, gain.votes, gain.surplus)).collect::<Vec<_>>(), vec![('a', 3.0, true)]);
+
+        // c only got there with d's ballot, after a's surplus went to b
+        let mut rankings = vec![vec![0, 1]; 6];
+        rankings.extend(vec![vec![2]; 2]);
+        rankings.extend(vec![vec![3, 2]; 1]);
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c', 'd'], &rankings).expect("valid rankings");
+        let seats = super::seats(&super::count(&ballot, 2, Default::default()));
+        assert_eq!((seats[1].candidate, seats[1].round, seats[1].reached_quota), ('c', 4, false));
+        assert_eq!(seats[1].gained.iter().map(|gain| (gain.from, gain.votes, gain.surplus)).collect::<Vec<_>>(), vec![('d', 1.0, false)]);
+    }
+
+    #[test]
+    fn surplus_drawn_at_random() {
+        let mut rankings = vec![vec![0, 1]; 4];
+        rankings.extend(vec![vec![0, 2]; 4]);
+        rankings.extend(vec![vec![1]; 2]);
+        rankings.extend(vec![vec![2]; 2]);
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c'], &rankings).expect("valid rankings");
+        let mut rng = crate::rng::Rng::new(7);
+        let count = super::count_by(&ballot, 2, Default::default(), super::Surplus::RandomDraw(&mut rng));
+
+        // a's surplus of 3 goes on as 3 whole ballots, split between b and c
+        // however they were drawn
+        let second = &count.rounds[1].tally;
+        assert!(count.rounds[0].drawn);
+        assert_eq!(second.iter().map(|(_, votes)| votes).sum::<f64>(), 7.0);
+        assert!(second.iter().all(|(_, votes)| votes.fract() == 0.0));
+        assert_eq!(rng.draws().len(), 3);
+        assert_eq!(rng.draws()[0].context, "surplus in round 1");
+
+        // the same seed draws the same ballots
+        let again = super::count_by(&ballot, 2, Default::default(), super::Surplus::RandomDraw(&mut crate::rng::Rng::new(7)));
+        assert_eq!(again.elected, count.elected);
+        assert_eq!(again.rounds[1].tally, count.rounds[1].tally);
+    }
+
+    #[test]
+    fn fared_by_group() {
+        let mut rankings = vec![vec![0, 1]; 6];
+        rankings.extend(vec![vec![2]; 2]);
+        rankings.extend(vec![vec![3, 2]; 1]);
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c', 'd'], &rankings).expect("valid rankings");
+        let count = super::count(&ballot, 2, Default::default());
+        let groups = super::groups(&count, |candidate| matches!(candidate, 'a' | 'b'));
+
+        // a and b's party had two thirds of the vote and half the seats
+        let fared: Vec<_> = groups.iter().map(|group| (group.group, group.first_preferences, group.seats)).collect();
+        assert_eq!(fared, [(true, 6.0, 1), (false, 3.0, 1)]);
+        assert_eq!(groups[0].share, 6.0 / 9.0);
+    }
+
+    #[test]
+    fn countback() {
+        let mut rankings = vec![vec![0, 1]; 3];
+        rankings.extend(vec![vec![0, 2]; 5]);
+        rankings.extend(vec![vec![1]; 3]);
+        rankings.extend(vec![vec![3]; 4]);
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c', 'd'], &rankings).expect("valid rankings");
+        let count = super::count(&ballot, 2, Default::default());
+        assert_eq!(count.elected, vec!['a', 'd']);
+
+        // a's quota of 6 was three quarters of each of their 8 ballots, and
+        // c was next on more of them than b, who lost out to d in the count
+        let (rounds, winner) = super::countback(&ballot, 2, Default::default(), super::Surplus::Fractional, &[], &'a', &['b', 'c']).expect("a reached the quota");
+        assert_eq!(rounds[0].tally, vec![('c', 3.75), ('b', 2.25)]);
+        assert_eq!(winner, Some('c'));
+
+        assert!(super::countback(&ballot, 2, Default::default(), super::Surplus::Fractional, &[], &'c', &['b']).is_none());
+    }
+
+    #[test]
+    fn constraints() {
+        let mut rankings = vec![vec![0, 1]; 8];
+        rankings.extend(vec![vec![1, 2]; 5]);
+        rankings.extend(vec![vec![2]; 3]);
+        rankings.extend(vec![vec![3]; 4]);
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c', 'd'], &rankings).expect("valid rankings");
+        assert_eq!(super::count(&ballot, 2, Default::default()).elected, vec!['a', 'b']);
+
+        // at most one of a and b, so b is skipped once a is elected and their
+        // ballots go on to c
+        let at_most = [super::Constraint::AtMost(1, vec![0, 1])];
+        let count = super::count_constrained(&ballot, 2, Default::default(), super::Surplus::Fractional, &at_most);
+        assert_eq!(count.elected, vec!['a', 'c']);
+        assert_eq!(count.skipped.iter().map(|skipped| (skipped.candidate, skipped.round, skipped.constraint)).collect::<Vec<_>>(), [('b', 2, 0)]);
+
+        // d has to be elected, so once there's a seat left it's theirs
+        let at_least = [super::Constraint::AtLeast(1, vec![3])];
+        let count = super::count_constrained(&ballot, 2, Default::default(), super::Surplus::Fractional, &at_least);
+        assert_eq!(count.elected, vec!['a', 'd']);
+        assert_eq!(count.skipped.len(), 2);
+    }
+}
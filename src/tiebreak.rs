@@ -0,0 +1,118 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Map, Value};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::Ballot;
+use rankit::rounding::Rounding;
+
+/// A tie for the most votes that was handed to the tie-break command.
+pub struct TieBreak {
+    /// The round, indexed at 1
+    pub round: usize,
+    pub tied: Vec<String>,
+    pub picked: String,
+}
+
+/// The runoff, except that when candidates tie for the most votes the shell
+/// command `command` picks between them instead of candidate order deciding.
+///
+/// The command gets the round, the tied candidates and every remaining
+/// candidate's votes as JSON on stdin, and has to print the label of the
+/// candidate it picks.
+pub fn runoff(
+    mut ballot: Ballot<String>,
+    command: &str,
+    rounding: Rounding,
+) -> Result<(Vec<crate::rcvis::Round>, Vec<TieBreak>)> {
+    let mut rounds = vec![];
+    let mut ties = vec![];
+
+    for round in 1..=ballot.count() {
+        let mut tier: Vec<_> = ballot.tier().into_iter().map(|votes| rounding.apply(votes)).collect();
+        let most = tier.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let tied: Vec<_> = (0..tier.len()).filter(|i| tier[*i] == most).collect();
+
+        let winner_index = match tied[..] {
+            [] => break,
+            [only] => only,
+            _ => {
+                let labels = ballot.labels();
+                let tied: Vec<_> = tied.iter().map(|i| labels[*i].clone()).collect();
+                let tally: Map<_, _> = labels
+                    .iter()
+                    .zip(&tier)
+                    .map(|(label, votes)| (label.clone(), json!(votes)))
+                    .collect();
+                let input = json!({ "round": round, "tied": tied, "tally": tally });
+
+                let picked = ask(command, &input)
+                    .with_context(|| format!("couldn't break the tie in round {round}"))?;
+                let index = labels
+                    .iter()
+                    .position(|label| *label == picked)
+                    .filter(|_| tied.contains(&picked))
+                    .ok_or_else(|| anyhow!("the tie-break command picked {picked:?}, which isn't tied in round {round}"))?;
+
+                ties.push(TieBreak { round, tied, picked });
+
+                index
+            }
+        };
+
+        let winner_label = ballot.take(winner_index);
+        let winner_count = tier.remove(winner_index);
+        let data: Vec<_> = ballot.labels().iter().cloned().zip(tier).collect();
+
+        rounds.push((winner_label, winner_count, data));
+    }
+
+    Ok((rounds, ties))
+}
+
+/// Runs the command through the shell with `input` on stdin, giving the first
+/// line it prints.
+fn ask(command: &str, input: &Value) -> Result<String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("couldn't run {command:?}"))?;
+
+    // a command that decides without reading stdin may exit before it's all written
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = writeln!(stdin, "{input}");
+    }
+
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("couldn't run {command:?}"))?;
+
+    if !output.status.success() {
+        return Err(anyhow!("{command:?} failed ({})", output.status));
+    }
+
+    let stdout = String::from_utf8(output.stdout).context("tie-break command printed invalid utf-8")?;
+
+    Ok(stdout.lines().next().unwrap_or_default().trim().to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Ballot;
+
+    #[test]
+    fn command_breaks_ties() {
+        let labels = vec![String::from("a"), String::from("b"), String::from("c")];
+        let ballot = Ballot::new(labels, vec![0, 1, 2, 1, 0, 2, 2, 1, 0, 2, 0, 1]).expect("valid ballot");
+
+        let (rounds, ties) = super::runoff(ballot, "cat > /dev/null; echo c", Default::default()).expect("command runs");
+        let winners: Vec<_> = rounds.iter().map(|(winner, _, _)| winner.as_str()).collect();
+
+        assert_eq!(winners, vec!["b", "c", "a"]);
+        assert_eq!(ties.len(), 1);
+        assert_eq!((ties[0].round, ties[0].picked.as_str()), (2, "c"));
+    }
+}
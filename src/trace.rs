@@ -0,0 +1,43 @@
+use alloc::vec::Vec;
+
+use crate::{Ballot, UNRANKED};
+
+/// Follows one ballot (by index) through a count, given the candidates still
+/// in the count in each round. Each round gives the candidates the ballot
+/// counted for, which is more than one only if it ranked them equally, or
+/// nobody once it's exhausted.
+pub fn trace<T: Clone + PartialEq>(ballot: &Ballot<T>, index: usize, rounds: &[Vec<T>]) -> Vec<Vec<T>> {
+    let row = ballot.row(index);
+    let ranked: Vec<_> = (0..row.len()).filter(|c| row[*c] != UNRANKED).collect();
+
+    rounds
+        .iter()
+        .map(|round| {
+            let standing: Vec<_> = ranked
+                .iter()
+                .copied()
+                .filter(|c| round.contains(&ballot.labels()[*c]))
+                .collect();
+            let top = standing.iter().map(|c| row[*c]).min();
+
+            standing
+                .into_iter()
+                .filter(|c| Some(row[*c]) == top)
+                .map(|c| ballot.labels()[c].clone())
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Ballot;
+
+    #[test]
+    fn moves_down_then_exhausts() {
+        let ballot = Ballot::from_rankings(vec!['a', 'b', 'c'], &[vec![1, 0]]).expect("valid rankings");
+        let rounds = vec![vec!['a', 'b', 'c'], vec!['a', 'c'], vec!['c']];
+
+        assert_eq!(super::trace(&ballot, 0, &rounds), vec![vec!['b'], vec!['a'], vec![]]);
+    }
+}
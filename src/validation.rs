@@ -0,0 +1,59 @@
+use csv::StringRecord;
+use serde_json::{json, Value};
+
+use rankit::inspect::{self, Column, Kind};
+
+/// What was found checking a CSV file before tallying it, as JSON, so that
+/// data problems can be tracked across files or elections without scraping
+/// warnings out of the report. `problem` is why the rank columns can't be
+/// counted, if they can't.
+pub fn summary(
+    columns: &[Column],
+    records: &[StringRecord],
+    start: usize,
+    count: usize,
+    indexed_at: usize,
+    problem: Option<String>,
+    warnings: &[String],
+) -> Value {
+    let issues = inspect::rank_issues(records, start, count, indexed_at);
+    let selected = start..start + count;
+
+    let columns: Vec<_> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let (kind, min, max) = match column.kind {
+                Kind::Empty => ("empty", None, None),
+                Kind::Integer { min, max } => ("integer", Some(min), Some(max)),
+                Kind::Number => ("number", None, None),
+                Kind::Text => ("text", None, None),
+            };
+
+            json!({
+                "index": i,
+                "name": column.name,
+                "ranks": selected.contains(&i),
+                "kind": kind,
+                "min": min,
+                "max": max,
+                "blank": column.blank,
+                "distinct": column.distinct.len(),
+            })
+        })
+        .collect();
+
+    json!({
+        "records": records.len(),
+        "usable": problem.is_none(),
+        "problem": problem,
+        "issues": {
+            "rank_problems": usize::from(problem.is_some()),
+            "suspicious_columns": warnings.len(),
+            "equal_ranks": issues.equal,
+            "skipped_ranks": issues.skipped,
+        },
+        "warnings": warnings,
+        "columns": columns,
+    })
+}
@@ -0,0 +1,165 @@
+use anyhow::{anyhow, Context, Result};
+use std::path::Path;
+
+/// A group of respondents sharing a value in the strata column.
+pub struct Stratum {
+    pub value: String,
+    /// How many ballots were in the stratum
+    pub ballots: usize,
+    /// The share of the population the stratum should make up, out of 1
+    pub target: f64,
+    /// How much each ballot in the stratum counts for
+    pub weight: f64,
+}
+
+/// Reads a CSV file (with headers) whose first column contains strata values
+/// and whose second column contains each stratum's size in the population.
+/// The sizes can be counts or shares; they're scaled to add up to 1.
+pub fn read_targets(path: &Path) -> Result<Vec<(String, f64)>> {
+    let mut csv_reader = csv::Reader::from_path(path)
+        .with_context(|| format!("couldn't open strata targets file {}", path.display()))?;
+    let mut targets = vec![];
+
+    for (i, r) in csv_reader.records().enumerate() {
+        let row = r.with_context(|| format!("bad strata targets record {i}"))?;
+
+        let (Some(value), Some(size)) = (row.get(0), row.get(1)) else {
+            return Err(anyhow!("strata targets record {i} needs a value and a size"));
+        };
+        let size: f64 = size
+            .trim()
+            .parse()
+            .with_context(|| format!("invalid size, strata targets record {i}"))?;
+
+        if !size.is_finite() || size < 0.0 {
+            return Err(anyhow!("invalid size, strata targets record {i} (must be 0 or more)"));
+        }
+
+        targets.push((value.to_owned(), size));
+    }
+
+    let total: f64 = targets.iter().map(|(_, size)| size).sum();
+
+    if total <= 0.0 {
+        return Err(anyhow!("strata targets add up to nothing"));
+    }
+
+    Ok(targets
+        .into_iter()
+        .map(|(value, size)| (value, size / total))
+        .collect())
+}
+
+/// Post-stratification: weights each ballot so that every stratum makes up
+/// its target share of the tally, whatever share of the responses it was.
+/// Returns the strata and a weight for each ballot.
+pub fn weights(strata: &[String], targets: &[(String, f64)]) -> Result<(Vec<Stratum>, Vec<f64>)> {
+    let mut groups: Vec<_> = targets
+        .iter()
+        .map(|(value, target)| Stratum {
+            value: value.clone(),
+            ballots: 0,
+            target: *target,
+            weight: 0.0,
+        })
+        .collect();
+
+    let mut membership = Vec::with_capacity(strata.len());
+
+    for (i, value) in strata.iter().enumerate() {
+        let Some(group) = groups.iter().position(|g| g.value == *value) else {
+            return Err(anyhow!("record {i} is in stratum {value:?}, which has no target"));
+        };
+
+        groups[group].ballots += 1;
+        membership.push(group);
+    }
+
+    let total = strata.len() as f64;
+
+    for group in groups.iter_mut() {
+        if group.ballots == 0 {
+            if group.target > 0.0 {
+                let value = &group.value;
+                return Err(anyhow!("stratum {value:?} has a target but no ballots to weight"));
+            }
+
+            continue;
+        }
+
+        group.weight = group.target * total / group.ballots as f64;
+    }
+
+    let weights = membership.into_iter().map(|g| groups[g].weight).collect();
+
+    Ok((groups, weights))
+}
+
+/// How the weights worked out across every ballot.
+pub struct Summary {
+    pub total: f64,
+    pub smallest: f64,
+    pub median: f64,
+    pub largest: f64,
+}
+
+/// Summarizes the weights, unless there aren't any.
+pub fn summarize(weights: &[f64]) -> Option<Summary> {
+    let mut sorted = weights.to_vec();
+    sorted.sort_by(f64::total_cmp);
+
+    let middle = sorted.len() / 2;
+    let median = match sorted.len() {
+        0 => return None,
+        len if len % 2 == 0 => (sorted[middle - 1] + sorted[middle]) / 2.0,
+        _ => sorted[middle],
+    };
+
+    Some(Summary {
+        total: sorted.iter().sum(),
+        smallest: sorted[0],
+        median,
+        largest: sorted[sorted.len() - 1],
+    })
+}
+
+/// Caps every weight at `max`, so that a few respondents in a small stratum
+/// can't swing the count. Returns each record (indexed at 0) that was capped,
+/// with its weight before. The other weights aren't scaled up to make up for
+/// it, so the total weight drops by however much was cut off.
+pub fn cap(weights: &mut [f64], max: f64) -> Vec<(usize, f64)> {
+    let mut capped = vec![];
+
+    for (i, weight) in weights.iter_mut().enumerate().filter(|(_, weight)| **weight > max) {
+        capped.push((i, *weight));
+        *weight = max;
+    }
+
+    capped
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn weights_to_targets() {
+        let strata: Vec<_> = ["a", "a", "a", "b"].into_iter().map(String::from).collect();
+        let targets = vec![(String::from("a"), 0.5), (String::from("b"), 0.5)];
+
+        let (groups, weights) = super::weights(&strata, &targets).expect("strata have targets");
+
+        assert_eq!(groups[0].ballots, 3);
+        assert_eq!(weights, vec![2.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0, 2.0]);
+        assert_eq!(weights.iter().sum::<f64>(), 4.0);
+    }
+
+    #[test]
+    fn capped_weights() {
+        let mut weights = vec![0.5, 3.0, 0.5, 1.0];
+
+        assert_eq!(super::cap(&mut weights, 2.0), vec![(1, 3.0)]);
+
+        let summary = super::summarize(&weights).expect("there are weights");
+
+        assert_eq!((summary.total, summary.median, summary.largest), (4.0, 0.75, 2.0));
+    }
+}